@@ -0,0 +1,426 @@
+//! 关键帧动画
+//!
+//! 动画由若干通道组成，每个通道用一条关键帧轨道驱动一个节点的平移、旋转、缩放或形变权重，
+//! 和 gltf 的动画模型一致，时间的单位为秒
+
+use crate::scene::Node;
+use nalgebra::{Quaternion, Unit, UnitQuaternion, Vector3};
+use std::fmt::Debug;
+
+/// 关键帧之间的插值方式，和 gltf 动画采样器的插值方式对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// 保持前一个关键帧的值不变
+    Step,
+    /// 线性插值，旋转使用球面线性插值
+    #[default]
+    Linear,
+    /// 使用关键帧的入切线和出切线做三次 Hermite 样条插值
+    Cubic,
+}
+
+/// 可以用关键帧驱动的值
+pub trait Animatable: Copy + PartialEq + Debug {
+    /// 三次插值使用的切线类型
+    type Tangent: Copy + PartialEq + Debug;
+
+    fn zero_tangent() -> Self::Tangent;
+
+    /// 从 self 到 other 的线性插值，t 为 [0, 1]
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+
+    /// 从 a 到 b 的三次 Hermite 插值，切线为每秒的变化量，dt 为两个关键帧的时间间隔
+    fn hermite(
+        a: &Self,
+        out_tangent: &Self::Tangent,
+        b: &Self,
+        in_tangent: &Self::Tangent,
+        t: f32,
+        dt: f32,
+    ) -> Self;
+}
+
+/// 三次 Hermite 基函数，依次为起点、起点切线、终点、终点切线的权重
+fn hermite_basis(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        2.0 * t3 - 3.0 * t2 + 1.0,
+        t3 - 2.0 * t2 + t,
+        -2.0 * t3 + 3.0 * t2,
+        t3 - t2,
+    ]
+}
+
+impl Animatable for f32 {
+    type Tangent = f32;
+
+    fn zero_tangent() -> Self::Tangent {
+        0.0
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn hermite(
+        a: &Self,
+        out_tangent: &Self::Tangent,
+        b: &Self,
+        in_tangent: &Self::Tangent,
+        t: f32,
+        dt: f32,
+    ) -> Self {
+        let [h0, h1, h2, h3] = hermite_basis(t);
+        a * h0 + out_tangent * h1 * dt + b * h2 + in_tangent * h3 * dt
+    }
+}
+
+impl Animatable for Vector3<f32> {
+    type Tangent = Vector3<f32>;
+
+    fn zero_tangent() -> Self::Tangent {
+        Vector3::zeros()
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn hermite(
+        a: &Self,
+        out_tangent: &Self::Tangent,
+        b: &Self,
+        in_tangent: &Self::Tangent,
+        t: f32,
+        dt: f32,
+    ) -> Self {
+        let [h0, h1, h2, h3] = hermite_basis(t);
+        a * h0 + out_tangent * (h1 * dt) + b * h2 + in_tangent * (h3 * dt)
+    }
+}
+
+impl Animatable for UnitQuaternion<f32> {
+    type Tangent = Quaternion<f32>;
+
+    fn zero_tangent() -> Self::Tangent {
+        Quaternion::new(0.0, 0.0, 0.0, 0.0)
+    }
+
+    /// 沿最短路径的球面线性插值
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+
+    /// 和 gltf 一致，对四元数的分量插值之后再归一化
+    fn hermite(
+        a: &Self,
+        out_tangent: &Self::Tangent,
+        b: &Self,
+        in_tangent: &Self::Tangent,
+        t: f32,
+        dt: f32,
+    ) -> Self {
+        let [h0, h1, h2, h3] = hermite_basis(t);
+        let q = a.quaternion() * h0
+            + out_tangent * (h1 * dt)
+            + b.quaternion() * h2
+            + in_tangent * (h3 * dt);
+        UnitQuaternion::try_new(q, f32::EPSILON).unwrap_or(if t < 0.5 { *a } else { *b })
+    }
+}
+
+/// 关键帧，切线只在三次插值时使用
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T: Animatable> {
+    pub time: f32,
+    pub value: T,
+    /// 进入这个关键帧的切线
+    pub in_tangent: T::Tangent,
+    /// 离开这个关键帧的切线
+    pub out_tangent: T::Tangent,
+}
+
+impl<T: Animatable> Keyframe<T> {
+    /// 切线为 0 的关键帧，三次插值时在关键帧处速度为 0
+    pub fn new(time: f32, value: T) -> Self {
+        Keyframe {
+            time,
+            value,
+            in_tangent: T::zero_tangent(),
+            out_tangent: T::zero_tangent(),
+        }
+    }
+}
+
+/// 关键帧轨道
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T: Animatable> {
+    pub interpolation: Interpolation,
+    /// 按时间从小到大排列的关键帧
+    pub keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Track<T> {
+    /// 创建轨道，关键帧会按时间排序
+    pub fn new(interpolation: Interpolation, mut keyframes: Vec<Keyframe<T>>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Track {
+            interpolation,
+            keyframes,
+        }
+    }
+
+    /// 最后一个关键帧的时间，没有关键帧时为 0
+    pub fn end_time(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// 计算 time 时刻的值，在第一个关键帧之前和最后一个关键帧之后保持端点的值
+    /// 没有关键帧时返回 None
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let keyframes = &self.keyframes;
+        let next = keyframes.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return keyframes.first().map(|k| k.value);
+        }
+        let a = &keyframes[next - 1];
+        let Some(b) = keyframes.get(next) else {
+            return Some(a.value);
+        };
+        let dt = b.time - a.time;
+        let t = if dt > 0.0 { (time - a.time) / dt } else { 0.0 };
+        Some(match self.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => a.value.lerp(&b.value, t),
+            Interpolation::Cubic => {
+                T::hermite(&a.value, &a.out_tangent, &b.value, &b.in_tangent, t, dt)
+            }
+        })
+    }
+}
+
+/// 通道驱动的节点属性
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Translation(Track<Vector3<f32>>),
+    Rotation(Track<UnitQuaternion<f32>>),
+    Scale(Track<Vector3<f32>>),
+    /// 节点上第几个形变目标的权重
+    MorphWeight(usize, Track<f32>),
+}
+
+/// 动画通道，驱动一个节点的一个属性
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    /// 节点在场景 nodes 中的索引
+    pub node: usize,
+    pub target: Target,
+}
+
+impl Channel {
+    pub fn end_time(&self) -> f32 {
+        match &self.target {
+            Target::Translation(track) | Target::Scale(track) => track.end_time(),
+            Target::Rotation(track) => track.end_time(),
+            Target::MorphWeight(_, track) => track.end_time(),
+        }
+    }
+}
+
+/// 一段动画
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Animation {
+    pub name: String,
+    pub channels: Vec<Channel>,
+}
+
+impl Animation {
+    pub fn new(name: &str) -> Self {
+        Animation {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 动画的时长，即所有通道中最后一个关键帧的时间
+    pub fn duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .map(Channel::end_time)
+            .fold(0.0, f32::max)
+    }
+
+    /// 把 time 时刻的值写入节点，没有被通道驱动的属性保持不变，越界的节点索引被忽略
+    pub fn apply(&self, nodes: &mut [Node], time: f32) {
+        for channel in &self.channels {
+            let Some(node) = nodes.get_mut(channel.node) else {
+                continue;
+            };
+            match &channel.target {
+                Target::Translation(track) => {
+                    if let Some(v) = track.sample(time) {
+                        node.translation = v;
+                    }
+                }
+                Target::Rotation(track) => {
+                    if let Some(q) = track.sample(time) {
+                        node.rotation = q;
+                    }
+                }
+                Target::Scale(track) => {
+                    if let Some(v) = track.sample(time) {
+                        node.scale = v;
+                    }
+                }
+                Target::MorphWeight(index, track) => {
+                    if let Some(w) = track.sample(time) {
+                        if node.morph_weights.len() <= *index {
+                            node.morph_weights.resize(index + 1, 0.0);
+                        }
+                        node.morph_weights[*index] = w;
+                    }
+                }
+            }
+        }
+    }
+
+    /// 在 duration 秒内绕 axis 匀速旋转一周的转台动画，从 start 的朝向开始
+    pub fn turntable(
+        node: usize,
+        axis: &Unit<Vector3<f32>>,
+        duration: f32,
+        start: UnitQuaternion<f32>,
+    ) -> Self {
+        // 每段旋转 90 度，避免相差 180 度时球面插值的方向不确定
+        let keyframes = (0..=4)
+            .map(|i| {
+                let turn = i as f32 / 4.0;
+                let rotation = UnitQuaternion::from_axis_angle(axis, turn * std::f32::consts::TAU);
+                Keyframe::new(turn * duration, rotation * start)
+            })
+            .collect();
+        Animation {
+            name: "turntable".to_string(),
+            channels: vec![Channel {
+                node,
+                target: Target::Rotation(Track::new(Interpolation::Linear, keyframes)),
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn track(interpolation: Interpolation) -> Track<Vector3<f32>> {
+        Track::new(
+            interpolation,
+            vec![
+                Keyframe::new(2.0, Vector3::new(4.0, 0.0, 0.0)),
+                Keyframe::new(0.0, Vector3::zeros()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_linear_and_step() {
+        let linear = track(Interpolation::Linear);
+        assert_eq!(linear.keyframes[0].time, 0.0);
+        assert_eq!(linear.end_time(), 2.0);
+        assert_eq!(linear.sample(0.5), Some(Vector3::new(1.0, 0.0, 0.0)));
+        // 范围之外保持端点的值
+        assert_eq!(linear.sample(-1.0), Some(Vector3::zeros()));
+        assert_eq!(linear.sample(3.0), Some(Vector3::new(4.0, 0.0, 0.0)));
+
+        let step = track(Interpolation::Step);
+        assert_eq!(step.sample(1.9), Some(Vector3::zeros()));
+        assert_eq!(step.sample(2.0), Some(Vector3::new(4.0, 0.0, 0.0)));
+
+        let empty: Track<Vector3<f32>> = Track::new(Interpolation::Linear, Vec::new());
+        assert_eq!(empty.sample(0.0), None);
+    }
+
+    #[test]
+    fn test_cubic() {
+        // 切线为 0 时在两端缓入缓出，中点和线性插值相同
+        let mut cubic = track(Interpolation::Cubic);
+        assert!((cubic.sample(1.0).unwrap().x - 2.0).abs() < 1e-6);
+        assert!(cubic.sample(0.2).unwrap().x < 0.4);
+
+        // 切线和线性插值的斜率一致时结果也是线性的
+        for k in cubic.keyframes.iter_mut() {
+            k.in_tangent = Vector3::new(2.0, 0.0, 0.0);
+            k.out_tangent = Vector3::new(2.0, 0.0, 0.0);
+        }
+        for time in [0.2, 0.7, 1.5] {
+            assert!((cubic.sample(time).unwrap().x - 2.0 * time).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        let quarter = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), FRAC_PI_2);
+        let track = Track::new(
+            Interpolation::Linear,
+            vec![
+                Keyframe::new(0.0, UnitQuaternion::identity()),
+                Keyframe::new(1.0, quarter),
+            ],
+        );
+        let half = track.sample(0.5).unwrap();
+        assert!((half.angle() - FRAC_PI_2 / 2.0).abs() < 1e-5);
+
+        // 分量插值之后归一化
+        let cubic = Track {
+            interpolation: Interpolation::Cubic,
+            ..track
+        };
+        let q = cubic.sample(0.5).unwrap();
+        assert!((q.quaternion().norm() - 1.0).abs() < 1e-6);
+        assert!((q.angle() - FRAC_PI_2 / 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_apply_and_turntable() {
+        let mut nodes = vec![Node::new("model"), Node::new("other")];
+        nodes[0].scale = Vector3::repeat(2.0);
+        let mut animation = Animation::turntable(0, &Vector3::y_axis(), 4.0, nodes[0].rotation);
+        animation.channels.push(Channel {
+            node: 5,
+            target: Target::Translation(track(Interpolation::Linear)),
+        });
+        assert_eq!(animation.duration(), 4.0);
+
+        for (time, angle) in [(1.0, 90.0f32), (3.0, 270.0)] {
+            animation.apply(&mut nodes, time);
+            let expected = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle.to_radians());
+            assert!(nodes[0].rotation.angle_to(&expected) < 1e-5);
+        }
+        // 匀速旋转
+        animation.apply(&mut nodes, 0.5);
+        assert!((nodes[0].rotation.angle() - 45f32.to_radians()).abs() < 1e-5);
+        // 没有被驱动的属性和节点保持不变
+        assert_eq!(nodes[0].scale, Vector3::repeat(2.0));
+        assert_eq!(nodes[1], Node::new("other"));
+
+        // 形变权重的通道，节点上缺少的权重补 0
+        let blink = Animation {
+            name: "blink".to_string(),
+            channels: vec![Channel {
+                node: 1,
+                target: Target::MorphWeight(
+                    2,
+                    Track::new(
+                        Interpolation::Linear,
+                        vec![Keyframe::new(0.0, 0.0), Keyframe::new(1.0, 1.0)],
+                    ),
+                ),
+            }],
+        };
+        blink.apply(&mut nodes, 0.25);
+        assert_eq!(nodes[1].morph_weights, vec![0.0, 0.0, 0.25]);
+    }
+}
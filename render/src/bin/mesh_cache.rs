@@ -0,0 +1,58 @@
+//! 把模型文件转换为 .rmesh 缓存
+//!
+//! 用法：mesh_cache <输入文件> [输出文件]，不指定输出文件时写到输入文件旁边的 <输入文件>.rmesh
+
+use render::io::{self, cache};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (input, output) = match &args[..] {
+        [input] => (PathBuf::from(input), cache::cache_path(input)),
+        [input, output] => (PathBuf::from(input), PathBuf::from(output)),
+        _ => {
+            eprintln!("usage: mesh_cache <input> [output]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start = Instant::now();
+    let mesh = match io::load_mesh(&input) {
+        Ok(mesh) => mesh,
+        Err(e) => {
+            eprintln!("failed to load {:?}: {}", input, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let parse_time = start.elapsed();
+    if let Err(e) = cache::save(&mesh, &output) {
+        eprintln!("failed to write {:?}: {}", output, e);
+        return ExitCode::FAILURE;
+    }
+
+    // 重新读取一遍，确认缓存和原始网格完全一致
+    let start = Instant::now();
+    match cache::load(&output) {
+        Ok(cached) if cached == mesh => {}
+        Ok(_) => {
+            eprintln!("{:?} does not match the source mesh", output);
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("failed to read back {:?}: {}", output, e);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!(
+        "{:?} -> {:?}: {} vertices, {} faces, parsed in {:?}, cache loaded in {:?}",
+        input,
+        output,
+        mesh.vertex_count(),
+        mesh.face_count(),
+        parse_time,
+        start.elapsed()
+    );
+    ExitCode::SUCCESS
+}
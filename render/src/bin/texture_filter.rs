@@ -0,0 +1,125 @@
+//! 对比双线性、三线性（mipmap）和各向异性过滤
+//!
+//! 用法：texture_filter <输出图片> [纹理文件] [--kaiser]
+//! 渲染一块向远处延伸的地面，从左到右依次使用双线性、三线性和 16x 各向异性过滤；
+//! 没有指定纹理时使用黑白棋盘格，远处的双线性结果会出现明显的摩尔纹，
+//! 三线性过滤消除了摩尔纹但远处变得模糊，各向异性过滤在远处仍然保持清晰
+
+use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+use nalgebra::{Matrix4, Point3, Vector2, Vector3, Vector4};
+use render::camera::{Camera, Pipeline};
+use render::light::{Light, WorldLight};
+use render::mesh::Mesh;
+use render::raster::{Cull, Framebuffer};
+use render::shader::DiffuseShader;
+use render::texture::{Filter, MipFilter, Texture};
+use std::process::ExitCode;
+
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 400;
+/// 地面上纹理重复的次数
+const REPEAT: f32 = 40.0;
+const MAX_ANISOTROPY: f32 = 16.0;
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let kaiser = args.iter().any(|a| a == "--kaiser");
+    args.retain(|a| a != "--kaiser");
+    let (output, texture) = match &args[..] {
+        [output] => (output, Ok(checker())),
+        [output, path] => (output, Texture::load(path)),
+        _ => {
+            eprintln!("usage: texture_filter <output image> [texture] [--kaiser]");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut texture = match texture {
+        Ok(texture) => texture,
+        Err(e) => {
+            eprintln!("failed to load texture: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let filter = if kaiser {
+        MipFilter::Kaiser
+    } else {
+        MipFilter::Box
+    };
+    texture.generate_mipmaps(filter, true);
+
+    let mesh = floor();
+    let camera = Camera::perspective(
+        Point3::new(0.0, 1.0, 0.0),
+        Point3::new(0.0, 0.7, -1.0),
+        Vector3::y(),
+        60f32.to_radians(),
+        0.1,
+        200.0,
+    );
+    let lights = [WorldLight {
+        light: Light::directional(Vector3::repeat(1.0), 1.0),
+        position: Point3::origin(),
+        direction: -Vector3::y(),
+    }];
+    let modes = [
+        (Filter::Bilinear, 1.0),
+        (Filter::Trilinear, 1.0),
+        (Filter::Trilinear, MAX_ANISOTROPY),
+    ];
+    let mut image = RgbaImage::new(WIDTH * modes.len() as u32, HEIGHT);
+    for (i, (filter, anisotropy)) in modes.into_iter().enumerate() {
+        texture.filter = filter;
+        texture.max_anisotropy = anisotropy;
+        let shader = DiffuseShader {
+            mesh: &mesh,
+            pipeline: Pipeline::new(Matrix4::identity(), &camera, WIDTH, HEIGHT),
+            joints: &[],
+            lights: &lights,
+            eye: camera.eye,
+            base_color: Vector4::repeat(1.0),
+            texture: Some(&texture),
+            normal_map: None,
+        };
+        let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+        framebuffer.clear(Rgba([40, 40, 48, 255]));
+        framebuffer.draw_mesh(&shader, &mesh, Cull::Back);
+        image
+            .copy_from(&framebuffer.color, i as u32 * WIDTH, 0)
+            .expect("framebuffer fits in the output image");
+    }
+    match image.save(output) {
+        Ok(()) => {
+            println!(
+                "wrote {:?} (bilinear, trilinear, {}x anisotropic)",
+                output, MAX_ANISOTROPY
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("failed to write {:?}: {}", output, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 8x8 格子的黑白棋盘格
+fn checker() -> Texture {
+    let image = RgbaImage::from_fn(256, 256, |x, y| {
+        let v = if (x / 32 + y / 32) % 2 == 0 { 230 } else { 20 };
+        Rgba([v, v, v, 255])
+    });
+    Texture::new(&DynamicImage::ImageRgba8(image))
+}
+
+/// y = 0 上 200x200 的正方形，纹理重复 REPEAT 次
+fn floor() -> Mesh {
+    let mut mesh = Mesh::new("floor");
+    for (x, z) in [(-1.0, 1.0), (1.0, 1.0), (1.0, -1.0), (-1.0, -1.0)] {
+        mesh.positions.push(Point3::new(x * 100.0, 0.0, z * 100.0));
+        mesh.normals.push(Vector3::y());
+        mesh.uvs
+            .push(Vector2::new(x + 1.0, 1.0 - z) * (REPEAT / 2.0));
+    }
+    mesh.faces = vec![vec![0, 1, 2, 3]];
+    mesh
+}
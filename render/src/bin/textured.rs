@@ -0,0 +1,129 @@
+//! 用漫反射贴图和法线贴图渲染模型，Blinn-Phong 光照，一个主光源和一个补光
+//!
+//! 用法：textured <模型文件> <输出图片> [--diffuse <贴图>] [--normal <贴图>] [--directx | --object]
+//! 法线贴图默认是 OpenGL 约定的切线空间贴图，--directx 表示绿色通道相反，--object 表示模型空间贴图；
+//! obj 的纹理坐标原点在左下角，读取 obj 时贴图会上下翻转。例如：
+//! `textured african_head.obj head.png --diffuse african_head_diffuse.tga --normal african_head_nm_tangent.tga`
+
+use image::Rgba;
+use nalgebra::{Point3, Vector3};
+use render::camera::{Camera, Pipeline};
+use render::io::{self, cache};
+use render::light::{BlinnPhong, Light, WorldLight};
+use render::normal_map::{NormalMap, NormalSpace};
+use render::raster::{Cull, Framebuffer};
+use render::shader::BlinnPhongShader;
+use render::texture::{Filter, MipFilter, Texture};
+use std::path::Path;
+use std::process::ExitCode;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 800;
+const USAGE: &str = "usage: textured <mesh> <output image> [--diffuse <image>] [--normal <image>] [--directx | --object]";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let (mut diffuse, mut normal) = (None, None);
+    let mut space = NormalSpace::TangentOpenGl;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--diffuse" => diffuse = args.next(),
+            "--normal" => normal = args.next(),
+            "--directx" => space = NormalSpace::TangentDirectX,
+            "--object" => space = NormalSpace::Object,
+            _ => paths.push(arg),
+        }
+    }
+    let [path, output] = &paths[..] else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let mut mesh = match cache::load_cached(path, |p| io::load_mesh(p)) {
+        Ok(mesh) => mesh,
+        Err(e) => {
+            eprintln!("failed to load {:?}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let flip_v = Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("obj"));
+    let load = |path: &String, srgb: bool| {
+        let mut texture =
+            Texture::load(path).map_err(|e| eprintln!("failed to load {:?}: {}", path, e))?;
+        texture.flip_v = flip_v;
+        texture.filter = Filter::Trilinear;
+        texture.max_anisotropy = 8.0;
+        texture.generate_mipmaps(MipFilter::Box, srgb);
+        Ok::<_, ()>(texture)
+    };
+    let Ok(texture) = diffuse.as_ref().map(|p| load(p, true)).transpose() else {
+        return ExitCode::FAILURE;
+    };
+    let Ok(normal_map) = normal.as_ref().map(|p| load(p, false)).transpose() else {
+        return ExitCode::FAILURE;
+    };
+    let normal_map = normal_map.map(|texture| NormalMap::new(texture, space));
+    mesh.ensure_normals(None);
+    if space != NormalSpace::Object
+        && normal_map.is_some()
+        && mesh.tangents.is_empty()
+        && !mesh.compute_tangents()
+    {
+        eprintln!("warning: mesh has no uvs, the normal map is ignored");
+    }
+
+    let center = mesh.bounding_sphere().center;
+    mesh.translate(&-center.coords);
+    let fov_y = 45f32.to_radians();
+    let framing = mesh.frame(
+        &Vector3::new(0.0, 0.0, 1.0),
+        fov_y,
+        WIDTH as f32 / HEIGHT as f32,
+    );
+    let camera = Camera::from_framing(&framing, fov_y);
+    let radius = mesh.bounding_sphere().radius;
+    let lights = [
+        WorldLight {
+            light: Light::directional(Vector3::repeat(1.0), 0.9),
+            position: Point3::origin(),
+            direction: Vector3::new(-1.0, -1.0, -2.0).normalize(),
+        },
+        WorldLight {
+            light: Light::point(Vector3::new(0.6, 0.7, 1.0), 2.0 * radius * radius, None),
+            position: Point3::new(-2.0, 0.5, 1.0) * radius,
+            direction: -Vector3::z(),
+        },
+    ];
+    let shader = BlinnPhongShader {
+        mesh: &mesh,
+        pipeline: Pipeline::new(nalgebra::Matrix4::identity(), &camera, WIDTH, HEIGHT),
+        joints: &[],
+        lights: &lights,
+        eye: camera.eye,
+        material: BlinnPhong {
+            ambient: Vector3::repeat(1.0),
+            diffuse: Vector3::repeat(1.0),
+            specular: Vector3::repeat(0.3),
+            shininess: 32.0,
+        },
+        ambient: Vector3::repeat(0.1),
+        double_sided: false,
+        texture: texture.as_ref(),
+        normal_map: normal_map.as_ref(),
+    };
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    framebuffer.clear(Rgba([40, 40, 48, 255]));
+    framebuffer.draw_mesh(&shader, &mesh, Cull::Back);
+    match framebuffer.color.save(output) {
+        Ok(()) => {
+            println!("wrote {:?}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("failed to write {:?}: {}", output, e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! 渲染模型绕竖直轴旋转一周的图片序列
+//!
+//! 用法：turntable <模型文件> <输出目录> [帧数] [--tga]，默认 120 帧 PNG，30 帧每秒
+//! 输出的 frame_0000.png ... 可以用 `ffmpeg -framerate 30 -i frame_%04d.png turntable.mp4` 合成视频
+
+use nalgebra::{UnitQuaternion, Vector3};
+use render::animation::Animation;
+use render::camera::Camera;
+use render::io::{self, cache};
+use render::scene::{Node, Scene};
+use render::sequence::{FrameFormat, Sequence};
+use std::process::ExitCode;
+use std::time::Instant;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let tga = args.iter().any(|a| a == "--tga");
+    args.retain(|a| a != "--tga");
+    let (path, directory, frames) = match &args[..] {
+        [path, directory] => (path, directory, Some(120)),
+        [path, directory, frames] => (path, directory, frames.parse().ok()),
+        _ => (&String::new(), &String::new(), None),
+    };
+    let Some(frames) = frames.filter(|&f| f > 0) else {
+        eprintln!("usage: turntable <mesh> <output directory> [frames] [--tga]");
+        return ExitCode::FAILURE;
+    };
+    let mut mesh = match cache::load_cached(path, |p| io::load_mesh(p)) {
+        Ok(mesh) => mesh,
+        Err(e) => {
+            eprintln!("failed to load {:?}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // 把模型移动到原点，绕自身的中心旋转
+    let center = mesh.bounding_sphere().center;
+    mesh.translate(&-center.coords);
+    let fov_y = 45f32.to_radians();
+    let framing = mesh.frame(
+        &Vector3::new(0.0, 0.0, 1.0),
+        fov_y,
+        WIDTH as f32 / HEIGHT as f32,
+    );
+    let camera = Camera::from_framing(&framing, fov_y);
+
+    let mut sequence = Sequence::new(directory, frames, WIDTH, HEIGHT);
+    if tga {
+        sequence.format = FrameFormat::Tga;
+    }
+    let mut scene = Scene::default();
+    scene.meshes.push(mesh);
+    let node = scene.add_node(
+        Node {
+            mesh: Some(0),
+            ..Node::new("model")
+        },
+        None,
+    );
+    scene.animations.push(Animation::turntable(
+        node,
+        &Vector3::y_axis(),
+        sequence.duration(),
+        UnitQuaternion::identity(),
+    ));
+
+    let start = Instant::now();
+    match sequence.render_scene(&mut scene, &camera) {
+        Ok(paths) => {
+            println!(
+                "wrote {} frames to {:?} in {:?}",
+                paths.len(),
+                sequence.directory,
+                start.elapsed()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("failed to write frames: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! 交互式查看模型
+//!
+//! 用法：viewer <模型文件> [形变目标文件...]，支持 obj、ply、stl、gltf、glb 和 rmesh，第一次打开时会在旁边写入 .rmesh 缓存
+//! 形变目标文件是和模型顶点一一对应的其他形状（比如同一个头部模型的不同表情）
+//! 有顶点颜色（比如 ply 扫描数据）时按顶点颜色着色；只绘制面，没有面的点云不会显示
+//!
+//! - 环绕模式：左键拖动旋转，右键或中键拖动平移，滚轮缩放
+//! - 飞行模式：WASD 移动，E/Q 上升下降，Shift 加速，拖动转动视角，滚轮调整速度
+//! - Tab 切换模式，F 重新取景，Escape 退出
+//! - 数字键 1~9 选择形变目标，上下方向键调整选中目标的权重
+
+use image::Rgba;
+use minifb::Key;
+use nalgebra::Vector3;
+use render::camera::Camera;
+use render::controller::{CameraController, FlyController, OrbitController};
+use render::display::display_interactive;
+use render::io::{self, cache};
+use render::mesh::Mesh;
+use render::raster::Framebuffer;
+use render::scene::{Node, Scene};
+use std::process::ExitCode;
+
+const WIDTH: usize = 800;
+const HEIGHT: usize = 600;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((path, targets)) = args.split_first() else {
+        eprintln!("usage: viewer <mesh> [morph target meshes...]");
+        return ExitCode::FAILURE;
+    };
+    let load = |path: &String| {
+        cache::load_cached(path, |p| io::load_mesh(p))
+            .map_err(|e| eprintln!("failed to load {:?}: {}", path, e))
+    };
+    let Ok(mut mesh) = load(path) else {
+        return ExitCode::FAILURE;
+    };
+    for target in targets {
+        let Ok(shape) = load(target) else {
+            return ExitCode::FAILURE;
+        };
+        if !mesh.add_morph_target(target, &shape) {
+            eprintln!(
+                "{:?} has {} vertices, expected {}",
+                target,
+                shape.vertex_count(),
+                mesh.vertex_count()
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let fov_y = 45f32.to_radians();
+    let frame = |mesh: &Mesh| {
+        let framing = mesh.frame(
+            &Vector3::new(0.0, 0.0, 1.0),
+            fov_y,
+            WIDTH as f32 / HEIGHT as f32,
+        );
+        let mut camera = Camera::from_framing(&framing, fov_y);
+        // 留出移动的余量，近平面和远平面不再紧贴包围球
+        camera.near *= 0.1;
+        camera.far *= 10.0;
+        camera
+    };
+    let mut camera = frame(&mesh);
+    let mut orbit = OrbitController::default();
+    let mut fly = FlyController {
+        speed: mesh.bounding_sphere().radius,
+        ..Default::default()
+    };
+    let mut flying = false;
+
+    let mut scene = Scene::default();
+    let node = scene.add_node(
+        Node {
+            mesh: Some(0),
+            morph_weights: vec![0.0; mesh.targets.len()],
+            ..Node::new("model")
+        },
+        None,
+    );
+    scene.meshes.push(mesh);
+    let mut selected = 0;
+    let mut framebuffer = Framebuffer::new(WIDTH as u32, HEIGHT as u32);
+
+    display_interactive("Viewer", WIDTH, HEIGHT, |input, dt, image| {
+        if input.is_key_pressed(Key::Tab) {
+            flying = !flying;
+        }
+        if input.is_key_pressed(Key::F) {
+            camera = frame(&scene.meshes[0]);
+        }
+        let digits = [
+            Key::Key1,
+            Key::Key2,
+            Key::Key3,
+            Key::Key4,
+            Key::Key5,
+            Key::Key6,
+            Key::Key7,
+            Key::Key8,
+            Key::Key9,
+        ];
+        let weights = &mut scene.nodes[node].morph_weights;
+        if let Some(i) = digits.iter().position(|&k| input.is_key_pressed(k)) {
+            if i < weights.len() {
+                selected = i;
+            }
+        }
+        if let Some(weight) = weights.get_mut(selected) {
+            // 一秒从 0 变到 1
+            if input.is_key_down(Key::Up) {
+                *weight = (*weight + dt).min(1.0);
+            }
+            if input.is_key_down(Key::Down) {
+                *weight = (*weight - dt).max(0.0);
+            }
+        }
+        if flying {
+            fly.update(input, dt, &mut camera);
+        } else {
+            orbit.update(input, dt, &mut camera);
+        }
+        framebuffer.clear(Rgba([40, 40, 48, 255]));
+        // 场景中没有光源，使用跟随相机的头灯
+        scene.render(&camera, &mut framebuffer);
+        image.copy_from_slice(&framebuffer.color);
+        true
+    });
+    ExitCode::SUCCESS
+}
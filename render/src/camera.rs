@@ -0,0 +1,252 @@
+use crate::math;
+use crate::mesh::bounds::Framing;
+use crate::mesh::Mesh;
+use nalgebra::{Matrix3, Matrix4, Point3, Vector3, Vector4};
+
+/// 相机的投影方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// 透视投影，fov_y 为垂直方向的视角（弧度）
+    Perspective { fov_y: f32 },
+    /// 正交投影，height 为视野的高度，宽度由宽高比决定
+    Orthographic { height: f32 },
+}
+
+/// 相机
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub eye: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    pub projection: Projection,
+    /// 到近平面和远平面的距离
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            eye: Point3::new(0.0, 0.0, 3.0),
+            target: Point3::origin(),
+            up: Vector3::y(),
+            projection: Projection::Perspective {
+                fov_y: 45f32.to_radians(),
+            },
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl Camera {
+    /// 透视相机
+    pub fn perspective(
+        eye: Point3<f32>,
+        target: Point3<f32>,
+        up: Vector3<f32>,
+        fov_y: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        Camera {
+            eye,
+            target,
+            up,
+            projection: Projection::Perspective { fov_y },
+            near,
+            far,
+        }
+    }
+
+    /// 正交相机
+    pub fn orthographic(
+        eye: Point3<f32>,
+        target: Point3<f32>,
+        up: Vector3<f32>,
+        height: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        Camera {
+            eye,
+            target,
+            up,
+            projection: Projection::Orthographic { height },
+            near,
+            far,
+        }
+    }
+
+    /// 使用 Mesh::frame 的取景结果创建透视相机，fov_y 应该和取景时使用的视角相同
+    pub fn from_framing(framing: &Framing, fov_y: f32) -> Self {
+        Camera::perspective(
+            framing.eye,
+            framing.target,
+            framing.up,
+            fov_y,
+            framing.near,
+            framing.far,
+        )
+    }
+
+    /// 视线方向（单位向量）
+    pub fn forward(&self) -> Vector3<f32> {
+        (self.target - self.eye).normalize()
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        math::look_at(&self.eye, &self.target, &self.up)
+    }
+
+    /// 投影矩阵，aspect 为宽高比
+    pub fn projection_matrix(&self, aspect: f32) -> Matrix4<f32> {
+        match self.projection {
+            Projection::Perspective { fov_y } => {
+                math::perspective(fov_y, aspect, self.near, self.far)
+            }
+            Projection::Orthographic { height } => {
+                let (half_w, half_h) = (height * aspect * 0.5, height * 0.5);
+                math::orthographic(-half_w, half_w, -half_h, half_h, self.near, self.far)
+            }
+        }
+    }
+}
+
+/// 把模型空间的顶点依次经过模型、视图、投影、透视除法和视口变换映射到屏幕
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub model: Matrix4<f32>,
+    pub view: Matrix4<f32>,
+    pub projection: Matrix4<f32>,
+    pub viewport: Matrix4<f32>,
+    /// projection * view * model
+    pub mvp: Matrix4<f32>,
+    /// 把模型空间的法向量变换到世界空间
+    pub normal: Matrix3<f32>,
+}
+
+impl Pipeline {
+    /// width、height 为屏幕的像素尺寸，宽高比由它们决定
+    pub fn new(model: Matrix4<f32>, camera: &Camera, width: u32, height: u32) -> Self {
+        let (width, height) = (width as f32, height as f32);
+        let view = camera.view_matrix();
+        let projection = camera.projection_matrix(width / height.max(1.0));
+        Pipeline {
+            model,
+            view,
+            projection,
+            viewport: math::viewport(width, height),
+            mvp: projection * view * model,
+            normal: math::normal_matrix(&model),
+        }
+    }
+
+    /// 模型空间的点变换到齐次裁剪空间
+    pub fn clip(&self, p: &Point3<f32>) -> Vector4<f32> {
+        self.mvp * p.to_homogeneous()
+    }
+
+    /// 模型空间的点变换到屏幕，x、y 为像素坐标（原点在左上角），z 为 [0, 1] 的深度
+    /// 点在相机平面上或者后面时返回 None，不检查点是否在视锥内
+    pub fn to_screen(&self, p: &Point3<f32>) -> Option<Point3<f32>> {
+        let ndc = math::perspective_divide(&self.clip(p))?;
+        Some(self.viewport.transform_point(&ndc))
+    }
+
+    /// 模型空间的法向量变换到世界空间并归一化
+    pub fn world_normal(&self, n: &Vector3<f32>) -> Vector3<f32> {
+        (self.normal * n)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros)
+    }
+
+    /// 网格所有顶点的屏幕坐标
+    pub fn project_mesh(&self, mesh: &Mesh) -> Vec<Option<Point3<f32>>> {
+        mesh.positions.iter().map(|p| self.to_screen(p)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::primitive;
+
+    #[test]
+    fn test_framed_mesh_on_screen() {
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mesh = crate::io::obj::load_mesh(path).unwrap();
+        let (width, height) = (800, 600);
+        let fov_y = 60f32.to_radians();
+        let framing = mesh.frame(
+            &Vector3::new(0.3, 0.2, 1.0),
+            fov_y,
+            width as f32 / height as f32,
+        );
+        let camera = Camera::from_framing(&framing, fov_y);
+        let pipeline = Pipeline::new(Matrix4::identity(), &camera, width, height);
+        for p in pipeline.project_mesh(&mesh) {
+            let p = p.unwrap();
+            assert!(p.x >= 0.0 && p.x <= width as f32);
+            assert!(p.y >= 0.0 && p.y <= height as f32);
+            assert!(p.z >= 0.0 && p.z <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_perspective_pipeline() {
+        let camera = Camera::default();
+        let model = Matrix4::new_translation(&Vector3::new(0.0, 0.0, -2.0));
+        let pipeline = Pipeline::new(model, &camera, 200, 100);
+
+        // 原点在视线上，落在屏幕中心
+        let center = pipeline.to_screen(&Point3::origin()).unwrap();
+        assert!((center.x - 100.0).abs() < 1e-3 && (center.y - 50.0).abs() < 1e-3);
+        // 世界空间 +y 在屏幕上向上，也就是像素 y 变小
+        let above = pipeline.to_screen(&Point3::new(0.0, 0.5, 0.0)).unwrap();
+        assert!(above.y < center.y);
+        // 近大远小：同样的偏移离相机越远在屏幕上越短
+        let near = pipeline.to_screen(&Point3::new(1.0, 0.0, 2.0)).unwrap();
+        let far = pipeline.to_screen(&Point3::new(1.0, 0.0, -2.0)).unwrap();
+        assert!(near.x - 100.0 > far.x - 100.0);
+        assert!(near.z < far.z);
+        // 相机后面
+        assert!(pipeline.to_screen(&Point3::new(0.0, 0.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn test_orthographic_pipeline() {
+        let camera = Camera::orthographic(
+            Point3::new(0.0, 0.0, 5.0),
+            Point3::origin(),
+            Vector3::y(),
+            2.0,
+            1.0,
+            10.0,
+        );
+        let pipeline = Pipeline::new(Matrix4::identity(), &camera, 400, 200);
+        // 正交投影时深度不影响屏幕位置
+        let a = pipeline.to_screen(&Point3::new(1.0, 0.5, 2.0)).unwrap();
+        let b = pipeline.to_screen(&Point3::new(1.0, 0.5, -3.0)).unwrap();
+        assert!((a.xy() - b.xy()).norm() < 1e-3);
+        assert!((a.x - 300.0).abs() < 1e-3 && (a.y - 50.0).abs() < 1e-3);
+        assert!(a.z < b.z);
+    }
+
+    #[test]
+    fn test_world_normal() {
+        let mesh = primitive::uv_sphere(1.0, 16, 8);
+        let model = Matrix4::new_nonuniform_scaling(&Vector3::new(3.0, 1.0, 1.0));
+        let camera = Camera::default();
+        let pipeline = Pipeline::new(model, &camera, 100, 100);
+        // 椭球面 (x/3)^2 + y^2 + z^2 = 1 的法向量和 (x/9, y, z) 同向
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            let world = model.transform_point(p);
+            let expected = Vector3::new(world.x / 9.0, world.y, world.z).normalize();
+            assert!((pipeline.world_normal(n) - expected).norm() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::camera::Camera;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+use nalgebra::{Rotation3, Unit, Vector3};
+
+/// 一帧的窗口输入
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Input {
+    /// 鼠标在窗口中的像素坐标，鼠标不在窗口内时为 None
+    pub cursor: Option<(f32, f32)>,
+    /// 鼠标和上一帧相比移动的像素数，任意一帧鼠标不在窗口内时为 0
+    pub cursor_delta: (f32, f32),
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+    /// 滚轮这一帧滚动的量，向上为正
+    pub scroll: f32,
+    /// 当前按住的键
+    pub keys: Vec<Key>,
+    /// 这一帧刚按下的键
+    pub pressed: Vec<Key>,
+}
+
+impl Input {
+    /// 读取窗口当前的输入，previous 为上一帧的输入，用来计算鼠标移动的距离
+    pub fn from_window(window: &Window, previous: &Input) -> Self {
+        let cursor = window.get_mouse_pos(MouseMode::Discard);
+        let cursor_delta = match (cursor, previous.cursor) {
+            (Some((x, y)), Some((px, py))) => (x - px, y - py),
+            _ => (0.0, 0.0),
+        };
+        Input {
+            cursor,
+            cursor_delta,
+            left: window.get_mouse_down(MouseButton::Left),
+            right: window.get_mouse_down(MouseButton::Right),
+            middle: window.get_mouse_down(MouseButton::Middle),
+            scroll: window.get_scroll_wheel().map_or(0.0, |(_, y)| y),
+            keys: window.get_keys(),
+            pressed: window.get_keys_pressed(KeyRepeat::No),
+        }
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        self.pressed.contains(&key)
+    }
+}
+
+/// 根据输入修改相机
+pub trait CameraController {
+    /// dt 为距离上一帧的秒数
+    fn update(&mut self, input: &Input, dt: f32, camera: &mut Camera);
+}
+
+/// 俯仰角的范围，避免视线和 up 平行
+const MAX_PITCH: f32 = 89f32 * std::f32::consts::PI / 180.0;
+
+/// 环绕相机：左键拖动绕 target 旋转，右键或中键拖动平移，滚轮缩放
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    /// 每拖动一个像素旋转的角度（弧度）
+    pub rotate_speed: f32,
+    /// 每拖动一个像素平移的距离，相对于相机到 target 的距离
+    pub pan_speed: f32,
+    /// 滚轮每滚动一格距离缩放的比例
+    pub zoom_speed: f32,
+    /// 相机到 target 的最小距离
+    pub min_distance: f32,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        OrbitController {
+            rotate_speed: 0.01,
+            pan_speed: 0.002,
+            zoom_speed: 1.1,
+            min_distance: 1e-3,
+        }
+    }
+}
+
+impl OrbitController {
+    /// 绕 target 旋转，yaw 绕 up 旋转，pitch 改变俯仰角
+    pub fn rotate(&self, camera: &mut Camera, yaw: f32, pitch: f32) {
+        let up = camera.up.normalize();
+        let offset = camera.eye - camera.target;
+        let distance = offset.norm();
+        if distance <= 0.0 {
+            return;
+        }
+        let direction = offset / distance;
+        // 当前的俯仰角，限制之后再计算实际能够旋转的角度
+        let current = direction.dot(&up).clamp(-1.0, 1.0).asin();
+        let pitch = (current + pitch).clamp(-MAX_PITCH, MAX_PITCH) - current;
+        let right = Unit::try_new(up.cross(&direction), f32::EPSILON)
+            .unwrap_or_else(|| Unit::new_normalize(up.cross(&Vector3::x())));
+        let rotation = Rotation3::from_axis_angle(&Unit::new_normalize(up), yaw)
+            * Rotation3::from_axis_angle(&right, -pitch);
+        camera.eye = camera.target + rotation * offset;
+    }
+
+    /// 在屏幕平面内同时移动相机和 target，dx、dy 为屏幕上的像素数
+    pub fn pan(&self, camera: &mut Camera, dx: f32, dy: f32) {
+        let forward = camera.forward();
+        let right = forward.cross(&camera.up).normalize();
+        let up = right.cross(&forward);
+        let scale = (camera.eye - camera.target).norm() * self.pan_speed;
+        // 拖动时场景跟着鼠标走，所以相机向相反的方向移动
+        let offset = (-right * dx + up * dy) * scale;
+        camera.eye += offset;
+        camera.target += offset;
+    }
+
+    /// 改变相机到 target 的距离，steps 为正时拉近
+    pub fn zoom(&self, camera: &mut Camera, steps: f32) {
+        let offset = camera.eye - camera.target;
+        let distance = offset.norm();
+        if distance <= 0.0 {
+            return;
+        }
+        let new_distance = (distance * self.zoom_speed.powf(-steps)).max(self.min_distance);
+        camera.eye = camera.target + offset * (new_distance / distance);
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, input: &Input, _dt: f32, camera: &mut Camera) {
+        let (dx, dy) = input.cursor_delta;
+        if input.left {
+            self.rotate(camera, -dx * self.rotate_speed, dy * self.rotate_speed);
+        } else if input.right || input.middle {
+            self.pan(camera, dx, dy);
+        }
+        if input.scroll != 0.0 {
+            self.zoom(camera, input.scroll);
+        }
+    }
+}
+
+/// 第一人称飞行相机
+///
+/// WASD 前后左右移动，E/Q 沿 up 上升下降，按住 Shift 加速；
+/// 左键或右键拖动转动视角，滚轮调整移动速度。相机到 target 的距离保持不变
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyController {
+    /// 每秒移动的距离
+    pub speed: f32,
+    /// 按住 Shift 时速度的倍数
+    pub boost: f32,
+    /// 每拖动一个像素转动的角度（弧度）
+    pub look_speed: f32,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        FlyController {
+            speed: 1.0,
+            boost: 4.0,
+            look_speed: 0.005,
+        }
+    }
+}
+
+impl FlyController {
+    /// 原地转动视角，yaw 为正时向左转，pitch 为正时向上看
+    pub fn look(&self, camera: &mut Camera, yaw: f32, pitch: f32) {
+        // 以相机为中心旋转 target，相当于交换 eye 和 target 之后的环绕旋转
+        let mut inverse = Camera {
+            eye: camera.target,
+            target: camera.eye,
+            ..*camera
+        };
+        OrbitController::default().rotate(&mut inverse, yaw, pitch);
+        camera.target = inverse.eye;
+    }
+
+    /// 按相机自身的方向移动，x 向右、y 沿 up、z 向前
+    pub fn translate(&self, camera: &mut Camera, x: f32, y: f32, z: f32) {
+        let forward = camera.forward();
+        let right = forward.cross(&camera.up).normalize();
+        let offset = right * x + camera.up.normalize() * y + forward * z;
+        camera.eye += offset;
+        camera.target += offset;
+    }
+}
+
+impl CameraController for FlyController {
+    fn update(&mut self, input: &Input, dt: f32, camera: &mut Camera) {
+        if input.scroll != 0.0 {
+            self.speed *= 1.1f32.powf(input.scroll);
+        }
+        let (dx, dy) = input.cursor_delta;
+        if input.left || input.right {
+            self.look(camera, -dx * self.look_speed, -dy * self.look_speed);
+        }
+
+        let axis = |positive: Key, negative: Key| -> f32 {
+            input.is_key_down(positive) as i32 as f32 - input.is_key_down(negative) as i32 as f32
+        };
+        let direction = Vector3::new(
+            axis(Key::D, Key::A),
+            axis(Key::E, Key::Q),
+            axis(Key::W, Key::S),
+        );
+        let Some(direction) = direction.try_normalize(f32::EPSILON) else {
+            return;
+        };
+        let boost = if input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift) {
+            self.boost
+        } else {
+            1.0
+        };
+        let step = direction * self.speed * boost * dt;
+        self.translate(camera, step.x, step.y, step.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Point3;
+
+    fn start() -> Camera {
+        Camera {
+            eye: Point3::new(0.0, 0.0, 5.0),
+            target: Point3::new(0.0, 0.0, 0.0),
+            ..Default::default()
+        }
+    }
+
+    fn drag(dx: f32, dy: f32) -> Input {
+        Input {
+            cursor_delta: (dx, dy),
+            left: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_orbit_rotate() {
+        let mut orbit = OrbitController::default();
+        let mut camera = start();
+        // 向左拖动 90 度，模型跟着鼠标向左转，相当于相机从 +z 转到 +x
+        let pixels = std::f32::consts::FRAC_PI_2 / orbit.rotate_speed;
+        orbit.update(&drag(-pixels, 0.0), 0.016, &mut camera);
+        assert!((camera.eye - Point3::new(5.0, 0.0, 0.0)).norm() < 1e-3);
+        assert_eq!(camera.target, Point3::origin());
+
+        // 俯仰角被限制，相机不会越过头顶
+        orbit.update(&drag(0.0, 1000.0), 0.016, &mut camera);
+        let direction = (camera.eye - camera.target).normalize();
+        assert!((direction.y.asin() - MAX_PITCH).abs() < 1e-3);
+        assert!(((camera.eye - camera.target).norm() - 5.0).abs() < 1e-3);
+        orbit.update(&drag(0.0, -3000.0), 0.016, &mut camera);
+        let direction = (camera.eye - camera.target).normalize();
+        assert!((direction.y.asin() + MAX_PITCH).abs() < 1e-3);
+        assert!(direction.x > 0.0);
+    }
+
+    #[test]
+    fn test_orbit_pan_and_zoom() {
+        let mut orbit = OrbitController::default();
+        let mut camera = start();
+        let input = Input {
+            cursor_delta: (100.0, 0.0),
+            right: true,
+            ..Default::default()
+        };
+        orbit.update(&input, 0.016, &mut camera);
+        // 向右拖动，相机和 target 一起向左移动
+        assert!(camera.target.x < 0.0);
+        assert!((camera.eye - camera.target - Vector3::new(0.0, 0.0, 5.0)).norm() < 1e-5);
+
+        let target = camera.target;
+        let input = Input {
+            scroll: 2.0,
+            ..Default::default()
+        };
+        orbit.update(&input, 0.016, &mut camera);
+        assert!(((camera.eye - camera.target).norm() - 5.0 / 1.21).abs() < 1e-4);
+        assert_eq!(camera.target, target);
+
+        // 距离不会小于最小值
+        let input = Input {
+            scroll: 1000.0,
+            ..Default::default()
+        };
+        orbit.update(&input, 0.016, &mut camera);
+        assert!(((camera.eye - camera.target).norm() - orbit.min_distance).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fly() {
+        let mut fly = FlyController::default();
+        let mut camera = start();
+        let input = Input {
+            keys: vec![Key::W, Key::D],
+            ..Default::default()
+        };
+        fly.update(&input, 1.0, &mut camera);
+        let expected = Vector3::new(1.0, 0.0, -1.0).normalize();
+        assert!((camera.eye - Point3::new(0.0, 0.0, 5.0) - expected).norm() < 1e-5);
+        assert!((camera.target - Point3::origin() - expected).norm() < 1e-5);
+
+        let input = Input {
+            keys: vec![Key::E, Key::LeftShift],
+            ..Default::default()
+        };
+        let before = camera.eye;
+        fly.update(&input, 0.5, &mut camera);
+        assert!((camera.eye - before - Vector3::new(0.0, 2.0, 0.0)).norm() < 1e-5);
+
+        // 转动视角时相机位置不变，向左拖动视线向左转
+        let mut camera = start();
+        let pixels = std::f32::consts::FRAC_PI_2 / fly.look_speed;
+        fly.update(&drag(-pixels, 0.0), 0.016, &mut camera);
+        assert_eq!(camera.eye, Point3::new(0.0, 0.0, 5.0));
+        assert!((camera.forward() + Vector3::x()).norm() < 1e-3);
+        assert!(((camera.target - camera.eye).norm() - 5.0).abs() < 1e-3);
+        // 向上拖动视线向上
+        fly.update(&drag(0.0, -10.0), 0.016, &mut camera);
+        assert!(camera.forward().y > 0.0);
+
+        // 滚轮调整速度
+        let input = Input {
+            scroll: 1.0,
+            ..Default::default()
+        };
+        fly.update(&input, 0.016, &mut camera);
+        assert!((fly.speed - 1.1).abs() < 1e-6);
+    }
+}
@@ -1,7 +1,9 @@
-use image::DynamicImage;
+use crate::controller::Input;
+use image::{DynamicImage, RgbaImage};
 use minifb::{Key, Window, WindowOptions};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// 创建窗口并且显示图片
 pub fn display_image(
@@ -48,6 +50,48 @@ pub fn display_image(
         }
     }
 }
+/// 创建窗口，每一帧读取输入并调用 frame 重新绘制图像，按 Escape 或关闭窗口时退出
+/// frame 的参数为这一帧的输入、距离上一帧的秒数以及要绘制的图像，返回 false 时也会退出
+pub fn display_interactive<F>(title: &str, width: usize, height: usize, mut frame: F)
+where
+    F: FnMut(&Input, f32, &mut RgbaImage) -> bool,
+{
+    let mut window = Window::new(
+        title,
+        width,
+        height,
+        WindowOptions {
+            resize: true,
+            ..Default::default()
+        },
+    )
+    .expect("Failed to create window");
+    // 限制在 60 帧左右，避免空转占满 CPU
+    window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
+
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    let mut buffer = vec![0u32; width * height];
+    let mut input = Input::default();
+    let mut last = Instant::now();
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        input = Input::from_window(&window, &input);
+        let now = Instant::now();
+        let dt = (now - last).as_secs_f32();
+        last = now;
+        if !frame(&input, dt, &mut image) {
+            break;
+        }
+        for (value, pixel) in buffer.iter_mut().zip(image.pixels()) {
+            let [r, g, b, _a] = pixel.0;
+            *value = ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+        }
+        if window.update_with_buffer(&buffer, width, height).is_err() {
+            eprintln!("Failed to update window");
+            break;
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! display_images {
     ( $duration:expr, $($image:expr),*  ) => {
@@ -0,0 +1,592 @@
+//! 网格缓存格式（.rmesh）
+//!
+//! 文件由 64 字节的文件头和数据区组成，所有数值都是小端序，数据区的每一段都按 4 字节对齐，
+//! 文件映射到内存之后可以直接按偏移取出顶点和索引数组。
+//!
+//! | 偏移 | 类型     | 内容                                    |
+//! |------|----------|-----------------------------------------|
+//! | 0    | [u8; 4]  | 魔数 `RMSH`                             |
+//! | 4    | u32      | 版本号                                  |
+//! | 8    | u32      | 属性标记，依次为 normals/uvs/colors/tangents/weights/joints |
+//! | 12   | u32      | 顶点数                                  |
+//! | 16   | u32      | 面数                                    |
+//! | 20   | u32      | 所有面的索引总数                        |
+//! | 24   | u32      | 分组数                                  |
+//! | 28   | u32      | 网格名字的字节数                        |
+//! | 32   | u64      | 数据区的字节数                          |
+//! | 40   | u32      | 数据区的 CRC32                          |
+//! | 44   | u32      | 形变目标数                              |
+//! | 48   | [u8; 16] | 保留，全部为 0                          |
+//!
+//! 数据区依次为：positions、存在的顶点属性（joints 每个顶点 4 个 u16，其余为 f32）、
+//! 形变目标表（每个目标 2 个 u32：名字字节数、偏移标记，依次为 positions/normals）和每个目标存在的偏移、
+//! 面的起始偏移（面数 + 1 个 u32）、索引（u32）、
+//! 分组表（每个分组 4 个 u32：名字字节数、材质、起始面、面数，没有材质时为 u32::MAX），
+//! 最后是网格名字、各个分组名字和各个形变目标名字拼接成的字符串，补 0 对齐到 4 字节。
+
+use super::{Error, Result};
+use crate::mesh::{Group, Mesh, MorphTarget};
+use nalgebra::{Point3, Vector2, Vector3, Vector4};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// 文件开头的魔数
+pub const MAGIC: [u8; 4] = *b"RMSH";
+/// 当前的格式版本，布局变化时递增，旧版本的缓存会被视为无效
+pub const VERSION: u32 = 3;
+/// 文件头的字节数
+pub const HEADER_SIZE: usize = 64;
+/// 缓存文件的扩展名
+pub const EXTENSION: &str = "rmesh";
+
+const HAS_NORMALS: u32 = 1;
+const HAS_UVS: u32 = 1 << 1;
+const HAS_COLORS: u32 = 1 << 2;
+const HAS_TANGENTS: u32 = 1 << 3;
+const HAS_WEIGHTS: u32 = 1 << 4;
+const HAS_JOINTS: u32 = 1 << 5;
+
+const TARGET_HAS_POSITIONS: u32 = 1;
+const TARGET_HAS_NORMALS: u32 = 1 << 1;
+
+/// 没有材质的分组写入的材质编号
+const NO_MATERIAL: u32 = u32::MAX;
+
+/// 从文件读取缓存
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    read(&bytes)
+}
+
+/// 将网格写入缓存文件
+pub fn save<P: AsRef<Path>>(mesh: &Mesh, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(mesh, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 源文件对应的缓存文件路径，在原文件名后追加 .rmesh，避免同名不同格式的文件互相覆盖
+pub fn cache_path<P: AsRef<Path>>(source: P) -> PathBuf {
+    let mut path = source.as_ref().as_os_str().to_owned();
+    path.push(".");
+    path.push(EXTENSION);
+    PathBuf::from(path)
+}
+
+/// 读取模型文件，优先使用旁边的缓存
+///
+/// 缓存不存在、比源文件旧或者无法解析时调用 load 重新读取源文件并写入缓存；
+/// 写缓存失败（比如目录只读）只会输出警告，不影响返回的网格
+pub fn load_cached<P, F>(source: P, load: F) -> Result<Mesh>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&Path) -> Result<Mesh>,
+{
+    let source = source.as_ref();
+    let cache = cache_path(source);
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    if let (Some(cache_time), Some(source_time)) = (modified(&cache), modified(source)) {
+        if cache_time >= source_time {
+            match self::load(&cache) {
+                Ok(mesh) => return Ok(mesh),
+                Err(e) => log::warn!("ignore invalid mesh cache {:?}: {}", cache, e),
+            }
+        }
+    }
+    let mesh = load(source)?;
+    if let Err(e) = save(&mesh, &cache) {
+        log::warn!("failed to write mesh cache {:?}: {}", cache, e);
+    }
+    Ok(mesh)
+}
+
+/// 写入缓存格式，顶点数、面数和索引都必须能用 u32 表示
+pub fn write<W: Write>(mesh: &Mesh, writer: &mut W) -> Result<()> {
+    let count = mesh.positions.len();
+    // 存在的属性必须和顶点数一致，返回属性标记
+    let attribute_flags = |attributes: &[(u32, usize, &str)]| -> Result<u32> {
+        let mut flags = 0;
+        for &(flag, len, attribute) in attributes {
+            if len == count && len != 0 {
+                flags |= flag;
+            } else if len != 0 {
+                return Err(Error::Format(format!(
+                    "{} has {} values, expected {}",
+                    attribute, len, count
+                )));
+            }
+        }
+        Ok(flags)
+    };
+    let flags = attribute_flags(&[
+        (HAS_NORMALS, mesh.normals.len(), "normals"),
+        (HAS_UVS, mesh.uvs.len(), "uvs"),
+        (HAS_COLORS, mesh.colors.len(), "colors"),
+        (HAS_TANGENTS, mesh.tangents.len(), "tangents"),
+        (HAS_WEIGHTS, mesh.weights.len(), "weights"),
+        (HAS_JOINTS, mesh.joints.len(), "joints"),
+    ])?;
+    let index_count: usize = mesh.faces.iter().map(|f| f.len()).sum();
+
+    let mut body = Vec::new();
+    mesh.positions
+        .iter()
+        .for_each(|p| push_f32s(&mut body, p.coords.as_slice()));
+    mesh.normals
+        .iter()
+        .for_each(|n| push_f32s(&mut body, n.as_slice()));
+    mesh.uvs
+        .iter()
+        .for_each(|t| push_f32s(&mut body, t.as_slice()));
+    mesh.colors
+        .iter()
+        .for_each(|c| push_f32s(&mut body, c.as_slice()));
+    mesh.tangents
+        .iter()
+        .for_each(|t| push_f32s(&mut body, t.as_slice()));
+    mesh.weights
+        .iter()
+        .for_each(|w| push_f32s(&mut body, w.as_slice()));
+    for &joint in mesh.joints.iter().flatten() {
+        body.extend_from_slice(&joint.to_le_bytes());
+    }
+
+    let mut target_flags = Vec::with_capacity(mesh.targets.len());
+    for target in &mesh.targets {
+        let flags = attribute_flags(&[
+            (
+                TARGET_HAS_POSITIONS,
+                target.positions.len(),
+                "morph target positions",
+            ),
+            (
+                TARGET_HAS_NORMALS,
+                target.normals.len(),
+                "morph target normals",
+            ),
+        ])?;
+        push_u32(&mut body, target.name.len())?;
+        push_u32(&mut body, flags as usize)?;
+        target_flags.push(flags);
+    }
+    for target in &mesh.targets {
+        target
+            .positions
+            .iter()
+            .chain(&target.normals)
+            .for_each(|d| push_f32s(&mut body, d.as_slice()));
+    }
+
+    let mut offset = 0;
+    push_u32(&mut body, 0)?;
+    for face in &mesh.faces {
+        offset += face.len();
+        push_u32(&mut body, offset)?;
+    }
+    for &v in mesh.faces.iter().flatten() {
+        push_u32(&mut body, v)?;
+    }
+    for group in &mesh.groups {
+        push_u32(&mut body, group.name.len())?;
+        match group.material {
+            Some(material) if material < NO_MATERIAL as usize => push_u32(&mut body, material)?,
+            Some(material) => return Err(too_large(material)),
+            None => body.extend_from_slice(&NO_MATERIAL.to_le_bytes()),
+        }
+        push_u32(&mut body, group.start)?;
+        push_u32(&mut body, group.count)?;
+    }
+    body.extend_from_slice(mesh.name.as_bytes());
+    for group in &mesh.groups {
+        body.extend_from_slice(group.name.as_bytes());
+    }
+    for target in &mesh.targets {
+        body.extend_from_slice(target.name.as_bytes());
+    }
+    body.resize(body.len().next_multiple_of(4), 0);
+
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(&MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&flags.to_le_bytes());
+    for value in [
+        count,
+        mesh.faces.len(),
+        index_count,
+        mesh.groups.len(),
+        mesh.name.len(),
+    ] {
+        push_u32(&mut header, value)?;
+    }
+    header.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    header.extend_from_slice(&crc32(&body).to_le_bytes());
+    push_u32(&mut header, mesh.targets.len())?;
+    header.resize(HEADER_SIZE, 0);
+
+    writer.write_all(&header)?;
+    writer.write_all(&body)?;
+    Ok(())
+}
+
+/// 从内存读取缓存，bytes 可以直接来自文件的内存映射
+/// 会检查魔数、版本、长度和校验和，以及索引和分组是否越界
+pub fn read(bytes: &[u8]) -> Result<Mesh> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(Error::Format("mesh cache shorter than header".to_string()));
+    }
+    if bytes[..4] != MAGIC {
+        return Err(Error::Format("not a mesh cache file".to_string()));
+    }
+    let mut header = Reader {
+        bytes: &bytes[..HEADER_SIZE],
+        pos: 4,
+    };
+    let version = header.u32()?;
+    if version != VERSION {
+        return Err(Error::Format(format!(
+            "unsupported mesh cache version {}, expected {}",
+            version, VERSION
+        )));
+    }
+    let flags = header.u32()?;
+    let vertex_count = header.u32()? as usize;
+    let face_count = header.u32()? as usize;
+    let index_count = header.u32()? as usize;
+    let group_count = header.u32()? as usize;
+    let name_len = header.u32()? as usize;
+    let body_len = header.u64()?;
+    let checksum = header.u32()?;
+    let target_count = header.u32()? as usize;
+
+    let body = &bytes[HEADER_SIZE..];
+    if body.len() as u64 != body_len {
+        return Err(Error::Format(format!(
+            "mesh cache body has {} bytes, expected {}",
+            body.len(),
+            body_len
+        )));
+    }
+    if crc32(body) != checksum {
+        return Err(Error::Format("mesh cache checksum mismatch".to_string()));
+    }
+
+    let mut reader = Reader {
+        bytes: body,
+        pos: 0,
+    };
+    let mut mesh = Mesh::new("");
+    mesh.positions = reader.vectors(vertex_count, |v: [f32; 3]| Point3::from(v))?;
+    if flags & HAS_NORMALS != 0 {
+        mesh.normals = reader.vectors(vertex_count, Vector3::from)?;
+    }
+    if flags & HAS_UVS != 0 {
+        mesh.uvs = reader.vectors(vertex_count, Vector2::from)?;
+    }
+    if flags & HAS_COLORS != 0 {
+        mesh.colors = reader.vectors(vertex_count, Vector4::from)?;
+    }
+    if flags & HAS_TANGENTS != 0 {
+        mesh.tangents = reader.vectors(vertex_count, Vector4::from)?;
+    }
+    if flags & HAS_WEIGHTS != 0 {
+        mesh.weights = reader.vectors(vertex_count, Vector4::from)?;
+    }
+    if flags & HAS_JOINTS != 0 {
+        mesh.joints = reader.joints(vertex_count)?;
+    }
+
+    let target_table = reader.u32s(target_count.saturating_mul(2))?;
+    for entry in target_table.chunks_exact(2) {
+        let flags = entry[1] as u32;
+        let mut target = MorphTarget::default();
+        if flags & TARGET_HAS_POSITIONS != 0 {
+            target.positions = reader.vectors(vertex_count, Vector3::from)?;
+        }
+        if flags & TARGET_HAS_NORMALS != 0 {
+            target.normals = reader.vectors(vertex_count, Vector3::from)?;
+        }
+        mesh.targets.push(target);
+    }
+
+    let offsets = reader.u32s(face_count + 1)?;
+    let indices = reader.u32s(index_count)?;
+    if offsets[0] != 0
+        || offsets[face_count] != index_count
+        || offsets.windows(2).any(|w| w[0] > w[1])
+    {
+        return Err(Error::Format(
+            "invalid face offsets in mesh cache".to_string(),
+        ));
+    }
+    if let Some(&index) = indices.iter().find(|&&v| v >= vertex_count) {
+        return Err(Error::Format(format!(
+            "index {} out of range, vertex count {}",
+            index, vertex_count
+        )));
+    }
+    mesh.faces = offsets
+        .windows(2)
+        .map(|w| indices[w[0]..w[1]].to_vec())
+        .collect();
+
+    let table = reader.u32s(group_count * 4)?;
+    let name = reader.take(name_len)?;
+    mesh.name = utf8(name)?;
+    for entry in table.chunks_exact(4) {
+        let (start, count) = (entry[2], entry[3]);
+        if start.checked_add(count).is_none_or(|end| end > face_count) {
+            return Err(Error::Format(format!(
+                "group range {}..{} out of range, face count {}",
+                start,
+                start.saturating_add(count),
+                face_count
+            )));
+        }
+        mesh.groups.push(Group {
+            name: utf8(reader.take(entry[0])?)?,
+            material: (entry[1] != NO_MATERIAL as usize).then_some(entry[1]),
+            start,
+            count,
+        });
+    }
+    for (target, entry) in mesh.targets.iter_mut().zip(target_table.chunks_exact(2)) {
+        target.name = utf8(reader.take(entry[0])?)?;
+    }
+    Ok(mesh)
+}
+
+fn push_f32s(buffer: &mut Vec<u8>, values: &[f32]) {
+    for v in values {
+        buffer.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: usize) -> Result<()> {
+    let value = u32::try_from(value).map_err(|_| too_large(value))?;
+    buffer.extend_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+fn too_large(value: usize) -> Error {
+    Error::Format(format!("value {} does not fit in mesh cache", value))
+}
+
+fn utf8(bytes: &[u8]) -> Result<String> {
+    String::from_utf8(bytes.to_vec())
+        .map_err(|e| Error::Format(format!("invalid name in mesh cache: {}", e)))
+}
+
+/// 按顺序读取小端序数据，越界时返回错误
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| Error::Format("unexpected end of mesh cache".to_string()))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn u32s(&mut self, count: usize) -> Result<Vec<usize>> {
+        let len = count
+            .checked_mul(4)
+            .ok_or_else(|| Error::Format("mesh cache count overflow".to_string()))?;
+        Ok(self
+            .take(len)?
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+            .collect())
+    }
+
+    fn joints(&mut self, count: usize) -> Result<Vec<[u16; 4]>> {
+        let len = count
+            .checked_mul(8)
+            .ok_or_else(|| Error::Format("mesh cache count overflow".to_string()))?;
+        Ok(self
+            .take(len)?
+            .chunks_exact(8)
+            .map(|chunk| {
+                let mut joints = [0; 4];
+                for (i, b) in chunk.chunks_exact(2).enumerate() {
+                    joints[i] = u16::from_le_bytes(b.try_into().unwrap());
+                }
+                joints
+            })
+            .collect())
+    }
+
+    fn vectors<T, const N: usize>(
+        &mut self,
+        count: usize,
+        convert: impl Fn([f32; N]) -> T,
+    ) -> Result<Vec<T>> {
+        let len = count
+            .checked_mul(N * 4)
+            .ok_or_else(|| Error::Format("mesh cache count overflow".to_string()))?;
+        Ok(self
+            .take(len)?
+            .chunks_exact(N * 4)
+            .map(|chunk| {
+                let mut v = [0.0; N];
+                for (i, b) in chunk.chunks_exact(4).enumerate() {
+                    v[i] = f32::from_le_bytes(b.try_into().unwrap());
+                }
+                convert(v)
+            })
+            .collect())
+    }
+}
+
+/// CRC32（IEEE 802.3 多项式）查找表
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |c, &b| {
+        CRC_TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::primitive;
+
+    fn round_trip(mesh: &Mesh) -> Mesh {
+        let mut bytes = Vec::new();
+        write(mesh, &mut bytes).unwrap();
+        assert_eq!(bytes.len() % 4, 0);
+        read(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mesh = crate::io::obj::load_mesh(path).unwrap();
+        assert_eq!(round_trip(&mesh), mesh);
+
+        // 所有属性、多边形、分组以及非 ASCII 的名字
+        let mut mesh = Mesh::new("立方体");
+        mesh.append(&primitive::cube(1.0), "cube", Some(2));
+        mesh.append(&primitive::torus(2.0, 0.5, 8, 6), "环", None);
+        mesh.faces.push(vec![0, 1, 2, 3, 4]);
+        mesh.groups[1].count += 1;
+        assert!(mesh.compute_tangents());
+        mesh.colors = (0..mesh.vertex_count())
+            .map(|i| Vector4::new(i as f32, -0.0, f32::MIN_POSITIVE, 1.0))
+            .collect();
+        mesh.joints = (0..mesh.vertex_count())
+            .map(|i| [i as u16, 1, u16::MAX, 0])
+            .collect();
+        mesh.weights = vec![Vector4::new(0.25, 0.75, 0.0, 0.0); mesh.vertex_count()];
+        mesh.targets = vec![
+            MorphTarget {
+                name: "微笑".to_string(),
+                positions: vec![Vector3::new(0.0, 0.1, 0.0); mesh.vertex_count()],
+                normals: Vec::new(),
+            },
+            MorphTarget {
+                name: String::new(),
+                positions: vec![Vector3::zeros(); mesh.vertex_count()],
+                normals: vec![Vector3::x(); mesh.vertex_count()],
+            },
+        ];
+        let result = round_trip(&mesh);
+        assert_eq!(result, mesh);
+        // 逐位相同，包括 -0 的符号
+        assert_eq!(result.colors[3].y.to_bits(), (-0.0f32).to_bits());
+
+        let empty = Mesh::new("");
+        assert_eq!(round_trip(&empty), empty);
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let mut bytes = Vec::new();
+        write(&primitive::cube(1.0), &mut bytes).unwrap();
+
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_SIZE + 5] ^= 1;
+        assert!(matches!(read(&corrupted), Err(Error::Format(msg)) if msg.contains("checksum")));
+
+        let mut version = bytes.clone();
+        version[4] = 99;
+        assert!(matches!(read(&version), Err(Error::Format(msg)) if msg.contains("version")));
+
+        assert!(read(&bytes[..bytes.len() - 4]).is_err());
+        assert!(read(&bytes[..10]).is_err());
+        assert!(read(b"solid not a mesh cache, just some text padded to the header size").is_err());
+
+        let mut mesh = primitive::cube(1.0);
+        mesh.uvs.pop();
+        assert!(write(&mesh, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_load_cached() {
+        let dir = std::env::temp_dir().join("render_cache_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("sphere.obj");
+        let sphere = primitive::uv_sphere(1.0, 8, 4);
+        crate::io::obj::save(&source, &sphere, &[], &[]).unwrap();
+        let cache = cache_path(&source);
+        assert_eq!(cache, dir.join("sphere.obj.rmesh"));
+        let _ = std::fs::remove_file(&cache);
+
+        let loaded = load_cached(&source, |p| crate::io::obj::load_mesh(p)).unwrap();
+        assert!(cache.exists());
+        // 第二次直接读取缓存，不会调用源文件的加载函数
+        let cached = load_cached(&source, |_| panic!("cache should be used")).unwrap();
+        assert_eq!(cached, loaded);
+
+        // 缓存损坏时重新读取源文件
+        std::fs::write(&cache, b"broken").unwrap();
+        let reloaded = load_cached(&source, |p| crate::io::obj::load_mesh(p)).unwrap();
+        assert_eq!(reloaded, loaded);
+        assert_eq!(load(&cache).unwrap(), loaded);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
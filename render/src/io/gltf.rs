@@ -0,0 +1,648 @@
+use super::{Error, Result};
+use crate::animation::{Animatable, Animation, Channel, Interpolation, Keyframe, Target, Track};
+use crate::camera::{Camera, Projection};
+use crate::material::{AlphaMode, Material};
+use crate::mesh::{Mesh, MorphTarget};
+use crate::scene::{Node, Scene, Skin};
+use ::gltf::animation::util::ReadOutputs;
+use ::gltf::image::{Data as ImageData, Format};
+use ::gltf::mesh::Mode;
+use ::gltf::{buffer, Document};
+use image::{DynamicImage, ImageBuffer};
+use nalgebra::{Matrix4, Point3, Quaternion, UnitQuaternion, Vector2, Vector3, Vector4};
+use std::path::Path;
+
+/// 读取 gltf 或 glb 文件
+/// 外部的 .bin 和图片文件相对于 gltf 文件所在目录加载
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene> {
+    let (document, buffers, images) = ::gltf::import(path)?;
+    convert(&document, &buffers, images)
+}
+
+/// 从内存读取 glb
+/// 注意 gltf 库在没有文件路径时不会解析 uri 图片，内嵌图片的 gltf 需要使用 load
+pub fn load_slice(bytes: &[u8]) -> Result<Scene> {
+    let (document, buffers, images) = ::gltf::import_slice(bytes)?;
+    convert(&document, &buffers, images)
+}
+
+/// 将 gltf 文档转换为场景
+fn convert(document: &Document, buffers: &[buffer::Data], images: Vec<ImageData>) -> Result<Scene> {
+    let mut scene = Scene::default();
+
+    for image in images {
+        scene.images.push(convert_image(image)?);
+    }
+
+    for material in document.materials() {
+        scene.materials.push(convert_material(&material));
+    }
+
+    for mesh in document.meshes() {
+        scene.meshes.push(convert_mesh(&mesh, buffers)?);
+    }
+
+    for camera in document.cameras() {
+        scene.cameras.push(convert_camera(&camera));
+    }
+
+    // 节点的索引和 gltf 中保持一致
+    for node in document.nodes() {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        let [x, y, z, w] = rotation;
+        scene.nodes.push(Node {
+            name: node.name().unwrap_or_default().to_string(),
+            translation: Vector3::from(translation),
+            rotation: UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)),
+            scale: Vector3::from(scale),
+            mesh: node.mesh().map(|m| m.index()),
+            light: None,
+            camera: node.camera().map(|c| c.index()),
+            skin: node.skin().map(|s| s.index()),
+            // 节点上没有权重时使用网格的默认权重
+            morph_weights: node
+                .weights()
+                .or_else(|| node.mesh().and_then(|m| m.weights()))
+                .map_or_else(Vec::new, |w| w.to_vec()),
+            children: node.children().map(|c| c.index()).collect(),
+        });
+    }
+
+    for skin in document.skins() {
+        scene.skins.push(convert_skin(&skin, buffers));
+    }
+
+    for animation in document.animations() {
+        scene
+            .animations
+            .push(convert_animation(&animation, buffers)?);
+    }
+
+    // 优先使用默认场景，没有时使用第一个场景
+    if let Some(s) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        scene.roots = s.nodes().map(|n| n.index()).collect();
+    }
+
+    Ok(scene)
+}
+
+/// gltf 的相机位于节点原点看向 -z，没有远平面的透视相机使用一个足够大的距离
+fn convert_camera(camera: &::gltf::Camera) -> Camera {
+    let (projection, near, far) = match camera.projection() {
+        ::gltf::camera::Projection::Perspective(p) => (
+            Projection::Perspective { fov_y: p.yfov() },
+            p.znear(),
+            p.zfar().unwrap_or(p.znear() * 1e4),
+        ),
+        ::gltf::camera::Projection::Orthographic(o) => (
+            Projection::Orthographic {
+                height: o.ymag() * 2.0,
+            },
+            o.znear(),
+            o.zfar(),
+        ),
+    };
+    Camera {
+        eye: Point3::origin(),
+        target: Point3::new(0.0, 0.0, -1.0),
+        up: Vector3::y(),
+        projection,
+        near,
+        far,
+    }
+}
+
+/// 转换蒙皮，没有逆绑定矩阵时所有骨骼使用单位矩阵
+fn convert_skin(skin: &::gltf::Skin, buffers: &[buffer::Data]) -> Skin {
+    let reader = skin.reader(|b| buffers.get(b.index()).map(|data| &data.0[..]));
+    Skin {
+        name: skin.name().unwrap_or_default().to_string(),
+        joints: skin.joints().map(|j| j.index()).collect(),
+        inverse_bind_matrices: reader
+            .read_inverse_bind_matrices()
+            .map(|matrices| matrices.map(Matrix4::from).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// 转换动画，形变权重的通道按目标拆分成多个通道
+fn convert_animation(animation: &::gltf::Animation, buffers: &[buffer::Data]) -> Result<Animation> {
+    let name = animation.name().unwrap_or_default();
+    let mut result = Animation::new(name);
+    for channel in animation.channels() {
+        let node = channel.target().node().index();
+        let reader = channel.reader(|b| buffers.get(b.index()).map(|data| &data.0[..]));
+        let missing = || Error::Format(format!("animation {:?} channel without samples", name));
+        let times: Vec<f32> = reader.read_inputs().ok_or_else(missing)?.collect();
+        let interpolation = match channel.sampler().interpolation() {
+            ::gltf::animation::Interpolation::Step => Interpolation::Step,
+            ::gltf::animation::Interpolation::Linear => Interpolation::Linear,
+            ::gltf::animation::Interpolation::CubicSpline => Interpolation::Cubic,
+        };
+        let target = match reader.read_outputs().ok_or_else(missing)? {
+            ReadOutputs::Translations(values) => Target::Translation(convert_track(
+                name,
+                interpolation,
+                &times,
+                values.map(Vector3::from).collect(),
+                |v| v,
+            )?),
+            ReadOutputs::Rotations(values) => Target::Rotation(convert_track(
+                name,
+                interpolation,
+                &times,
+                values
+                    .into_f32()
+                    .map(|[x, y, z, w]| Quaternion::new(w, x, y, z))
+                    .collect(),
+                UnitQuaternion::from_quaternion,
+            )?),
+            ReadOutputs::Scales(values) => Target::Scale(convert_track(
+                name,
+                interpolation,
+                &times,
+                values.map(Vector3::from).collect(),
+                |v| v,
+            )?),
+            ReadOutputs::MorphTargetWeights(values) => {
+                // 每个关键帧依次保存所有目标的权重，三次插值时为所有目标的入切线、权重、出切线
+                let values: Vec<f32> = values.into_f32().collect();
+                let stride = if interpolation == Interpolation::Cubic {
+                    3
+                } else {
+                    1
+                };
+                let count = values.len().checked_div(times.len() * stride).unwrap_or(0);
+                for index in 0..count {
+                    let outputs = values.iter().skip(index).step_by(count).copied().collect();
+                    result.channels.push(Channel {
+                        node,
+                        target: Target::MorphWeight(
+                            index,
+                            convert_track(name, interpolation, &times, outputs, |v| v)?,
+                        ),
+                    });
+                }
+                continue;
+            }
+        };
+        result.channels.push(Channel { node, target });
+    }
+    Ok(result)
+}
+
+/// 把采样器的输入和输出组装成轨道，三次插值时每个关键帧的输出依次为入切线、值、出切线
+fn convert_track<T: Animatable>(
+    name: &str,
+    interpolation: Interpolation,
+    times: &[f32],
+    outputs: Vec<T::Tangent>,
+    value: fn(T::Tangent) -> T,
+) -> Result<Track<T>> {
+    let stride = if interpolation == Interpolation::Cubic {
+        3
+    } else {
+        1
+    };
+    if outputs.len() != times.len() * stride {
+        return Err(Error::Format(format!(
+            "animation {:?} has {} keyframes but {} outputs",
+            name,
+            times.len(),
+            outputs.len()
+        )));
+    }
+    let keyframes = times
+        .iter()
+        .zip(outputs.chunks_exact(stride))
+        .map(|(&time, output)| match output {
+            &[in_tangent, v, out_tangent] => Keyframe {
+                time,
+                value: value(v),
+                in_tangent,
+                out_tangent,
+            },
+            _ => Keyframe::new(time, value(output[0])),
+        })
+        .collect();
+    Ok(Track::new(interpolation, keyframes))
+}
+
+/// 将 gltf 的 mesh 转换为网格，每个 primitive 对应网格中的一个分组
+fn convert_mesh(mesh: &::gltf::Mesh, buffers: &[buffer::Data]) -> Result<Mesh> {
+    let name = mesh.name().unwrap_or_default();
+    let mut result = Mesh::new(name);
+
+    for primitive in mesh.primitives() {
+        let reader = primitive.reader(|b| buffers.get(b.index()).map(|data| &data.0[..]));
+        let positions: Vec<Point3<f32>> = reader
+            .read_positions()
+            .ok_or_else(|| Error::Format(format!("mesh {:?} primitive without POSITION", name)))?
+            .map(Point3::from)
+            .collect();
+        let count = positions.len();
+
+        let mut part = Mesh::new(name);
+        part.positions = positions;
+        if let Some(normals) = reader.read_normals() {
+            part.normals = normals.map(Vector3::from).collect();
+        }
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            part.uvs = uvs.into_f32().map(Vector2::from).collect();
+        }
+        if let Some(colors) = reader.read_colors(0) {
+            part.colors = colors.into_rgba_f32().map(Vector4::from).collect();
+        }
+        if let Some(tangents) = reader.read_tangents() {
+            part.tangents = tangents.map(Vector4::from).collect();
+        }
+        if let Some(joints) = reader.read_joints(0) {
+            part.joints = joints.into_u16().collect();
+        }
+        if let Some(weights) = reader.read_weights(0) {
+            part.weights = weights.into_f32().map(Vector4::from).collect();
+        }
+
+        let indices: Vec<usize> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+            None => (0..count).collect(),
+        };
+        if let Some(&i) = indices.iter().find(|&&i| i >= count) {
+            return Err(Error::Format(format!("index {} out of range {}", i, count)));
+        }
+
+        part.faces = match primitive.mode() {
+            Mode::Triangles => indices.chunks_exact(3).map(|t| t.to_vec()).collect(),
+            Mode::TriangleStrip => (0..indices.len().saturating_sub(2))
+                .map(|i| {
+                    // 奇数三角形需要交换顶点顺序保持绕序一致
+                    if i % 2 == 0 {
+                        vec![indices[i], indices[i + 1], indices[i + 2]]
+                    } else {
+                        vec![indices[i + 1], indices[i], indices[i + 2]]
+                    }
+                })
+                .collect(),
+            Mode::TriangleFan => (1..indices.len().saturating_sub(1))
+                .map(|i| vec![indices[0], indices[i], indices[i + 1]])
+                .collect(),
+            // 点和线只保留顶点
+            mode => {
+                log::warn!("mesh {:?} primitive mode {:?} has no faces", name, mode);
+                Vec::new()
+            }
+        };
+
+        for (i, (positions, normals, _)) in reader.read_morph_targets().enumerate() {
+            part.targets.push(MorphTarget {
+                name: format!("target{}", i),
+                positions: positions.map_or_else(Vec::new, |p| p.map(Vector3::from).collect()),
+                normals: normals.map_or_else(Vec::new, |n| n.map(Vector3::from).collect()),
+            });
+        }
+
+        let group_name = format!("{}#{}", name, primitive.index());
+        result.append(&part, &group_name, primitive.material().index());
+    }
+
+    Ok(result)
+}
+
+/// 转换 PBR 金属度-粗糙度材质，纹理索引转换为图片索引
+fn convert_material(material: &::gltf::Material) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    Material {
+        name: material.name().unwrap_or_default().to_string(),
+        base_color: Vector4::from(pbr.base_color_factor()),
+        base_color_texture: pbr
+            .base_color_texture()
+            .map(|t| t.texture().source().index()),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr
+            .metallic_roughness_texture()
+            .map(|t| t.texture().source().index()),
+        normal_texture: material
+            .normal_texture()
+            .map(|t| t.texture().source().index()),
+        normal_scale: material.normal_texture().map_or(1.0, |t| t.scale()),
+        occlusion_texture: material
+            .occlusion_texture()
+            .map(|t| t.texture().source().index()),
+        occlusion_strength: material.occlusion_texture().map_or(1.0, |t| t.strength()),
+        emissive: Vector3::from(material.emissive_factor()),
+        emissive_texture: material
+            .emissive_texture()
+            .map(|t| t.texture().source().index()),
+        alpha_mode: match material.alpha_mode() {
+            ::gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            ::gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            ::gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+    }
+}
+
+/// 将 gltf 解码后的像素数据转换为 DynamicImage
+fn convert_image(data: ImageData) -> Result<DynamicImage> {
+    let (w, h) = (data.width, data.height);
+    let invalid = || Error::Format(format!("invalid {:?} image data {}x{}", data.format, w, h));
+    let u16s = |pixels: &[u8]| -> Vec<u16> {
+        pixels
+            .chunks_exact(2)
+            .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+            .collect()
+    };
+    let f32s = |pixels: &[u8]| -> Vec<f32> {
+        pixels
+            .chunks_exact(4)
+            .map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+            .collect()
+    };
+    let image = match data.format {
+        Format::R8 => {
+            ImageBuffer::from_raw(w, h, data.pixels.clone()).map(DynamicImage::ImageLuma8)
+        }
+        Format::R8G8 => {
+            ImageBuffer::from_raw(w, h, data.pixels.clone()).map(DynamicImage::ImageLumaA8)
+        }
+        Format::R8G8B8 => {
+            ImageBuffer::from_raw(w, h, data.pixels.clone()).map(DynamicImage::ImageRgb8)
+        }
+        Format::R8G8B8A8 => {
+            ImageBuffer::from_raw(w, h, data.pixels.clone()).map(DynamicImage::ImageRgba8)
+        }
+        Format::R16 => {
+            ImageBuffer::from_raw(w, h, u16s(&data.pixels)).map(DynamicImage::ImageLuma16)
+        }
+        Format::R16G16 => {
+            ImageBuffer::from_raw(w, h, u16s(&data.pixels)).map(DynamicImage::ImageLumaA16)
+        }
+        Format::R16G16B16 => {
+            ImageBuffer::from_raw(w, h, u16s(&data.pixels)).map(DynamicImage::ImageRgb16)
+        }
+        Format::R16G16B16A16 => {
+            ImageBuffer::from_raw(w, h, u16s(&data.pixels)).map(DynamicImage::ImageRgba16)
+        }
+        Format::R32G32B32FLOAT => {
+            ImageBuffer::from_raw(w, h, f32s(&data.pixels)).map(DynamicImage::ImageRgb32F)
+        }
+        Format::R32G32B32A32FLOAT => {
+            ImageBuffer::from_raw(w, h, f32s(&data.pixels)).map(DynamicImage::ImageRgba32F)
+        }
+    };
+    image.ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    fn resource(name: &str) -> String {
+        format!("{}/../resource/gltf/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    /// 检查样例文件中的两个四边形以及材质
+    fn check_quad_scene(scene: &Scene) {
+        assert_eq!(scene.meshes.len(), 1);
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.name, "quad");
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert_eq!(mesh.normals, vec![Vector3::new(0.0, 0.0, 1.0); 4]);
+        assert_eq!(mesh.uvs[2], Vector2::new(1.0, 0.0));
+        assert_eq!(mesh.groups.len(), 1);
+        assert_eq!(mesh.groups[0].material, Some(0));
+
+        let material = &scene.materials[0];
+        assert_eq!(material.name, "checker");
+        assert_eq!(material.base_color, Vector4::new(1.0, 0.5, 0.25, 1.0));
+        assert_eq!(material.metallic, 0.0);
+        assert_eq!(material.roughness, 0.5);
+        assert_eq!(material.base_color_texture, Some(0));
+        assert!(material.double_sided);
+
+        assert_eq!(scene.images.len(), 1);
+        assert_eq!(scene.images[0].dimensions(), (2, 2));
+        assert_eq!(
+            scene.images[0].to_rgba8().get_pixel(0, 0).0,
+            [255, 0, 0, 255]
+        );
+    }
+
+    /// 检查父子节点的层级关系
+    fn check_hierarchy(scene: &Scene) {
+        assert_eq!(scene.roots, vec![0]);
+        assert_eq!(scene.nodes[0].name, "root");
+        assert_eq!(scene.nodes[0].children, vec![1]);
+        assert_eq!(scene.nodes[0].translation, Vector3::new(1.0, 0.0, 0.0));
+        let child = &scene.nodes[1];
+        assert_eq!(child.name, "child");
+        assert_eq!(child.mesh, Some(0));
+        assert_eq!(child.scale, Vector3::new(2.0, 2.0, 2.0));
+        assert!((child.rotation.angle() - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_load_gltf_external() {
+        let scene = load(resource("quad.gltf")).expect("Failed to load gltf");
+        check_quad_scene(&scene);
+        check_hierarchy(&scene);
+    }
+
+    #[test]
+    fn test_load_gltf_embedded() {
+        let scene = load(resource("quad_embedded.gltf")).expect("Failed to load gltf");
+        check_quad_scene(&scene);
+        check_hierarchy(&scene);
+    }
+
+    #[test]
+    fn test_load_glb() {
+        let scene = load(resource("quad.glb")).expect("Failed to load glb");
+        check_quad_scene(&scene);
+        check_hierarchy(&scene);
+
+        let bytes = std::fs::read(resource("quad.glb")).unwrap();
+        let scene = load_slice(&bytes).expect("Failed to load glb");
+        check_quad_scene(&scene);
+    }
+
+    #[test]
+    fn test_load_camera() {
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "cameras": [
+                {"type": "perspective", "perspective": {"yfov": 0.8, "znear": 0.1, "zfar": 50}},
+                {"type": "orthographic", "orthographic": {"xmag": 2, "ymag": 1.5, "znear": 0, "zfar": 10}}
+            ],
+            "nodes": [{"camera": 0, "translation": [0, 0, 5]}, {"camera": 1}],
+            "scenes": [{"nodes": [0, 1]}],
+            "scene": 0
+        }"#;
+        let scene = load_slice(json.as_bytes()).expect("Failed to load gltf");
+        assert_eq!(scene.cameras.len(), 2);
+        assert_eq!(
+            scene.cameras[0].projection,
+            Projection::Perspective { fov_y: 0.8 }
+        );
+        assert_eq!((scene.cameras[0].near, scene.cameras[0].far), (0.1, 50.0));
+        assert_eq!(
+            scene.cameras[1].projection,
+            Projection::Orthographic { height: 3.0 }
+        );
+        assert_eq!(scene.nodes[0].camera, Some(0));
+        let camera = scene.active_camera().unwrap();
+        assert_eq!(camera.eye, Point3::new(0.0, 0.0, 5.0));
+        assert_eq!(camera.forward(), -Vector3::z());
+    }
+
+    #[test]
+    fn test_load_animation() {
+        // 缓冲区依次为时间 [0, 1]、线性平移、阶跃旋转、三次样条缩放（入切线、值、出切线）
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 136, "uri": "data:application/octet-stream;base64,AAAAAAAAgD8AAAAAAAAAAAAAAAAAAABAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAPMENT8AAAAA8wQ1PwAAAAAAAAAAAAAAAAAAgD8AAIA/AACAPwAAgD8AAIA/AACAPwAAgD8AAIA/AACAPwAAAEAAAABAAAAAQAAAAAAAAAAAAAAAAA=="}],
+            "bufferViews": [{"buffer": 0, "byteLength": 136}],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0], "max": [1]},
+                {"bufferView": 0, "byteOffset": 8, "componentType": 5126, "count": 2, "type": "VEC3"},
+                {"bufferView": 0, "byteOffset": 32, "componentType": 5126, "count": 2, "type": "VEC4"},
+                {"bufferView": 0, "byteOffset": 64, "componentType": 5126, "count": 6, "type": "VEC3"}
+            ],
+            "animations": [{
+                "name": "move",
+                "samplers": [
+                    {"input": 0, "output": 1},
+                    {"input": 0, "output": 2, "interpolation": "STEP"},
+                    {"input": 0, "output": 3, "interpolation": "CUBICSPLINE"}
+                ],
+                "channels": [
+                    {"sampler": 0, "target": {"node": 0, "path": "translation"}},
+                    {"sampler": 1, "target": {"node": 0, "path": "rotation"}},
+                    {"sampler": 2, "target": {"node": 0, "path": "scale"}}
+                ]
+            }],
+            "nodes": [{"name": "box"}],
+            "scenes": [{"nodes": [0]}]
+        }"#;
+        let mut scene = load_slice(json.as_bytes()).expect("Failed to load gltf");
+        assert_eq!(scene.animations.len(), 1);
+        let animation = &scene.animations[0];
+        assert_eq!(animation.name, "move");
+        assert_eq!(animation.channels.len(), 3);
+        assert_eq!(animation.duration(), 1.0);
+
+        scene.animate(0.5);
+        let node = &scene.nodes[0];
+        assert_eq!(node.translation, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(node.rotation, UnitQuaternion::identity());
+        assert!((node.scale - Vector3::repeat(1.5)).norm() < 1e-6);
+        scene.animate(1.0);
+        assert!((scene.nodes[0].rotation.angle() - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_load_skin() {
+        // 缓冲区依次为三角形的坐标、JOINTS_0（u16）、WEIGHTS_0 和两个逆绑定矩阵
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 236, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAAAAAAAAAAEAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAAAAAACAPwAAgD8AAAAAAAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAIC/AAAAAAAAgD8="}],
+            "bufferViews": [{"buffer": 0, "byteLength": 236}],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+                {"bufferView": 0, "byteOffset": 36, "componentType": 5123, "count": 3, "type": "VEC4"},
+                {"bufferView": 0, "byteOffset": 60, "componentType": 5126, "count": 3, "type": "VEC4"},
+                {"bufferView": 0, "byteOffset": 108, "componentType": 5126, "count": 2, "type": "MAT4"}
+            ],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0, "JOINTS_0": 1, "WEIGHTS_0": 2}}]}],
+            "skins": [{"name": "arm", "joints": [1, 2], "inverseBindMatrices": 3}],
+            "nodes": [
+                {"name": "body", "mesh": 0, "skin": 0},
+                {"name": "root", "children": [2]},
+                {"name": "tip", "translation": [0, 1, 0]}
+            ],
+            "scenes": [{"nodes": [0, 1]}]
+        }"#;
+        let mut scene = load_slice(json.as_bytes()).expect("Failed to load gltf");
+        assert_eq!(scene.nodes[0].skin, Some(0));
+        let skin = &scene.skins[0];
+        assert_eq!(skin.name, "arm");
+        assert_eq!(skin.joints, vec![1, 2]);
+        assert_eq!(
+            skin.inverse_bind_matrices[1],
+            Matrix4::new_translation(&Vector3::new(0.0, -1.0, 0.0))
+        );
+        let mesh = &scene.meshes[0];
+        assert!(mesh.is_skinned());
+        assert_eq!(mesh.joints[2], [1, 0, 0, 0]);
+        assert_eq!(mesh.weights[2], Vector4::x());
+
+        // 绑定姿势下网格不变，移动骨骼之后只有绑定到它的顶点跟着移动
+        let calls = scene.draw_calls();
+        assert_eq!(scene.meshes[0].skin(&calls[0].joints), scene.meshes[0]);
+        scene.nodes[2].translation.x = 2.0;
+        let posed = scene.meshes[0].skin(&scene.draw_calls()[0].joints);
+        assert_eq!(posed.positions[1], Point3::new(1.0, 0.0, 0.0));
+        assert!((posed.positions[2] - Point3::new(2.0, 1.0, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_load_morph_targets() {
+        // 缓冲区依次为三角形的坐标、两个形变目标的坐标偏移、时间 [0, 1] 和两个关键帧的权重
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 132, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAIA/AAAAAAAAAAAAAIA/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAACAPwAAgD8AAAAA"}],
+            "bufferViews": [{"buffer": 0, "byteLength": 132}],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+                {"bufferView": 0, "byteOffset": 36, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 1], "max": [0, 0, 1]},
+                {"bufferView": 0, "byteOffset": 72, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 0, 0]},
+                {"bufferView": 0, "byteOffset": 108, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0], "max": [1]},
+                {"bufferView": 0, "byteOffset": 116, "componentType": 5126, "count": 4, "type": "SCALAR"}
+            ],
+            "meshes": [{
+                "primitives": [{"attributes": {"POSITION": 0}, "targets": [{"POSITION": 1}, {"POSITION": 2}]}],
+                "weights": [0.5, 0]
+            }],
+            "animations": [{
+                "samplers": [{"input": 3, "output": 4}],
+                "channels": [{"sampler": 0, "target": {"node": 0, "path": "weights"}}]
+            }],
+            "nodes": [{"name": "face", "mesh": 0}],
+            "scenes": [{"nodes": [0]}]
+        }"#;
+        let mut scene = load_slice(json.as_bytes()).expect("Failed to load gltf");
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.targets.len(), 2);
+        assert_eq!(mesh.targets[0].positions, vec![Vector3::z(); 3]);
+        assert!(mesh.targets[1].normals.is_empty());
+        // 节点使用网格的默认权重
+        assert_eq!(scene.nodes[0].morph_weights, vec![0.5, 0.0]);
+        let call = &scene.draw_calls()[0];
+        assert_eq!(
+            scene.posed_mesh(call).positions[0],
+            Point3::new(0.0, 0.0, 0.5)
+        );
+
+        // 形变权重的动画按目标拆分成两个通道
+        assert_eq!(scene.animations[0].channels.len(), 2);
+        scene.animate(0.25);
+        assert_eq!(scene.nodes[0].morph_weights, vec![0.25, 0.75]);
+        let posed = scene.posed_mesh(&scene.draw_calls()[0]).into_owned();
+        assert!((posed.positions[2] - Point3::new(0.75, 1.0, 0.25)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        assert!(matches!(
+            load(resource("missing.gltf")),
+            Err(Error::Gltf(_))
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+pub mod cache;
+pub mod gltf;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use crate::mesh::Mesh;
+use std::fmt;
+use std::path::Path;
+
+/// 模型文件读写错误
+#[derive(Debug)]
+pub enum Error {
+    /// 文件读写失败
+    Io(std::io::Error),
+    /// gltf 解析失败
+    Gltf(::gltf::Error),
+    /// 文件内容不符合格式要求
+    Format(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Gltf(e) => write!(f, "gltf error: {}", e),
+            Error::Format(msg) => write!(f, "format error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<::gltf::Error> for Error {
+    fn from(e: ::gltf::Error) -> Self {
+        Error::Gltf(e)
+    }
+}
+
+/// 按扩展名读取一个网格，支持 obj、ply、stl、gltf、glb 和 rmesh
+/// gltf 中的多个网格会合并成一个，每个网格成为一个分组
+pub fn load_mesh<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "obj" => obj::load_mesh(path),
+        "ply" => ply::load(path),
+        "stl" => stl::load(path, None),
+        "gltf" | "glb" => {
+            let scene = gltf::load(path)?;
+            if scene.meshes.len() == 1 {
+                return Ok(scene.meshes.into_iter().next().unwrap());
+            }
+            let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("mesh");
+            let mut mesh = Mesh::new(name);
+            for m in &scene.meshes {
+                mesh.append(m, &m.name, None);
+            }
+            Ok(mesh)
+        }
+        cache::EXTENSION => cache::load(path),
+        _ => Err(Error::Format(format!(
+            "unsupported mesh file extension {:?}",
+            extension
+        ))),
+    }
+}
@@ -0,0 +1,604 @@
+use super::{Error, Result};
+use crate::material::{roughness_to_shininess, shininess_to_roughness, Material};
+use crate::mesh::{Group, Mesh};
+use crate::scene::{Node, Scene};
+use image::DynamicImage;
+use nalgebra::{Point3, Vector2, Vector3, Vector4};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// 读取 obj 文件以及它引用的 mtl 材质和贴图
+/// 返回的场景只有一个网格和一个根节点
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let parsed = parse_obj(BufReader::new(File::open(path)?))?;
+
+    let mut scene = Scene::default();
+    let mut materials: Vec<Material> = parsed
+        .material_names
+        .iter()
+        .map(|name| Material {
+            name: name.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut image_paths: HashMap<String, usize> = HashMap::new();
+
+    for lib in &parsed.mtllibs {
+        let mtl_path = dir.join(lib);
+        let file = match File::open(&mtl_path) {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!("failed to open mtllib {:?}: {}", mtl_path, e);
+                continue;
+            }
+        };
+        for (material, maps) in parse_mtl(BufReader::new(file))? {
+            let Some(slot) = materials.iter_mut().find(|m| m.name == material.name) else {
+                continue;
+            };
+            *slot = material;
+            for (kind, map) in maps {
+                let index = match image_paths.get(&map) {
+                    Some(&index) => index,
+                    None => match image::open(dir.join(&map)) {
+                        Ok(image) => {
+                            scene.images.push(image);
+                            image_paths.insert(map.clone(), scene.images.len() - 1);
+                            scene.images.len() - 1
+                        }
+                        Err(e) => {
+                            log::warn!("failed to load texture {:?}: {}", map, e);
+                            continue;
+                        }
+                    },
+                };
+                match kind {
+                    TextureMap::BaseColor => slot.base_color_texture = Some(index),
+                    TextureMap::Normal => slot.normal_texture = Some(index),
+                    TextureMap::Emissive => slot.emissive_texture = Some(index),
+                }
+            }
+        }
+    }
+
+    let mut node = Node::new(&parsed.mesh.name);
+    node.mesh = Some(0);
+    scene.nodes.push(node);
+    scene.roots.push(0);
+    scene.meshes.push(parsed.mesh);
+    scene.materials = materials;
+    Ok(scene)
+}
+
+/// 只读取 obj 中的网格，忽略材质
+pub fn load_mesh<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// 从 reader 读取 obj 网格
+/// 分组的材质索引按照 usemtl 第一次出现的顺序编号
+pub fn read<R: BufRead>(reader: R) -> Result<Mesh> {
+    Ok(parse_obj(reader)?.mesh)
+}
+
+/// 将网格和材质写入 obj 文件
+/// 有材质时在同一目录写入同名的 .mtl，材质引用的贴图保存为 png
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    mesh: &Mesh,
+    materials: &[Material],
+    images: &[DynamicImage],
+) -> Result<()> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("mesh")
+        .to_string();
+
+    // 先保存贴图和 mtl，出错时不会留下写了一半的 obj
+    let mtl_name = format!("{}.mtl", stem);
+    if !materials.is_empty() {
+        // 保存材质引用到的贴图
+        let mut maps = HashMap::new();
+        for index in materials.iter().flat_map(material_textures) {
+            if maps.contains_key(&index) {
+                continue;
+            }
+            let image = images
+                .get(index)
+                .ok_or_else(|| Error::Format(format!("texture {} out of range", index)))?;
+            let name = format!("{}_{}.png", stem, index);
+            image
+                .save(dir.join(&name))
+                .map_err(|e| Error::Format(format!("failed to save texture {}: {}", name, e)))?;
+            maps.insert(index, name);
+        }
+
+        let mut mtl = BufWriter::new(File::create(dir.join(&mtl_name))?);
+        write_mtl(materials, &maps, &mut mtl)?;
+        mtl.flush()?;
+    }
+    let mtllib = (!materials.is_empty()).then_some(mtl_name.as_str());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_obj(mesh, mtllib, materials, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 写入 obj 文本
+/// 每个顶点写一组 v/vt/vn，面直接引用顶点索引，所以重新读取后顶点和面保持不变；
+/// 分组的材质不在 materials 中时（比如读取 obj 时没有保留材质列表）不写 usemtl
+pub fn write_obj<W: Write>(
+    mesh: &Mesh,
+    mtllib: Option<&str>,
+    materials: &[Material],
+    writer: &mut W,
+) -> Result<()> {
+    writeln!(writer, "# {} vertices", mesh.vertex_count())?;
+    writeln!(writer, "# {} faces", mesh.face_count())?;
+    if let Some(mtllib) = mtllib {
+        writeln!(writer, "mtllib {}", mtllib)?;
+    }
+    if !mesh.name.is_empty() {
+        writeln!(writer, "o {}", mesh.name)?;
+    }
+
+    for (i, p) in mesh.positions.iter().enumerate() {
+        match mesh.colors.get(i) {
+            Some(c) => writeln!(writer, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.x, c.y, c.z)?,
+            None => writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?,
+        }
+    }
+    for uv in &mesh.uvs {
+        writeln!(writer, "vt {} {}", uv.x, uv.y)?;
+    }
+    for n in &mesh.normals {
+        writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+    }
+
+    let has_uv = !mesh.uvs.is_empty();
+    let has_normal = !mesh.normals.is_empty();
+    let write_faces = |writer: &mut W, faces: &[Vec<usize>]| -> Result<()> {
+        for face in faces {
+            write!(writer, "f")?;
+            for &i in face {
+                let i = i + 1;
+                match (has_uv, has_normal) {
+                    (true, true) => write!(writer, " {}/{}/{}", i, i, i)?,
+                    (true, false) => write!(writer, " {}/{}", i, i)?,
+                    (false, true) => write!(writer, " {}//{}", i, i)?,
+                    (false, false) => write!(writer, " {}", i)?,
+                }
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    };
+
+    if mesh.groups.is_empty() {
+        write_faces(writer, &mesh.faces)?;
+    }
+    for group in &mesh.groups {
+        writeln!(writer, "g {}", group.name)?;
+        if let Some(material) = group.material.and_then(|m| materials.get(m)) {
+            writeln!(writer, "usemtl {}", material.name)?;
+        }
+        write_faces(writer, &mesh.faces[group.start..group.start + group.count])?;
+    }
+    Ok(())
+}
+
+/// 写入 mtl 文本，maps 为贴图索引到文件名的映射
+/// 除了传统的 Phong 参数，还写入 PBR 扩展的 Pr/Pm
+pub fn write_mtl<W: Write>(
+    materials: &[Material],
+    maps: &HashMap<usize, String>,
+    writer: &mut W,
+) -> Result<()> {
+    for material in materials {
+        let c = material.base_color;
+        let e = material.emissive;
+        writeln!(writer, "newmtl {}", material.name)?;
+        writeln!(writer, "Kd {} {} {}", c.x, c.y, c.z)?;
+        writeln!(writer, "d {}", c.w)?;
+        writeln!(writer, "Ke {} {} {}", e.x, e.y, e.z)?;
+        writeln!(writer, "Ns {}", roughness_to_shininess(material.roughness))?;
+        writeln!(writer, "Pr {}", material.roughness)?;
+        writeln!(writer, "Pm {}", material.metallic)?;
+        let texture = |index: Option<usize>| index.and_then(|i| maps.get(&i));
+        if let Some(map) = texture(material.base_color_texture) {
+            writeln!(writer, "map_Kd {}", map)?;
+        }
+        if let Some(map) = texture(material.normal_texture) {
+            writeln!(writer, "norm {}", map)?;
+        }
+        if let Some(map) = texture(material.emissive_texture) {
+            writeln!(writer, "map_Ke {}", map)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// 读取 mtl 材质，贴图只在 load 中随 obj 一起加载
+pub fn read_mtl<R: BufRead>(reader: R) -> Result<Vec<Material>> {
+    Ok(parse_mtl(reader)?.into_iter().map(|(m, _)| m).collect())
+}
+
+fn material_textures(material: &Material) -> impl Iterator<Item = usize> {
+    [
+        material.base_color_texture,
+        material.normal_texture,
+        material.emissive_texture,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// obj 的解析结果
+struct ParsedObj {
+    mesh: Mesh,
+    /// usemtl 引用的材质名，分组的材质索引指向这里
+    material_names: Vec<String>,
+    mtllibs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextureMap {
+    BaseColor,
+    Normal,
+    Emissive,
+}
+
+fn parse_floats<const N: usize>(tokens: &[&str], line: usize) -> Result<[f32; N]> {
+    let mut v = [0.0; N];
+    if tokens.len() < N {
+        return Err(Error::Format(format!(
+            "line {}: expect {} numbers",
+            line, N
+        )));
+    }
+    for (i, t) in tokens.iter().take(N).enumerate() {
+        v[i] = t
+            .parse()
+            .map_err(|_| Error::Format(format!("line {}: invalid number {:?}", line, t)))?;
+    }
+    Ok(v)
+}
+
+/// 解析 obj 中的索引，支持从 1 开始的正索引和从末尾开始的负索引
+fn parse_index(token: &str, count: usize, line: usize) -> Result<usize> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| Error::Format(format!("line {}: invalid index {:?}", line, token)))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(Error::Format(format!(
+            "line {}: index {} out of range {}",
+            line, index, count
+        )));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_obj<R: BufRead>(reader: R) -> Result<ParsedObj> {
+    let mut positions: Vec<Point3<f32>> = Vec::new();
+    let mut colors: Vec<Vector4<f32>> = Vec::new();
+    let mut uvs: Vec<Vector2<f32>> = Vec::new();
+    let mut normals: Vec<Vector3<f32>> = Vec::new();
+
+    let mut mesh = Mesh::new("");
+    let mut material_names: Vec<String> = Vec::new();
+    let mut mtllibs = Vec::new();
+    // (v, vt, vn) -> 网格顶点索引
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), usize> = HashMap::new();
+    let mut keys: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+
+    let mut group_name = String::new();
+    let mut material: Option<usize> = None;
+    let mut group_start = 0;
+
+    // 当前分组有面时才结束分组
+    let close_group = |mesh: &mut Mesh, name: &str, material: Option<usize>, start: usize| {
+        if mesh.faces.len() > start {
+            mesh.groups.push(Group {
+                name: name.to_string(),
+                material,
+                start,
+                count: mesh.faces.len() - start,
+            });
+        }
+    };
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = i + 1;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(args, line_number)?;
+                positions.push(Point3::new(x, y, z));
+                // 部分工具会在坐标后面追加顶点颜色
+                if args.len() >= 6 {
+                    let [r, g, b] = parse_floats::<3>(&args[3..], line_number)?;
+                    colors.push(Vector4::new(r, g, b, 1.0));
+                }
+            }
+            "vt" => {
+                let u = parse_floats::<1>(args, line_number)?[0];
+                let v = if args.len() > 1 {
+                    parse_floats::<1>(&args[1..], line_number)?[0]
+                } else {
+                    0.0
+                };
+                uvs.push(Vector2::new(u, v));
+            }
+            "vn" => normals.push(Vector3::from(parse_floats::<3>(args, line_number)?)),
+            "f" => {
+                if args.len() < 3 {
+                    return Err(Error::Format(format!(
+                        "line {}: face needs at least 3 vertices",
+                        line_number
+                    )));
+                }
+                let mut face = Vec::with_capacity(args.len());
+                for arg in args {
+                    let mut parts = arg.split('/');
+                    let v = parse_index(parts.next().unwrap_or(""), positions.len(), line_number)?;
+                    let vt = match parts.next() {
+                        Some(t) if !t.is_empty() => Some(parse_index(t, uvs.len(), line_number)?),
+                        _ => None,
+                    };
+                    let vn = match parts.next() {
+                        Some(t) if !t.is_empty() => {
+                            Some(parse_index(t, normals.len(), line_number)?)
+                        }
+                        _ => None,
+                    };
+                    let key = (v, vt, vn);
+                    let index = *vertices.entry(key).or_insert_with(|| {
+                        keys.push(key);
+                        keys.len() - 1
+                    });
+                    face.push(index);
+                }
+                mesh.faces.push(face);
+            }
+            "o" => {
+                if mesh.name.is_empty() {
+                    mesh.name = args.join(" ");
+                }
+            }
+            "g" => {
+                close_group(&mut mesh, &group_name, material, group_start);
+                group_start = mesh.faces.len();
+                group_name = args.join(" ");
+            }
+            "usemtl" => {
+                close_group(&mut mesh, &group_name, material, group_start);
+                group_start = mesh.faces.len();
+                let name = args.join(" ");
+                material = Some(match material_names.iter().position(|m| *m == name) {
+                    Some(index) => index,
+                    None => {
+                        material_names.push(name);
+                        material_names.len() - 1
+                    }
+                });
+            }
+            "mtllib" => mtllibs.extend(args.iter().map(|s| s.to_string())),
+            // 平滑组、线、点等暂不支持
+            _ => log::debug!("line {}: ignore {:?}", line_number, keyword),
+        }
+    }
+    close_group(&mut mesh, &group_name, material, group_start);
+    // 只有一个没有名字和材质的分组时，等价于没有分组
+    if mesh.groups.len() == 1 && mesh.groups[0].name.is_empty() && mesh.groups[0].material.is_none()
+    {
+        mesh.groups.clear();
+    }
+
+    // 按照 (v, vt, vn) 组合展开顶点属性
+    let has_uv = keys.iter().any(|k| k.1.is_some());
+    let has_normal = keys.iter().any(|k| k.2.is_some());
+    let has_color = colors.len() == positions.len() && !colors.is_empty();
+    for &(v, vt, vn) in &keys {
+        mesh.positions.push(positions[v]);
+        if has_uv {
+            mesh.uvs.push(vt.map_or_else(Vector2::zeros, |t| uvs[t]));
+        }
+        if has_normal {
+            mesh.normals
+                .push(vn.map_or_else(Vector3::zeros, |n| normals[n]));
+        }
+        if has_color {
+            mesh.colors.push(colors[v]);
+        }
+    }
+
+    Ok(ParsedObj {
+        mesh,
+        material_names,
+        mtllibs,
+    })
+}
+
+/// mtl 中的材质以及它引用的贴图文件
+type MtlEntry = (Material, Vec<(TextureMap, String)>);
+
+fn parse_mtl<R: BufRead>(reader: R) -> Result<Vec<MtlEntry>> {
+    let mut materials: Vec<MtlEntry> = Vec::new();
+    // 是否显式指定了 PBR 粗糙度，没有时由 Ns 换算
+    let mut has_roughness = false;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = i + 1;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            materials.push((
+                Material {
+                    name: args.join(" "),
+                    metallic: 0.0,
+                    ..Default::default()
+                },
+                Vec::new(),
+            ));
+            has_roughness = false;
+            continue;
+        }
+        let Some((material, maps)) = materials.last_mut() else {
+            continue;
+        };
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = parse_floats::<3>(args, line_number)?;
+                material.base_color = Vector4::new(r, g, b, material.base_color.w);
+            }
+            "d" => material.base_color.w = parse_floats::<1>(args, line_number)?[0],
+            "Tr" => material.base_color.w = 1.0 - parse_floats::<1>(args, line_number)?[0],
+            "Ke" => material.emissive = Vector3::from(parse_floats::<3>(args, line_number)?),
+            "Ns" if !has_roughness => {
+                material.roughness =
+                    shininess_to_roughness(parse_floats::<1>(args, line_number)?[0])
+            }
+            "Pr" => {
+                material.roughness = parse_floats::<1>(args, line_number)?[0];
+                has_roughness = true;
+            }
+            "Pm" => material.metallic = parse_floats::<1>(args, line_number)?[0],
+            // 贴图的文件名在最后，前面可能有 -s -o 之类的选项
+            "map_Kd" | "map_Bump" | "map_bump" | "bump" | "norm" | "map_Ke" => {
+                if let Some(file) = args.last() {
+                    let kind = match keyword {
+                        "map_Kd" => TextureMap::BaseColor,
+                        "map_Ke" => TextureMap::Emissive,
+                        _ => TextureMap::Normal,
+                    };
+                    maps.push((kind, file.to_string()));
+                }
+            }
+            _ => log::debug!("line {}: ignore {:?}", line_number, keyword),
+        }
+    }
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn resource(name: &str) -> String {
+        format!("{}/../resource/obj/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_round_trip_african_head() {
+        let mesh = load_mesh(resource("african_head.obj")).expect("Failed to load OBJ file");
+        assert_eq!(mesh.face_count(), 2492);
+        assert_eq!(mesh.groups.len(), 1);
+        assert_eq!(mesh.groups[0].name, "head");
+        assert_eq!(mesh.uvs.len(), mesh.vertex_count());
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+
+        let mut bytes = Vec::new();
+        write_obj(&mesh, None, &[], &mut bytes).unwrap();
+        let reloaded = read(Cursor::new(bytes)).unwrap();
+        assert_eq!(reloaded, mesh);
+    }
+
+    #[test]
+    fn test_round_trip_materials() {
+        let mut mesh = read(Cursor::new(
+            "o quad\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\ng a\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 3 4\n",
+        ))
+        .unwrap();
+        mesh.triangulate();
+        assert_eq!(mesh.groups.len(), 2);
+        assert_eq!(mesh.groups[1].material, Some(1));
+
+        let mut red = Material {
+            name: "red".to_string(),
+            base_color: Vector4::new(1.0, 0.0, 0.0, 0.5),
+            metallic: 0.25,
+            roughness: 0.5,
+            ..Default::default()
+        };
+        red.base_color_texture = Some(0);
+        let blue = Material {
+            name: "blue".to_string(),
+            base_color: Vector4::new(0.0, 0.0, 1.0, 1.0),
+            ..Default::default()
+        };
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            2,
+            2,
+            image::Rgba([255, 0, 0, 255]),
+        ));
+
+        let dir = std::env::temp_dir().join("render_obj_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("quad.obj");
+        save(&path, &mesh, &[red.clone(), blue.clone()], &[image]).unwrap();
+
+        let scene = load(&path).unwrap();
+        assert_eq!(scene.meshes[0], mesh);
+        assert_eq!(scene.materials.len(), 2);
+        assert_eq!(scene.materials[0], red);
+        assert_eq!(scene.materials[1].base_color, blue.base_color);
+        assert_eq!(scene.images.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_without_materials() {
+        // 只读取网格时材质列表被丢弃，保存时跳过 usemtl
+        let text = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\ng a\nusemtl red\nf 1 2 3\ng b\nusemtl blue\nf 1 3 4\n";
+        let mesh = read(Cursor::new(text)).unwrap();
+        assert_eq!(mesh.groups[1].material, Some(1));
+        let path = std::env::temp_dir().join("render_obj_no_materials.obj");
+        save(&path, &mesh, &[], &[]).unwrap();
+        let reloaded = load_mesh(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.positions, mesh.positions);
+        assert_eq!(reloaded.faces, mesh.faces);
+        let names: Vec<_> = reloaded.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert!(reloaded.groups.iter().all(|g| g.material.is_none()));
+    }
+
+    #[test]
+    fn test_read_index_forms() {
+        let mesh = read(Cursor::new(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf -4//1 -3//1 -2//1 -1//1\n",
+        ))
+        .unwrap();
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2, 3]]);
+        assert_eq!(mesh.normals.len(), 4);
+        assert!(mesh.uvs.is_empty());
+        assert!(mesh.groups.is_empty());
+    }
+
+    #[test]
+    fn test_read_invalid_index() {
+        let result = read(Cursor::new("v 0 0 0\nv 1 0 0\nf 1 2 3\n"));
+        assert!(matches!(result, Err(Error::Format(_))));
+    }
+}
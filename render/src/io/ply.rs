@@ -0,0 +1,507 @@
+use super::{Error, Result};
+use crate::mesh::Mesh;
+use nalgebra::{Point3, Vector2, Vector3, Vector4};
+use std::path::Path;
+
+/// 读取 PLY 文件
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    read(&bytes)
+}
+
+/// 从内存读取 PLY，支持 ascii、binary_little_endian 和 binary_big_endian
+///
+/// 读取 vertex 元素的坐标、法向量、颜色、纹理坐标以及 face 元素的顶点索引，
+/// 其它元素会被跳过。没有 face 元素时得到的是只有顶点的点云
+pub fn read(bytes: &[u8]) -> Result<Mesh> {
+    let (header, body) = parse_header(bytes)?;
+    let mut source = match header.format {
+        PlyFormat::Ascii => {
+            let text = std::str::from_utf8(body)
+                .map_err(|e| Error::Format(format!("ascii ply is not utf8: {}", e)))?;
+            Source::Ascii(text.split_ascii_whitespace())
+        }
+        PlyFormat::BinaryLittleEndian => Source::Binary {
+            bytes: body,
+            pos: 0,
+            big_endian: false,
+        },
+        PlyFormat::BinaryBigEndian => Source::Binary {
+            bytes: body,
+            pos: 0,
+            big_endian: true,
+        },
+    };
+
+    let mut mesh = Mesh::new("");
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => read_vertices(element, &mut source, &mut mesh)?,
+            "face" => read_faces(element, &mut source, &mut mesh)?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        source.read_property(property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let count = mesh.vertex_count();
+    if let Some(i) = mesh.faces.iter().flatten().find(|&&i| i >= count) {
+        return Err(Error::Format(format!(
+            "face index {} out of range {}",
+            i, count
+        )));
+    }
+    Ok(mesh)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// 属性的标量类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => ScalarType::I8,
+            "uchar" | "uint8" => ScalarType::U8,
+            "short" | "int16" => ScalarType::I16,
+            "ushort" | "uint16" => ScalarType::U16,
+            "int" | "int32" => ScalarType::I32,
+            "uint" | "uint32" => ScalarType::U32,
+            "float" | "float32" => ScalarType::F32,
+            "double" | "float64" => ScalarType::F64,
+            _ => return Err(Error::Format(format!("unknown ply type {:?}", name))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    /// 列表属性：长度的类型，元素的类型
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|p| names.contains(&p.name.as_str()))
+    }
+}
+
+struct Header {
+    format: PlyFormat,
+    elements: Vec<Element>,
+}
+
+/// 解析文件头，返回文件头和数据部分
+fn parse_header(bytes: &[u8]) -> Result<(Header, &[u8])> {
+    let end = find_end_header(bytes)
+        .ok_or_else(|| Error::Format("ply without end_header".to_string()))?;
+    let text = std::str::from_utf8(&bytes[..end])
+        .map_err(|e| Error::Format(format!("ply header is not utf8: {}", e)))?;
+
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(Error::Format("missing ply magic".to_string()));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(Error::Format(format!("unknown ply format {:?}", name))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| Error::Format(format!("invalid element count {:?}", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, name] => {
+                last_element(&mut elements)?.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyKind::List(
+                        ScalarType::parse(count_type)?,
+                        ScalarType::parse(item_type)?,
+                    ),
+                });
+            }
+            ["property", ty, name] => {
+                last_element(&mut elements)?.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyKind::Scalar(ScalarType::parse(ty)?),
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | ["end_header"] | [] => {}
+            _ => return Err(Error::Format(format!("invalid ply header line {:?}", line))),
+        }
+    }
+
+    let format = format.ok_or_else(|| Error::Format("ply without format".to_string()))?;
+    Ok((Header { format, elements }, &bytes[end..]))
+}
+
+/// 逐行查找内容为 end_header 的行，返回该行的结束位置
+/// 注释等其他行中出现的 end_header 不算文件头结束
+fn find_end_header(bytes: &[u8]) -> Option<usize> {
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = bytes[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |i| start + i + 1);
+        if bytes[start..end].trim_ascii() == b"end_header" {
+            return Some(end);
+        }
+        start = end;
+    }
+    None
+}
+
+fn last_element(elements: &mut [Element]) -> Result<&mut Element> {
+    elements
+        .last_mut()
+        .ok_or_else(|| Error::Format("property before element".to_string()))
+}
+
+/// 数据读取，ascii 按空白分隔读取，二进制按类型大小读取
+enum Source<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        pos: usize,
+        big_endian: bool,
+    },
+}
+
+/// 属性值，列表属性读取为多个值
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl Source<'_> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64> {
+        match self {
+            Source::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| Error::Format("unexpected end of ply data".to_string()))?;
+                token
+                    .parse()
+                    .map_err(|_| Error::Format(format!("invalid ply number {:?}", token)))
+            }
+            Source::Binary {
+                bytes,
+                pos,
+                big_endian,
+            } => {
+                let size = ty.size();
+                let data = bytes
+                    .get(*pos..*pos + size)
+                    .ok_or_else(|| Error::Format("unexpected end of ply data".to_string()))?;
+                *pos += size;
+                let mut buf = [0u8; 8];
+                buf[..size].copy_from_slice(data);
+                if *big_endian {
+                    buf[..size].reverse();
+                }
+                // 统一转换成小端字节序再解析
+                Ok(match ty {
+                    ScalarType::I8 => buf[0] as i8 as f64,
+                    ScalarType::U8 => buf[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+                    ScalarType::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    ScalarType::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    ScalarType::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                    ScalarType::F64 => f64::from_le_bytes(buf),
+                })
+            }
+        }
+    }
+
+    fn read_property(&mut self, property: &Property) -> Result<Value> {
+        match property.kind {
+            PropertyKind::Scalar(ty) => Ok(Value::Scalar(self.read(ty)?)),
+            PropertyKind::List(count_type, item_type) => {
+                let count = self.read(count_type)? as usize;
+                let items = (0..count)
+                    .map(|_| self.read(item_type))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Value::List(items))
+            }
+        }
+    }
+}
+
+fn read_vertices(element: &Element, source: &mut Source, mesh: &mut Mesh) -> Result<()> {
+    let find = |names: &[&str]| element.property(names);
+    let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+    let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+    let color = [
+        find(&["red", "r", "diffuse_red"]),
+        find(&["green", "g", "diffuse_green"]),
+        find(&["blue", "b", "diffuse_blue"]),
+    ];
+    let alpha = find(&["alpha", "a"]);
+    let uv = [
+        find(&["u", "s", "texture_u", "texture_s"]),
+        find(&["v", "t", "texture_v", "texture_t"]),
+    ];
+    if position.iter().any(Option::is_none) {
+        return Err(Error::Format("ply vertex without x y z".to_string()));
+    }
+    let has_normal = normal.iter().all(Option::is_some);
+    let has_color = color.iter().all(Option::is_some);
+    let has_uv = uv.iter().all(Option::is_some);
+
+    // 整数类型的颜色按照最大值归一化到 0~1
+    let color_scale = |index: usize| -> f64 {
+        match element.properties[index].kind {
+            PropertyKind::Scalar(ScalarType::U8) => 255.0,
+            PropertyKind::Scalar(ScalarType::U16) => 65535.0,
+            _ => 1.0,
+        }
+    };
+
+    let mut values = vec![0.0; element.properties.len()];
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            if let Value::Scalar(v) = source.read_property(property)? {
+                values[i] = v;
+            }
+        }
+        let get = |i: Option<usize>| values[i.unwrap()] as f32;
+        mesh.positions.push(Point3::new(
+            get(position[0]),
+            get(position[1]),
+            get(position[2]),
+        ));
+        if has_normal {
+            mesh.normals
+                .push(Vector3::new(get(normal[0]), get(normal[1]), get(normal[2])));
+        }
+        if has_color {
+            let channel = |i: Option<usize>| (values[i.unwrap()] / color_scale(i.unwrap())) as f32;
+            let a = alpha.map_or(1.0, |a| channel(Some(a)));
+            mesh.colors.push(Vector4::new(
+                channel(color[0]),
+                channel(color[1]),
+                channel(color[2]),
+                a,
+            ));
+        }
+        if has_uv {
+            mesh.uvs.push(Vector2::new(get(uv[0]), get(uv[1])));
+        }
+    }
+    Ok(())
+}
+
+fn read_faces(element: &Element, source: &mut Source, mesh: &mut Mesh) -> Result<()> {
+    let indices = element
+        .property(&["vertex_indices", "vertex_index"])
+        .ok_or_else(|| Error::Format("ply face without vertex_indices".to_string()))?;
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            let value = source.read_property(property)?;
+            if let (true, Value::List(items)) = (i == indices, value) {
+                if items.len() >= 3 {
+                    let face = items
+                        .iter()
+                        .map(|&v| {
+                            if v.is_finite() && v >= 0.0 && v.fract() == 0.0 {
+                                Ok(v as usize)
+                            } else {
+                                Err(Error::Format(format!("invalid face index {}", v)))
+                            }
+                        })
+                        .collect::<Result<_>>()?;
+                    mesh.faces.push(face);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_QUAD: &str = "ply
+format ascii 1.0
+comment handmade quad
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+property uchar flags
+end_header
+0 0 0 0 0 1 255 0 0
+1 0 0 0 0 1 0 255 0
+1 1 0 0 0 1 0 0 255
+0 1 0 0 0 1 255 255 255
+4 0 1 2 3 7
+";
+
+    /// 构造只有顶点坐标和颜色的二进制点云
+    fn binary_point_cloud(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut bytes = format!(
+            "ply\nformat {} 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nproperty ushort red\nproperty ushort green\nproperty ushort blue\nelement camera 1\nproperty double focal\nend_header\n",
+            format
+        )
+        .into_bytes();
+        for (p, c) in [
+            ([1.0f32, 2.0, 3.0], [65535u16, 0, 0]),
+            ([-1.0, -2.0, -3.0], [0, 0, 65535]),
+        ] {
+            for v in p {
+                bytes.extend(if big_endian {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                });
+            }
+            for v in c {
+                bytes.extend(if big_endian {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                });
+            }
+        }
+        bytes.extend(if big_endian {
+            35.0f64.to_be_bytes()
+        } else {
+            35.0f64.to_le_bytes()
+        });
+        bytes
+    }
+
+    #[test]
+    fn test_read_ascii() {
+        let mesh = read(ASCII_QUAD.as_bytes()).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2, 3]]);
+        assert_eq!(mesh.normals[2], Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(mesh.colors[1], Vector4::new(0.0, 1.0, 0.0, 1.0));
+        assert!(mesh.uvs.is_empty());
+    }
+
+    #[test]
+    fn test_read_binary_point_cloud() {
+        for big_endian in [false, true] {
+            let mesh = read(&binary_point_cloud(big_endian)).unwrap();
+            assert_eq!(
+                mesh.positions,
+                vec![Point3::new(1.0, 2.0, 3.0), Point3::new(-1.0, -2.0, -3.0)]
+            );
+            assert_eq!(mesh.colors[0], Vector4::new(1.0, 0.0, 0.0, 1.0));
+            assert_eq!(mesh.colors[1], Vector4::new(0.0, 0.0, 1.0, 1.0));
+            assert!(mesh.faces.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_end_header_in_comment() {
+        // 注释中的 end_header 不能提前结束文件头
+        let mut bytes = binary_point_cloud(false);
+        let comment = b"comment exported before end_header\n";
+        bytes.splice(4..4, comment.iter().copied());
+        let mesh = read(&bytes).unwrap();
+        assert_eq!(mesh.positions[1], Point3::new(-1.0, -2.0, -3.0));
+    }
+
+    #[test]
+    fn test_truncated_binary() {
+        let mut bytes = binary_point_cloud(false);
+        bytes.truncate(bytes.len() - 12);
+        assert!(matches!(read(&bytes), Err(Error::Format(_))));
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let text = ASCII_QUAD.replace("property list uchar int", "property list uchar float");
+        assert!(read(text.as_bytes()).is_ok());
+        for face in ["4 0 1 -2 3 7", "4 0 1 2.5 3 7", "4 0 1 nan 3 7"] {
+            let text = text.replace("4 0 1 2 3 7", face);
+            assert!(
+                matches!(read(text.as_bytes()), Err(Error::Format(_))),
+                "{}",
+                face
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let text = ASCII_QUAD.replace("4 0 1 2 3 7", "3 0 1 9 7");
+        assert!(matches!(read(text.as_bytes()), Err(Error::Format(_))));
+    }
+}
@@ -0,0 +1,345 @@
+use super::{Error, Result};
+use crate::mesh::normal::NormalWeighting;
+use crate::mesh::Mesh;
+use nalgebra::{Point3, Vector3};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// STL 文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    Binary,
+}
+
+/// 从文件读取 STL，自动识别 ASCII 和二进制格式
+/// weld 为 Some(tolerance) 时，距离在容差内的顶点会被合并
+pub fn load<P: AsRef<Path>>(path: P, weld: Option<f32>) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    read(&bytes, weld)
+}
+
+/// 从内存读取 STL，自动识别 ASCII 和二进制格式
+///
+/// 不合并顶点时，每个三角面拥有独立的三个顶点，顶点法向量就是面法向量；
+/// 合并顶点时，顶点法向量为相邻面法向量按面积加权的平均值
+pub fn read(bytes: &[u8], weld: Option<f32>) -> Result<Mesh> {
+    let (name, facets) = if is_binary(bytes) {
+        (String::new(), read_binary(bytes)?)
+    } else {
+        read_ascii(bytes)?
+    };
+    Ok(build_mesh(&name, &facets, weld))
+}
+
+/// 将网格写入 STL 文件
+pub fn save<P: AsRef<Path>>(mesh: &Mesh, path: P, format: StlFormat) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        StlFormat::Ascii => write_ascii(mesh, &mut writer)?,
+        StlFormat::Binary => write_binary(mesh, &mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 写入 ASCII STL，多边形会被扇形三角化，面法向量由顶点坐标重新计算
+pub fn write_ascii<W: Write>(mesh: &Mesh, writer: &mut W) -> Result<()> {
+    let name = if mesh.name.is_empty() {
+        "mesh"
+    } else {
+        &mesh.name
+    };
+    writeln!(writer, "solid {}", name)?;
+    for triangle in mesh.triangles() {
+        let [a, b, c] = mesh.triangle_positions(triangle);
+        let n = facet_normal(&[a, b, c]);
+        writeln!(writer, "  facet normal {:e} {:e} {:e}", n.x, n.y, n.z)?;
+        writeln!(writer, "    outer loop")?;
+        for p in [a, b, c] {
+            writeln!(writer, "      vertex {:e} {:e} {:e}", p.x, p.y, p.z)?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {}", name)?;
+    Ok(())
+}
+
+/// 写入二进制 STL
+/// 80 字节文件头 + u32 三角形数量 + 每个三角形 50 字节（法向量、三个顶点、u16 属性）
+pub fn write_binary<W: Write>(mesh: &Mesh, writer: &mut W) -> Result<()> {
+    let mut header = [0u8; 80];
+    let text = format!("binary stl: {}", mesh.name);
+    let len = text.len().min(header.len());
+    header[..len].copy_from_slice(&text.as_bytes()[..len]);
+    writer.write_all(&header)?;
+    writer.write_all(&(mesh.triangle_count() as u32).to_le_bytes())?;
+    for triangle in mesh.triangles() {
+        let points = mesh.triangle_positions(triangle);
+        let n = facet_normal(&points);
+        for v in [n.x, n.y, n.z] {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        for p in points {
+            for v in [p.x, p.y, p.z] {
+                writer.write_all(&v.to_le_bytes())?;
+            }
+        }
+        writer.write_all(&0u16.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// 文件中的一个面
+struct Facet {
+    normal: Vector3<f32>,
+    vertices: Vec<Point3<f32>>,
+}
+
+/// 二进制 STL 的长度由三角形数量唯一确定
+/// ASCII 文件以 solid 开头，但部分导出工具写的二进制文件头也以 solid 开头，所以优先按长度判断
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() >= 84 {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if count.checked_mul(50).and_then(|n| n.checked_add(84)) == Some(bytes.len()) {
+            return true;
+        }
+    }
+    !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Vec<Facet>> {
+    if bytes.len() < 84 {
+        return Err(Error::Format("binary stl shorter than header".to_string()));
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let body = &bytes[84..];
+    if body.len() < count * 50 {
+        return Err(Error::Format(format!(
+            "binary stl expects {} triangles but has {} bytes",
+            count,
+            body.len()
+        )));
+    }
+    let f = |chunk: &[u8], i: usize| {
+        f32::from_le_bytes([
+            chunk[i * 4],
+            chunk[i * 4 + 1],
+            chunk[i * 4 + 2],
+            chunk[i * 4 + 3],
+        ])
+    };
+    Ok(body
+        .chunks_exact(50)
+        .take(count)
+        .map(|chunk| Facet {
+            normal: Vector3::new(f(chunk, 0), f(chunk, 1), f(chunk, 2)),
+            vertices: (1..4)
+                .map(|v| Point3::new(f(chunk, v * 3), f(chunk, v * 3 + 1), f(chunk, v * 3 + 2)))
+                .collect(),
+        })
+        .collect())
+}
+
+fn read_ascii(bytes: &[u8]) -> Result<(String, Vec<Facet>)> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|e| Error::Format(format!("ascii stl is not utf8: {}", e)))?;
+    let mut name = String::new();
+    let mut facets = Vec::new();
+    let mut current: Option<Facet> = None;
+
+    let parse = |tokens: &[&str], line: usize| -> Result<[f32; 3]> {
+        if tokens.len() != 3 {
+            return Err(Error::Format(format!("line {}: expect 3 numbers", line)));
+        }
+        let mut v = [0.0; 3];
+        for (i, t) in tokens.iter().enumerate() {
+            v[i] = t
+                .parse()
+                .map_err(|_| Error::Format(format!("line {}: invalid number {:?}", line, t)))?;
+        }
+        Ok(v)
+    };
+
+    for (i, line) in text.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            Some("solid") => name = tokens[1..].join(" "),
+            Some("facet") => {
+                if tokens.get(1) != Some(&"normal") {
+                    return Err(Error::Format(format!(
+                        "line {}: expect facet normal",
+                        i + 1
+                    )));
+                }
+                let n = parse(&tokens[2..], i + 1)?;
+                current = Some(Facet {
+                    normal: Vector3::from(n),
+                    vertices: Vec::new(),
+                });
+            }
+            Some("vertex") => {
+                let v = parse(&tokens[1..], i + 1)?;
+                current
+                    .as_mut()
+                    .ok_or_else(|| Error::Format(format!("line {}: vertex outside facet", i + 1)))?
+                    .vertices
+                    .push(Point3::from(v));
+            }
+            Some("endfacet") => {
+                let facet = current
+                    .take()
+                    .ok_or_else(|| Error::Format(format!("line {}: unexpected endfacet", i + 1)))?;
+                if facet.vertices.len() < 3 {
+                    return Err(Error::Format(format!(
+                        "line {}: facet has {} vertices",
+                        i + 1,
+                        facet.vertices.len()
+                    )));
+                }
+                facets.push(facet);
+            }
+            // outer loop / endloop / endsolid 以及空行不需要处理
+            _ => {}
+        }
+    }
+    Ok((name, facets))
+}
+
+/// 根据三角形顶点计算面法向量（右手螺旋，逆时针为正面）
+fn facet_normal(points: &[Point3<f32>]) -> Vector3<f32> {
+    (points[1] - points[0])
+        .cross(&(points[2] - points[0]))
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vector3::zeros)
+}
+
+fn build_mesh(name: &str, facets: &[Facet], weld: Option<f32>) -> Mesh {
+    let mut mesh = Mesh::new(name);
+    for facet in facets {
+        // 文件中的法向量可能为 0，这时根据顶点重新计算
+        let normal = facet
+            .normal
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| facet_normal(&facet.vertices));
+        let start = mesh.positions.len();
+        mesh.positions.extend_from_slice(&facet.vertices);
+        mesh.normals
+            .extend(std::iter::repeat_n(normal, facet.vertices.len()));
+        mesh.faces.push((start..mesh.positions.len()).collect());
+    }
+
+    if let Some(tolerance) = weld {
+        // 面法向量不同的顶点不会被合并，先去掉法向量，焊接后再按面积加权重新计算
+        mesh.normals.clear();
+        mesh.weld(tolerance);
+        mesh.compute_normals(NormalWeighting::Area, None);
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_SQUARE: &str = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 0
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+
+    #[test]
+    fn test_read_ascii() {
+        let mesh = read(ASCII_SQUARE.as_bytes(), None).unwrap();
+        assert_eq!(mesh.name, "square");
+        assert_eq!(mesh.vertex_count(), 6);
+        assert_eq!(mesh.face_count(), 2);
+        // 第二个面的法向量为 0，需要重新计算
+        assert_eq!(mesh.normals[5], Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_read_ascii_weld() {
+        let mesh = read(ASCII_SQUARE.as_bytes(), Some(1e-5)).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert!(mesh
+            .normals
+            .iter()
+            .all(|n| (n - Vector3::new(0.0, 0.0, 1.0)).norm() < 1e-6));
+    }
+
+    #[test]
+    fn test_weld_across_cells() {
+        // 两个顶点相距不到容差，但四舍五入到了不同的网格单元
+        let text = ASCII_SQUARE
+            .replacen("vertex 1 1 0", "vertex 1.049 1 0", 1)
+            .replacen("vertex 1 1 0", "vertex 1.051 1 0", 1);
+        let mesh = read(text.as_bytes(), Some(0.1)).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.faces[0][2], mesh.faces[1][1]);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let mesh = read(ASCII_SQUARE.as_bytes(), Some(1e-5)).unwrap();
+        let mut bytes = Vec::new();
+        write_binary(&mesh, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), 84 + 50 * 2);
+        let loaded = read(&bytes, Some(1e-5)).unwrap();
+        assert_eq!(loaded.positions, mesh.positions);
+        assert_eq!(loaded.faces, mesh.faces);
+    }
+
+    #[test]
+    fn test_binary_header_starts_with_solid() {
+        let mut mesh = read(ASCII_SQUARE.as_bytes(), None).unwrap();
+        mesh.name = "solid".to_string();
+        let mut bytes = Vec::new();
+        write_binary(&mesh, &mut bytes).unwrap();
+        bytes[..5].copy_from_slice(b"solid");
+        assert!(is_binary(&bytes));
+        assert_eq!(read(&bytes, None).unwrap().face_count(), 2);
+    }
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let mesh = read(ASCII_SQUARE.as_bytes(), None).unwrap();
+        let mut bytes = Vec::new();
+        write_ascii(&mesh, &mut bytes).unwrap();
+        let loaded = read(&bytes, None).unwrap();
+        assert_eq!(loaded.name, "square");
+        assert_eq!(loaded.positions, mesh.positions);
+        assert_eq!(loaded.normals, mesh.normals);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mesh = read(ASCII_SQUARE.as_bytes(), None).unwrap();
+        let path = std::env::temp_dir().join("render_stl_test.stl");
+        save(&mesh, &path, StlFormat::Binary).unwrap();
+        let loaded = load(&path, None).unwrap();
+        assert_eq!(loaded.positions, mesh.positions);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_ascii() {
+        let text = "solid bad\n  facet normal 0 0 1\n    outer loop\n      vertex 0 0\n";
+        assert!(matches!(read(text.as_bytes(), None), Err(Error::Format(_))));
+    }
+}
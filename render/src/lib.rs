@@ -1,3 +1,17 @@
 pub mod transform;
 pub mod display;
 pub mod geometry;
+pub mod mesh;
+pub mod material;
+pub mod scene;
+pub mod io;
+pub mod math;
+pub mod camera;
+pub mod controller;
+pub mod light;
+pub mod raster;
+pub mod animation;
+pub mod sequence;
+pub mod shader;
+pub mod texture;
+pub mod normal_map;
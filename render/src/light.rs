@@ -0,0 +1,319 @@
+use crate::material::{self, Material};
+use nalgebra::{Point3, Vector3};
+
+/// 光源类型，和 gltf 的 KHR_lights_punctual 对应
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// 平行光，只有方向没有位置
+    Directional,
+    /// 点光源
+    Point,
+    /// 聚光灯，inner_angle 以内强度不衰减，到 outer_angle 衰减为 0（弧度，相对于光照方向）
+    Spot { inner_angle: f32, outer_angle: f32 },
+}
+
+/// 光源，挂载到场景节点上，位置为节点的原点，照射方向为节点的 -z 方向
+#[derive(Debug, Clone, PartialEq)]
+pub struct Light {
+    pub name: String,
+    /// 线性空间的颜色
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    pub kind: LightKind,
+    /// 点光源和聚光灯的作用范围，超过之后强度为 0，None 表示无限远
+    pub range: Option<f32>,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Light {
+            name: String::new(),
+            color: Vector3::repeat(1.0),
+            intensity: 1.0,
+            kind: LightKind::Directional,
+            range: None,
+        }
+    }
+}
+
+impl Light {
+    pub fn directional(color: Vector3<f32>, intensity: f32) -> Self {
+        Light {
+            color,
+            intensity,
+            ..Default::default()
+        }
+    }
+
+    pub fn point(color: Vector3<f32>, intensity: f32, range: Option<f32>) -> Self {
+        Light {
+            color,
+            intensity,
+            kind: LightKind::Point,
+            range,
+            ..Default::default()
+        }
+    }
+
+    pub fn spot(
+        color: Vector3<f32>,
+        intensity: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+        range: Option<f32>,
+    ) -> Self {
+        Light {
+            color,
+            intensity,
+            kind: LightKind::Spot {
+                inner_angle,
+                outer_angle,
+            },
+            range,
+            ..Default::default()
+        }
+    }
+}
+
+/// 放置到世界空间中的光源
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldLight {
+    pub light: Light,
+    pub position: Point3<f32>,
+    /// 光照射的方向（单位向量）
+    pub direction: Vector3<f32>,
+}
+
+impl WorldLight {
+    /// 点 p 处指向光源的单位向量以及到达的辐照度（颜色 * 强度 * 衰减）
+    /// 点在光源范围之外或者聚光灯的锥形之外时辐照度为 0
+    pub fn incident(&self, p: &Point3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+        let light = &self.light;
+        let radiance = light.color * light.intensity;
+        if light.kind == LightKind::Directional {
+            return (-self.direction, radiance);
+        }
+        let offset = self.position - p;
+        let distance = offset.norm();
+        let Some(to_light) = offset.try_normalize(f32::EPSILON) else {
+            return (-self.direction, Vector3::zeros());
+        };
+        // 平方反比衰减，有范围时在范围边缘平滑地降到 0（和 gltf 推荐的方式一致）
+        let mut attenuation = 1.0 / distance.max(1e-4).powi(2);
+        if let Some(range) = light.range {
+            attenuation *= (1.0 - (distance / range).powi(4)).clamp(0.0, 1.0);
+        }
+        if let LightKind::Spot {
+            inner_angle,
+            outer_angle,
+        } = light.kind
+        {
+            let cos_outer = outer_angle.cos();
+            let cos_inner = inner_angle.cos();
+            let cos = (-to_light).dot(&self.direction);
+            let t = ((cos - cos_outer) / (cos_inner - cos_outer).max(1e-4)).clamp(0.0, 1.0);
+            attenuation *= t * t;
+        }
+        (to_light, radiance * attenuation)
+    }
+}
+
+/// Blinn-Phong 光照模型的材质参数，颜色都在线性空间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlinnPhong {
+    /// 环境光反射率，乘以环境光的颜色
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    /// 高光指数，越大高光越小越集中
+    pub shininess: f32,
+}
+
+impl BlinnPhong {
+    /// 由 PBR 材质近似：金属没有漫反射，高光颜色是基础颜色；非金属的高光是 4% 的白色
+    /// 高光指数由粗糙度换算（和 mtl 的 Ns 相同）
+    pub fn from_material(material: &Material) -> Self {
+        let base = material.base_color.xyz();
+        let metallic = material.metallic.clamp(0.0, 1.0);
+        let diffuse = base * (1.0 - metallic);
+        BlinnPhong {
+            ambient: diffuse,
+            diffuse,
+            specular: Vector3::repeat(0.04).lerp(&base, metallic),
+            shininess: material::roughness_to_shininess(material.roughness),
+        }
+    }
+
+    /// 点 p 处反射到 eye 的颜色，normal 为单位法向量，ambient 为环境光的颜色
+    /// 每个光源贡献漫反射 diffuse * (n·l) 和高光 specular * (n·h)^shininess，背对光源时没有贡献
+    pub fn shade(
+        &self,
+        p: &Point3<f32>,
+        normal: &Vector3<f32>,
+        eye: &Point3<f32>,
+        lights: &[WorldLight],
+        ambient: &Vector3<f32>,
+    ) -> Vector3<f32> {
+        let to_eye = (eye - p).try_normalize(f32::EPSILON).unwrap_or(*normal);
+        lights
+            .iter()
+            .fold(self.ambient.component_mul(ambient), |sum, light| {
+                let (to_light, irradiance) = light.incident(p);
+                let n_dot_l = normal.dot(&to_light);
+                if n_dot_l <= 0.0 {
+                    return sum;
+                }
+                let half = (to_light + to_eye)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(*normal);
+                let highlight = normal.dot(&half).max(0.0).powf(self.shininess);
+                let reflected = self.diffuse * n_dot_l + self.specular * highlight;
+                sum + reflected.component_mul(&irradiance)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(light: Light, position: Point3<f32>, direction: Vector3<f32>) -> WorldLight {
+        WorldLight {
+            light,
+            position,
+            direction,
+        }
+    }
+
+    #[test]
+    fn test_directional() {
+        let light = place(
+            Light::directional(Vector3::new(1.0, 0.5, 0.0), 2.0),
+            Point3::new(100.0, 0.0, 0.0),
+            -Vector3::y(),
+        );
+        for p in [Point3::origin(), Point3::new(-50.0, 3.0, 7.0)] {
+            let (l, e) = light.incident(&p);
+            assert_eq!(l, Vector3::y());
+            assert_eq!(e, Vector3::new(2.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_point() {
+        let light = place(
+            Light::point(Vector3::repeat(1.0), 4.0, None),
+            Point3::new(0.0, 2.0, 0.0),
+            -Vector3::z(),
+        );
+        let (l, e) = light.incident(&Point3::origin());
+        assert!((l - Vector3::y()).norm() < 1e-6);
+        assert!((e.x - 1.0).abs() < 1e-6);
+        let (_, far) = light.incident(&Point3::new(0.0, -2.0, 0.0));
+        assert!((far.x - 0.25).abs() < 1e-6);
+
+        let limited = place(
+            Light::point(Vector3::repeat(1.0), 4.0, Some(3.0)),
+            Point3::new(0.0, 2.0, 0.0),
+            -Vector3::z(),
+        );
+        assert!(limited.incident(&Point3::origin()).1.x < 1.0);
+        assert_eq!(
+            limited.incident(&Point3::new(0.0, -2.0, 0.0)).1,
+            Vector3::zeros()
+        );
+    }
+
+    #[test]
+    fn test_spot() {
+        let light = place(
+            Light::spot(
+                Vector3::repeat(1.0),
+                1.0,
+                10f32.to_radians(),
+                20f32.to_radians(),
+                None,
+            ),
+            Point3::new(0.0, 1.0, 0.0),
+            -Vector3::y(),
+        );
+        let at = |angle: f32| {
+            let angle = angle.to_radians();
+            light.incident(&Point3::new(angle.tan(), 0.0, 0.0)).1.x * (1.0 / angle.cos()).powi(2)
+        };
+        assert!((at(0.0) - 1.0).abs() < 1e-5);
+        assert!((at(9.0) - 1.0).abs() < 1e-5);
+        assert!(at(15.0) > 0.0 && at(15.0) < 1.0);
+        assert_eq!(at(25.0), 0.0);
+    }
+
+    #[test]
+    fn test_blinn_phong() {
+        let model = BlinnPhong {
+            ambient: Vector3::repeat(0.5),
+            diffuse: Vector3::new(1.0, 0.0, 0.0),
+            specular: Vector3::new(0.0, 1.0, 0.0),
+            shininess: 32.0,
+        };
+        let p = Point3::origin();
+        let n = Vector3::z();
+        let ambient = Vector3::repeat(0.2);
+        // 没有光源时只有环境光
+        assert_eq!(
+            model.shade(&p, &n, &Point3::new(0.0, 0.0, 1.0), &[], &ambient),
+            Vector3::repeat(0.1)
+        );
+
+        // 从 45 度照过来的平行光，以及正上方的点光源
+        let lights = [
+            place(
+                Light::directional(Vector3::repeat(1.0), 1.0),
+                Point3::origin(),
+                Vector3::new(-1.0, 0.0, -1.0).normalize(),
+            ),
+            place(
+                Light::point(Vector3::repeat(1.0), 4.0, None),
+                Point3::new(0.0, 0.0, 2.0),
+                -Vector3::z(),
+            ),
+        ];
+        let (directional, point) = (&lights[..1], &lights[1..]);
+        let black = Vector3::zeros();
+        // 眼睛在镜面反射方向时高光最强
+        let mirror = Point3::new(-1.0, 0.0, 1.0);
+        let color = model.shade(&p, &n, &mirror, directional, &black);
+        assert!((color.x - 0.5f32.sqrt()).abs() < 1e-5);
+        assert!((color.y - 1.0).abs() < 1e-5);
+        // 偏离反射方向时高光迅速减弱，漫反射不变
+        let aside = model.shade(&p, &n, &Point3::new(0.0, 0.0, 1.0), directional, &black);
+        assert!((aside.x - color.x).abs() < 1e-5);
+        assert!(aside.y < 0.2);
+        // 背对光源时没有漫反射和高光
+        assert_eq!(model.shade(&p, &-n, &mirror, directional, &black), black);
+
+        // 多个光源的贡献相加，点光源按距离衰减
+        let single = model.shade(&p, &n, &mirror, point, &black);
+        assert!((single.x - 1.0).abs() < 1e-5);
+        let both = model.shade(&p, &n, &mirror, &lights, &black);
+        assert!((both - color - single).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_from_material() {
+        let plastic = BlinnPhong::from_material(&Material {
+            base_color: nalgebra::Vector4::new(1.0, 0.0, 0.0, 1.0),
+            metallic: 0.0,
+            roughness: 0.5,
+            ..Default::default()
+        });
+        assert_eq!(plastic.diffuse, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(plastic.specular, Vector3::repeat(0.04));
+        assert!((plastic.shininess - 6.0).abs() < 1e-4);
+        let gold = BlinnPhong::from_material(&Material {
+            base_color: nalgebra::Vector4::new(1.0, 0.8, 0.3, 1.0),
+            ..Default::default()
+        });
+        assert_eq!(gold.diffuse, Vector3::zeros());
+        assert_eq!(gold.specular, Vector3::new(1.0, 0.8, 0.3));
+    }
+}
@@ -0,0 +1,68 @@
+use nalgebra::{Vector3, Vector4};
+
+/// 透明模式，和 gltf 的 alphaMode 对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// PBR 金属度-粗糙度材质
+/// 纹理字段保存的是场景 images 中的索引
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// 基础颜色（线性空间 RGBA）
+    pub base_color: Vector4<f32>,
+    pub base_color_texture: Option<usize>,
+    /// 金属度
+    pub metallic: f32,
+    /// 粗糙度
+    pub roughness: f32,
+    /// 金属度存放在 b 通道，粗糙度存放在 g 通道
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<usize>,
+    pub occlusion_strength: f32,
+    /// 自发光颜色
+    pub emissive: Vector3<f32>,
+    pub emissive_texture: Option<usize>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for Material {
+    /// gltf 规范中的默认材质
+    fn default() -> Self {
+        Material {
+            name: String::new(),
+            base_color: Vector4::repeat(1.0),
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: Vector3::zeros(),
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+/// Phong 高光指数和粗糙度的近似换算
+pub fn shininess_to_roughness(ns: f32) -> f32 {
+    (2.0 / (ns.max(0.0) + 2.0)).sqrt()
+}
+
+pub fn roughness_to_shininess(roughness: f32) -> f32 {
+    let r = roughness.clamp(0.01, 1.0);
+    2.0 / (r * r) - 2.0
+}
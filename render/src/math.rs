@@ -0,0 +1,216 @@
+//! 变换矩阵
+//!
+//! 使用右手坐标系，相机看向 -z，投影之后的 NDC 三个分量都在 [-1, 1] 之间（和 OpenGL 一致）
+
+use nalgebra::{Matrix3, Matrix4, Point3, Vector3, Vector4};
+
+/// 视图矩阵，把世界坐标变换到以 eye 为原点、看向 target 的相机坐标系
+/// up 只需要大致向上，不要求和视线垂直，但不能和视线平行
+pub fn look_at(eye: &Point3<f32>, target: &Point3<f32>, up: &Vector3<f32>) -> Matrix4<f32> {
+    let forward = (target - eye).normalize();
+    let right = forward.cross(up).normalize();
+    let up = right.cross(&forward);
+    #[rustfmt::skip]
+    let matrix = Matrix4::new(
+        right.x, right.y, right.z, -right.dot(&eye.coords),
+        up.x, up.y, up.z, -up.dot(&eye.coords),
+        -forward.x, -forward.y, -forward.z, forward.dot(&eye.coords),
+        0.0, 0.0, 0.0, 1.0,
+    );
+    matrix
+}
+
+/// 透视投影矩阵
+/// fov_y 为垂直方向的视角（弧度），aspect 为宽高比，near、far 为到近平面和远平面的距离（正数）
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Matrix4<f32> {
+    let f = 1.0 / (fov_y * 0.5).tan();
+    #[rustfmt::skip]
+    let matrix = Matrix4::new(
+        f / aspect, 0.0, 0.0, 0.0,
+        0.0, f, 0.0, 0.0,
+        0.0, 0.0, (far + near) / (near - far), 2.0 * far * near / (near - far),
+        0.0, 0.0, -1.0, 0.0,
+    );
+    matrix
+}
+
+/// 正交投影矩阵，把相机坐标系中 [left, right] x [bottom, top] x [-far, -near] 的长方体映射到 NDC
+pub fn orthographic(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+) -> Matrix4<f32> {
+    #[rustfmt::skip]
+    let matrix = Matrix4::new(
+        2.0 / (right - left), 0.0, 0.0, -(right + left) / (right - left),
+        0.0, 2.0 / (top - bottom), 0.0, -(top + bottom) / (top - bottom),
+        0.0, 0.0, -2.0 / (far - near), -(far + near) / (far - near),
+        0.0, 0.0, 0.0, 1.0,
+    );
+    matrix
+}
+
+/// 视口矩阵，把 NDC 映射到屏幕
+/// 屏幕原点在左上角，x 向右、y 向下，和图片的像素坐标一致；深度从 [-1, 1] 映射到 [0, 1]
+pub fn viewport(width: f32, height: f32) -> Matrix4<f32> {
+    #[rustfmt::skip]
+    let matrix = Matrix4::new(
+        width * 0.5, 0.0, 0.0, width * 0.5,
+        0.0, -height * 0.5, 0.0, height * 0.5,
+        0.0, 0.0, 0.5, 0.5,
+        0.0, 0.0, 0.0, 1.0,
+    );
+    matrix
+}
+
+/// 法向量矩阵，即左上角 3x3 矩阵的逆转置
+/// 非均匀缩放时直接用模型矩阵变换法向量，结果不再垂直于表面
+/// 矩阵不可逆时退化为左上角的 3x3 矩阵
+pub fn normal_matrix(matrix: &Matrix4<f32>) -> Matrix3<f32> {
+    let linear = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+    linear
+        .try_inverse()
+        .map(|inverse| inverse.transpose())
+        .unwrap_or(linear)
+}
+
+/// 齐次坐标除以 w 得到 NDC，w 不大于 0（点在相机后面或者在相机平面上）时返回 None
+pub fn perspective_divide(clip: &Vector4<f32>) -> Option<Point3<f32>> {
+    if clip.w <= 0.0 {
+        return None;
+    }
+    Some(Point3::new(
+        clip.x / clip.w,
+        clip.y / clip.w,
+        clip.z / clip.w,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: &Point3<f32>, b: &Point3<f32>) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    #[test]
+    fn test_look_at() {
+        let eye = Point3::new(1.0, 2.0, 3.0);
+        let target = Point3::new(1.0, 2.0, -7.0);
+        let view = look_at(&eye, &target, &Vector3::y());
+        assert!(approx(&view.transform_point(&eye), &Point3::origin()));
+        assert!(approx(
+            &view.transform_point(&target),
+            &Point3::new(0.0, 0.0, -10.0)
+        ));
+        assert!(approx(
+            &view.transform_point(&Point3::new(2.0, 3.0, 3.0)),
+            &Point3::new(1.0, 1.0, 0.0)
+        ));
+
+        // 从 +x 看向原点，up 不和视线垂直
+        let view = look_at(
+            &Point3::new(5.0, 0.0, 0.0),
+            &Point3::origin(),
+            &Vector3::new(0.0, 1.0, 1.0),
+        );
+        let p = view.transform_point(&Point3::origin());
+        assert!(approx(&p, &Point3::new(0.0, 0.0, -5.0)));
+        let rotation = view.fixed_view::<3, 3>(0, 0);
+        assert!((rotation * rotation.transpose() - Matrix3::identity()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_perspective() {
+        let projection = perspective(90f32.to_radians(), 2.0, 1.0, 10.0);
+        let ndc = |p: Point3<f32>| perspective_divide(&(projection * p.to_homogeneous())).unwrap();
+        assert!(approx(
+            &ndc(Point3::new(0.0, 0.0, -1.0)),
+            &Point3::new(0.0, 0.0, -1.0)
+        ));
+        assert!(approx(
+            &ndc(Point3::new(0.0, 0.0, -10.0)),
+            &Point3::new(0.0, 0.0, 1.0)
+        ));
+        // 90 度视角时视锥的边缘满足 |y| = |z|，水平方向再乘以宽高比
+        let p = ndc(Point3::new(8.0, 4.0, -4.0));
+        assert!((p.x - 1.0).abs() < 1e-5 && (p.y - 1.0).abs() < 1e-5);
+        let p = ndc(Point3::new(-3.0, 1.0, -2.0));
+        assert!((p.x + 0.75).abs() < 1e-5 && (p.y - 0.5).abs() < 1e-5);
+
+        // 深度单调递增
+        let depths: Vec<f32> = [1.0, 2.0, 5.0, 9.0]
+            .iter()
+            .map(|&d| ndc(Point3::new(0.0, 0.0, -d)).z)
+            .collect();
+        assert!(depths.windows(2).all(|w| w[0] < w[1]));
+
+        // 相机后面的点
+        let behind = projection * Point3::new(0.0, 0.0, 1.0).to_homogeneous();
+        assert!(perspective_divide(&behind).is_none());
+    }
+
+    #[test]
+    fn test_orthographic() {
+        let projection = orthographic(-2.0, 2.0, -1.0, 1.0, 0.5, 4.5);
+        let ndc = |p: Point3<f32>| projection.transform_point(&p);
+        assert!(approx(
+            &ndc(Point3::new(-2.0, -1.0, -0.5)),
+            &Point3::new(-1.0, -1.0, -1.0)
+        ));
+        assert!(approx(
+            &ndc(Point3::new(2.0, 1.0, -4.5)),
+            &Point3::new(1.0, 1.0, 1.0)
+        ));
+        assert!(approx(
+            &ndc(Point3::new(1.0, 0.0, -2.5)),
+            &Point3::new(0.5, 0.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn test_viewport() {
+        let matrix = viewport(800.0, 600.0);
+        let screen = |x, y, z| matrix.transform_point(&Point3::new(x, y, z));
+        assert!(approx(
+            &screen(-1.0, 1.0, -1.0),
+            &Point3::new(0.0, 0.0, 0.0)
+        ));
+        assert!(approx(
+            &screen(1.0, -1.0, 1.0),
+            &Point3::new(800.0, 600.0, 1.0)
+        ));
+        assert!(approx(
+            &screen(0.0, 0.0, 0.0),
+            &Point3::new(400.0, 300.0, 0.5)
+        ));
+    }
+
+    #[test]
+    fn test_normal_matrix() {
+        let model = Matrix4::new_rotation(Vector3::new(0.3, -0.2, 0.5))
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(4.0, 1.0, 0.5));
+        let normal = normal_matrix(&model);
+        // 斜面 x + y + z = 1 上的切向量和法向量
+        let tangents = [Vector3::new(1.0, -1.0, 0.0), Vector3::new(0.0, 1.0, -1.0)];
+        let n = normal * Vector3::new(1.0, 1.0, 1.0);
+        for t in tangents {
+            let t = model.transform_vector(&t);
+            assert!(n.dot(&t).abs() < 1e-4);
+        }
+        // 直接用模型矩阵变换的法向量不再垂直
+        let wrong = model.transform_vector(&Vector3::new(1.0, 1.0, 1.0));
+        assert!(wrong.dot(&model.transform_vector(&tangents[0])).abs() > 0.1);
+
+        // 纯旋转时法向量矩阵就是旋转矩阵
+        let rotation = Matrix4::new_rotation(Vector3::new(0.1, 0.7, -0.4));
+        assert!((normal_matrix(&rotation) - rotation.fixed_view::<3, 3>(0, 0)).norm() < 1e-5);
+        // 不可逆的矩阵
+        let flat = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 0.0, 1.0));
+        assert_eq!(normal_matrix(&flat), flat.fixed_view::<3, 3>(0, 0));
+    }
+}
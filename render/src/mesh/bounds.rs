@@ -0,0 +1,353 @@
+use super::Mesh;
+use nalgebra::{Matrix4, Point3, Vector3};
+
+/// 轴对齐包围盒
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Aabb::empty()
+    }
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Aabb { min, max }
+    }
+
+    /// 不包含任何点的包围盒，min 为正无穷，max 为负无穷
+    pub fn empty() -> Self {
+        Aabb {
+            min: Point3::from(Vector3::repeat(f32::INFINITY)),
+            max: Point3::from(Vector3::repeat(f32::NEG_INFINITY)),
+        }
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Point3<f32>>) -> Self {
+        let mut aabb = Aabb::empty();
+        for p in points {
+            aabb.grow(p);
+        }
+        aabb
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// 扩大包围盒使其包含一个点
+    pub fn grow(&mut self, p: &Point3<f32>) {
+        self.min = self.min.inf(p);
+        self.max = self.max.sup(p);
+    }
+
+    /// 同时包含两个包围盒的包围盒
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    /// 三个方向上的边长
+    pub fn size(&self) -> Vector3<f32> {
+        if self.is_empty() {
+            Vector3::zeros()
+        } else {
+            self.max - self.min
+        }
+    }
+
+    /// 表面积，用于 BVH 的 SAH 代价估计
+    pub fn surface_area(&self) -> f32 {
+        let s = self.size();
+        2.0 * (s.x * s.y + s.y * s.z + s.z * s.x)
+    }
+
+    pub fn contains(&self, p: &Point3<f32>) -> bool {
+        (0..3).all(|i| p[i] >= self.min[i] && p[i] <= self.max[i])
+    }
+
+    /// 八个角点经过变换后的包围盒
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+        let corners: Vec<Point3<f32>> = (0..8)
+            .map(|i| {
+                let corner = Point3::new(
+                    if i & 1 == 0 { self.min.x } else { self.max.x },
+                    if i & 2 == 0 { self.min.y } else { self.max.y },
+                    if i & 4 == 0 { self.min.z } else { self.max.z },
+                );
+                matrix.transform_point(&corner)
+            })
+            .collect();
+        Aabb::from_points(&corners)
+    }
+}
+
+/// 包围球
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// 使用 Ritter 算法计算包围球，结果接近但不一定是最小包围球
+    /// 同时和包围盒的外接球比较，取较小的一个
+    pub fn from_points(points: &[Point3<f32>]) -> Self {
+        let Some(&first) = points.first() else {
+            return BoundingSphere {
+                center: Point3::origin(),
+                radius: 0.0,
+            };
+        };
+        let farthest = |from: &Point3<f32>| -> Point3<f32> {
+            *points
+                .iter()
+                .max_by(|a, b| {
+                    (*a - from)
+                        .norm_squared()
+                        .total_cmp(&(*b - from).norm_squared())
+                })
+                .unwrap()
+        };
+        let a = farthest(&first);
+        let b = farthest(&a);
+        let mut center = nalgebra::center(&a, &b);
+        let mut radius = (b - a).norm() * 0.5;
+        for p in points {
+            let distance = (p - center).norm();
+            if distance > radius {
+                // 把球扩大到刚好包含这个点，球心向该点移动
+                let new_radius = (radius + distance) * 0.5;
+                center += (p - center) * ((new_radius - radius) / distance);
+                radius = new_radius;
+            }
+        }
+
+        let aabb = Aabb::from_points(points);
+        let aabb_center = aabb.center();
+        let aabb_radius = points
+            .iter()
+            .map(|p| (p - aabb_center).norm())
+            .fold(0.0, f32::max);
+        if aabb_radius < radius {
+            return BoundingSphere {
+                center: aabb_center,
+                radius: aabb_radius,
+            };
+        }
+        BoundingSphere { center, radius }
+    }
+
+    /// 点是否在球内，允许一点浮点误差
+    pub fn contains(&self, p: &Point3<f32>) -> bool {
+        (p - self.center).norm() <= self.radius * (1.0 + 1e-5) + 1e-6
+    }
+
+    /// 透视相机完整看到整个球时，相机到球心的距离
+    /// fov_y 为垂直方向的视角（弧度），aspect 为宽高比
+    pub fn fit_distance(&self, fov_y: f32, aspect: f32) -> f32 {
+        let half_y = fov_y * 0.5;
+        let half_x = ((fov_y * 0.5).tan() * aspect).atan();
+        self.radius / half_x.min(half_y).sin()
+    }
+}
+
+/// 相机自动取景的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Framing {
+    pub eye: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    /// 近平面和远平面刚好包住整个包围球
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Mesh {
+    /// 所有顶点的轴对齐包围盒
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(&self.positions)
+    }
+
+    /// 所有顶点的包围球
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::from_points(&self.positions)
+    }
+
+    /// 平移所有顶点
+    pub fn translate(&mut self, offset: &Vector3<f32>) {
+        for p in self.positions.iter_mut() {
+            *p += offset;
+        }
+    }
+
+    /// 平移网格使包围盒的中心位于原点，返回平移量
+    pub fn center(&mut self) -> Vector3<f32> {
+        let aabb = self.aabb();
+        if aabb.is_empty() {
+            return Vector3::zeros();
+        }
+        let offset = -aabb.center().coords;
+        self.translate(&offset);
+        offset
+    }
+
+    /// 居中并等比缩放，使网格刚好放进 [-1, 1] 的立方体中，返回缩放系数
+    /// 之后可以直接用 (x + 1) * width / 2 这样的方式映射到屏幕上；形变目标的坐标偏移同样缩放
+    pub fn normalize(&mut self) -> f32 {
+        self.center();
+        let extent = self.aabb().size().max() * 0.5;
+        if extent <= 0.0 {
+            return 1.0;
+        }
+        let scale = 1.0 / extent;
+        for p in self.positions.iter_mut() {
+            *p *= scale;
+        }
+        for delta in self.targets.iter_mut().flat_map(|t| t.positions.iter_mut()) {
+            *delta *= scale;
+        }
+        scale
+    }
+
+    /// 让透视相机完整看到整个网格
+    /// direction 为从网格中心指向相机的方向，fov_y 为垂直视角（弧度），aspect 为宽高比
+    pub fn frame(&self, direction: &Vector3<f32>, fov_y: f32, aspect: f32) -> Framing {
+        let sphere = self.bounding_sphere();
+        let direction = direction
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::z);
+        // 方向接近竖直时换一个向上的方向
+        let up = if direction.y.abs() > 0.999 {
+            Vector3::z()
+        } else {
+            Vector3::y()
+        };
+        let radius = sphere.radius.max(f32::EPSILON);
+        let distance = BoundingSphere {
+            center: sphere.center,
+            radius,
+        }
+        .fit_distance(fov_y, aspect);
+        Framing {
+            eye: sphere.center + direction * distance,
+            target: sphere.center,
+            up,
+            near: (distance - radius).max(distance * 1e-3),
+            far: distance + radius,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::primitive;
+
+    fn african_head() -> Mesh {
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        crate::io::obj::load_mesh(path).expect("Failed to load OBJ file")
+    }
+
+    #[test]
+    fn test_aabb() {
+        let mut mesh = primitive::cube(2.0);
+        mesh.translate(&Vector3::new(10.0, 0.0, 0.0));
+        let aabb = mesh.aabb();
+        assert_eq!(aabb.min, Point3::new(9.0, -1.0, -1.0));
+        assert_eq!(aabb.max, Point3::new(11.0, 1.0, 1.0));
+        assert_eq!(aabb.surface_area(), 24.0);
+        assert!(Aabb::empty().is_empty());
+        assert_eq!(Mesh::new("empty").aabb().size(), Vector3::zeros());
+
+        let rotated = aabb.transform(&Matrix4::new_rotation(
+            Vector3::z() * std::f32::consts::FRAC_PI_2,
+        ));
+        assert!((rotated.min - Point3::new(-1.0, 9.0, -1.0)).norm() < 1e-5);
+        assert!((rotated.max - Point3::new(1.0, 11.0, 1.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let sphere = primitive::icosphere(3.0, 2).bounding_sphere();
+        assert!(sphere.center.coords.norm() < 1e-4);
+        assert!((sphere.radius - 3.0).abs() < 0.05);
+
+        let mesh = african_head();
+        let sphere = mesh.bounding_sphere();
+        assert!(mesh.positions.iter().all(|p| sphere.contains(p)));
+        let aabb = mesh.aabb();
+        assert!(sphere.radius <= aabb.size().norm() * 0.5 + 1e-5);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut mesh = primitive::torus(40.0, 10.0, 16, 8);
+        mesh.translate(&Vector3::new(100.0, -50.0, 3.0));
+        let scale = mesh.normalize();
+        assert!((scale - 1.0 / 50.0).abs() < 1e-6);
+        let aabb = mesh.aabb();
+        assert!(aabb.center().coords.norm() < 1e-5);
+        assert!((aabb.size().max() - 2.0).abs() < 1e-5);
+        assert!(mesh.positions.iter().all(|p| p.coords.amax() <= 1.0 + 1e-5));
+    }
+
+    #[test]
+    fn test_normalize_morph_targets() {
+        let mut mesh = primitive::cube(4.0);
+        mesh.translate(&Vector3::new(10.0, 0.0, 0.0));
+        let mut shape = mesh.clone();
+        for p in shape.positions.iter_mut() {
+            p.y *= 1.5;
+        }
+        assert!(mesh.add_morph_target("stretch", &shape));
+        let offset = mesh.center();
+        let scale = mesh.normalize();
+        // 形变之后和同样居中、缩放的目标形状一致
+        let morphed = mesh.morph(&[1.0]);
+        for (p, target) in morphed.positions.iter().zip(&shape.positions) {
+            let expected = (target + offset) * scale;
+            assert!((p - expected).norm() < 1e-5, "{} {}", p, expected);
+        }
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut mesh = african_head();
+        for p in mesh.positions.iter_mut() {
+            *p = Point3::from(p.coords * 250.0 + Vector3::new(30.0, 400.0, -80.0));
+        }
+        let fov_y = 45f32.to_radians();
+        let aspect = 16.0 / 9.0;
+        let direction = Vector3::new(1.0, 0.5, 2.0);
+        let framing = mesh.frame(&direction, fov_y, aspect);
+
+        let forward = (framing.target - framing.eye).normalize();
+        let right = forward.cross(&framing.up).normalize();
+        let up = right.cross(&forward);
+        let tan_y = (fov_y * 0.5).tan();
+        for p in &mesh.positions {
+            let d = p - framing.eye;
+            let depth = d.dot(&forward);
+            assert!(depth >= framing.near && depth <= framing.far);
+            assert!(d.dot(&up).abs() / depth <= tan_y);
+            assert!(d.dot(&right).abs() / depth <= tan_y * aspect);
+        }
+    }
+}
@@ -0,0 +1,585 @@
+use super::bounds::Aabb;
+use super::Mesh;
+use nalgebra::{Point3, Vector3};
+
+/// 叶子节点最多包含的三角形数量，超过时即使 SAH 认为不值得也会继续划分
+const MAX_LEAF_SIZE: usize = 8;
+/// SAH 划分时每个轴上的分桶数量
+const BIN_COUNT: usize = 16;
+/// 遍历一个节点相对于求交一个三角形的代价
+const TRAVERSAL_COST: f32 = 1.0;
+
+/// 射线，direction 不要求是单位向量，t 以 direction 的长度为单位
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Ray { origin, direction }
+    }
+
+    pub fn at(&self, t: f32) -> Point3<f32> {
+        self.origin + self.direction * t
+    }
+}
+
+/// 射线和三角形的交点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// 三角形在 BVH 中的编号，和 Mesh::triangles 的遍历顺序一致
+    pub triangle: usize,
+    /// 三角形所在的面
+    pub face: usize,
+    /// 三角形的顶点索引
+    pub vertices: [usize; 3],
+    pub t: f32,
+    /// 交点的重心坐标，可以用来插值顶点属性
+    pub barycentric: Vector3<f32>,
+}
+
+/// 网格上离查询点最近的点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosestPoint {
+    pub triangle: usize,
+    pub face: usize,
+    pub point: Point3<f32>,
+    pub distance: f32,
+}
+
+/// BVH 节点，count 为 0 时是内部节点，两个子节点为 first 和 first + 1
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    bounds: Aabb,
+    first: usize,
+    count: usize,
+}
+
+/// 网格三角形上的层次包围盒
+///
+/// BVH 只保存三角形的顶点索引，查询时需要传入构建时使用的网格，
+/// 顶点移动之后（例如动画或者蒙皮）可以用 refit 更新包围盒而不用重新构建
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    triangles: Vec<[usize; 3]>,
+    /// 每个三角形所在的面
+    faces: Vec<usize>,
+    /// 叶子节点引用的三角形编号，叶子节点覆盖其中连续的一段
+    order: Vec<usize>,
+}
+
+impl Bvh {
+    /// 使用分桶的表面积启发式（SAH）构建
+    pub fn build(mesh: &Mesh) -> Bvh {
+        let mut triangles = Vec::with_capacity(mesh.triangle_count());
+        let mut faces = Vec::with_capacity(mesh.triangle_count());
+        for (f, face) in mesh.faces.iter().enumerate() {
+            for i in 1..face.len().saturating_sub(1) {
+                triangles.push([face[0], face[i], face[i + 1]]);
+                faces.push(f);
+            }
+        }
+        let bounds: Vec<Aabb> = triangles
+            .iter()
+            .map(|&t| Aabb::from_points(&mesh.triangle_positions(t)))
+            .collect();
+        let centroids: Vec<Point3<f32>> = bounds.iter().map(Aabb::center).collect();
+
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(triangles.len() * 2),
+            order: (0..triangles.len()).collect(),
+            triangles,
+            faces,
+        };
+        bvh.nodes.push(Node {
+            bounds: Aabb::empty(),
+            first: 0,
+            count: bvh.triangles.len(),
+        });
+        if !bvh.triangles.is_empty() {
+            bvh.subdivide(0, &bounds, &centroids);
+        }
+        bvh
+    }
+
+    fn subdivide(&mut self, index: usize, bounds: &[Aabb], centroids: &[Point3<f32>]) {
+        let Node { first, count, .. } = self.nodes[index];
+        let items = &mut self.order[first..first + count];
+        let node_bounds = items
+            .iter()
+            .fold(Aabb::empty(), |b, &t| b.union(&bounds[t]));
+        self.nodes[index].bounds = node_bounds;
+        if count <= 2 {
+            return;
+        }
+
+        let centroid_bounds = Aabb::from_points(items.iter().map(|&t| &centroids[t]));
+        let extent = centroid_bounds.size();
+        // 所有三角形的中心重合时无法划分
+        if extent.max() <= 0.0 {
+            return;
+        }
+
+        // 在每个轴上分桶，计算每个划分位置的 SAH 代价
+        let mut best: Option<(usize, usize, f32)> = None;
+        for axis in 0..3 {
+            if extent[axis] <= 0.0 {
+                continue;
+            }
+            let bin_of =
+                |t: usize| bin_index(centroids[t][axis], centroid_bounds.min[axis], extent[axis]);
+            let mut bins = [(Aabb::empty(), 0usize); BIN_COUNT];
+            for &t in items.iter() {
+                let bin = &mut bins[bin_of(t)];
+                bin.0 = bin.0.union(&bounds[t]);
+                bin.1 += 1;
+            }
+            // 从右往左累计右侧的包围盒和数量
+            let mut right = [(0.0f32, 0usize); BIN_COUNT];
+            let (mut area, mut n) = (Aabb::empty(), 0);
+            for i in (1..BIN_COUNT).rev() {
+                area = area.union(&bins[i].0);
+                n += bins[i].1;
+                right[i] = (area.surface_area(), n);
+            }
+            let (mut area, mut n) = (Aabb::empty(), 0);
+            for split in 1..BIN_COUNT {
+                area = area.union(&bins[split - 1].0);
+                n += bins[split - 1].1;
+                let (right_area, right_count) = right[split];
+                if n == 0 || right_count == 0 {
+                    continue;
+                }
+                let cost = area.surface_area() * n as f32 + right_area * right_count as f32;
+                if best.is_none_or(|(_, _, c)| cost < c) {
+                    best = Some((axis, split, cost));
+                }
+            }
+        }
+        let Some((axis, split, cost)) = best else {
+            return;
+        };
+        let cost = TRAVERSAL_COST + cost / node_bounds.surface_area().max(f32::EPSILON);
+        if cost >= count as f32 && count <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        // 按照桶的位置把三角形分到两侧
+        let mut left = 0;
+        for i in 0..items.len() {
+            let t = items[i];
+            if bin_index(centroids[t][axis], centroid_bounds.min[axis], extent[axis]) < split {
+                items.swap(i, left);
+                left += 1;
+            }
+        }
+
+        let child = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: Aabb::empty(),
+            first,
+            count: left,
+        });
+        self.nodes.push(Node {
+            bounds: Aabb::empty(),
+            first: first + left,
+            count: count - left,
+        });
+        self.nodes[index] = Node {
+            bounds: node_bounds,
+            first: child,
+            count: 0,
+        };
+        self.subdivide(child, bounds, centroids);
+        self.subdivide(child + 1, bounds, centroids);
+    }
+
+    /// 网格顶点移动之后重新计算包围盒，网格的拓扑必须和构建时一致
+    pub fn refit(&mut self, mesh: &Mesh) {
+        // 子节点总是在父节点之后，倒序遍历即可自底向上更新
+        for index in (0..self.nodes.len()).rev() {
+            let Node { first, count, .. } = self.nodes[index];
+            self.nodes[index].bounds = if count > 0 {
+                self.order[first..first + count]
+                    .iter()
+                    .fold(Aabb::empty(), |b, &t| {
+                        b.union(&Aabb::from_points(
+                            &mesh.triangle_positions(self.triangles[t]),
+                        ))
+                    })
+            } else if first == 0 {
+                // 空的 BVH 只有一个根节点
+                Aabb::empty()
+            } else {
+                self.nodes[first]
+                    .bounds
+                    .union(&self.nodes[first + 1].bounds)
+            };
+        }
+    }
+
+    /// 整个网格的包围盒
+    pub fn bounds(&self) -> Aabb {
+        self.nodes[0].bounds
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// 求射线在 (t_min, t_max) 范围内最近的交点，三角形的正反面都会相交
+    pub fn intersect(&self, mesh: &Mesh, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let mut closest: Option<Hit> = None;
+        let mut t_max = t_max;
+        self.traverse(ray, t_min, &mut t_max, |t, t_max| {
+            let vertices = self.triangles[t];
+            if let Some((distance, barycentric)) =
+                intersect_triangle(ray, &mesh.triangle_positions(vertices), t_min, *t_max)
+            {
+                *t_max = distance;
+                closest = Some(Hit {
+                    triangle: t,
+                    face: self.faces[t],
+                    vertices,
+                    t: distance,
+                    barycentric,
+                });
+            }
+            false
+        });
+        closest
+    }
+
+    /// 射线在 (t_min, t_max) 范围内是否和任意三角形相交，找到一个交点就返回，用于阴影测试
+    pub fn occluded(&self, mesh: &Mesh, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let mut hit = false;
+        let mut t_max = t_max;
+        self.traverse(ray, t_min, &mut t_max, |t, t_max| {
+            let positions = mesh.triangle_positions(self.triangles[t]);
+            hit = intersect_triangle(ray, &positions, t_min, *t_max).is_some();
+            hit
+        });
+        hit
+    }
+
+    /// 遍历和射线相交的叶子节点，visit 返回 true 时提前结束
+    fn traverse(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: &mut f32,
+        mut visit: impl FnMut(usize, &mut f32) -> bool,
+    ) {
+        if self.triangles.is_empty() {
+            return;
+        }
+        let inverse = ray.direction.map(|d| 1.0 / d);
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if intersect_aabb(&node.bounds, ray, &inverse, t_min, *t_max).is_none() {
+                continue;
+            }
+            if node.count > 0 {
+                for &t in &self.order[node.first..node.first + node.count] {
+                    if visit(t, t_max) {
+                        return;
+                    }
+                }
+                continue;
+            }
+            // 先访问较近的子节点，更早缩小 t_max
+            let (a, b) = (node.first, node.first + 1);
+            let da = intersect_aabb(&self.nodes[a].bounds, ray, &inverse, t_min, *t_max);
+            let db = intersect_aabb(&self.nodes[b].bounds, ray, &inverse, t_min, *t_max);
+            match (da, db) {
+                (Some(da), Some(db)) if da <= db => stack.extend([b, a]),
+                (Some(_), Some(_)) => stack.extend([a, b]),
+                (Some(_), None) => stack.push(a),
+                (None, Some(_)) => stack.push(b),
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// 网格上离 point 最近的点
+    pub fn closest_point(&self, mesh: &Mesh, point: &Point3<f32>) -> Option<ClosestPoint> {
+        if self.triangles.is_empty() {
+            return None;
+        }
+        let mut best: Option<ClosestPoint> = None;
+        let mut best_squared = f32::INFINITY;
+        let mut stack = vec![(0, distance_squared_to_aabb(&self.nodes[0].bounds, point))];
+        while let Some((index, box_squared)) = stack.pop() {
+            if box_squared > best_squared {
+                continue;
+            }
+            let node = &self.nodes[index];
+            if node.count > 0 {
+                for &t in &self.order[node.first..node.first + node.count] {
+                    let positions = mesh.triangle_positions(self.triangles[t]);
+                    let closest = closest_point_on_triangle(point, &positions);
+                    let squared = (closest - point).norm_squared();
+                    if squared < best_squared {
+                        best_squared = squared;
+                        best = Some(ClosestPoint {
+                            triangle: t,
+                            face: self.faces[t],
+                            point: closest,
+                            distance: squared.sqrt(),
+                        });
+                    }
+                }
+                continue;
+            }
+            let (a, b) = (node.first, node.first + 1);
+            let da = distance_squared_to_aabb(&self.nodes[a].bounds, point);
+            let db = distance_squared_to_aabb(&self.nodes[b].bounds, point);
+            if da <= db {
+                stack.extend([(b, db), (a, da)]);
+            } else {
+                stack.extend([(a, da), (b, db)]);
+            }
+        }
+        best
+    }
+}
+
+/// 中心点所在的桶
+fn bin_index(value: f32, min: f32, extent: f32) -> usize {
+    (((value - min) / extent * BIN_COUNT as f32) as usize).min(BIN_COUNT - 1)
+}
+
+/// 射线和包围盒的 slab 测试，返回进入包围盒的 t
+fn intersect_aabb(
+    aabb: &Aabb,
+    ray: &Ray,
+    inverse: &Vector3<f32>,
+    t_min: f32,
+    t_max: f32,
+) -> Option<f32> {
+    let (mut near, mut far) = (t_min, t_max);
+    for i in 0..3 {
+        let t0 = (aabb.min[i] - ray.origin[i]) * inverse[i];
+        let t1 = (aabb.max[i] - ray.origin[i]) * inverse[i];
+        // 射线和 slab 平行并且起点在边界上时得到 NaN，max/min 会忽略 NaN
+        near = near.max(t0.min(t1));
+        far = far.min(t0.max(t1));
+    }
+    // 稍微放宽远端，避免浮点误差漏掉刚好落在包围盒表面上的三角形
+    (near <= far + far.abs() * 4.0 * f32::EPSILON).then_some(near)
+}
+
+/// Möller–Trumbore 射线三角形求交，返回 t 和重心坐标
+fn intersect_triangle(
+    ray: &Ray,
+    [a, b, c]: &[Point3<f32>; 3],
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, Vector3<f32>)> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = ray.direction.cross(&ac);
+    let det = ab.dot(&p);
+    if det.abs() < f32::EPSILON * ab.norm() * ac.norm() * ray.direction.norm() {
+        return None;
+    }
+    let inverse = 1.0 / det;
+    let s = ray.origin - a;
+    let u = s.dot(&p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&ab);
+    let v = ray.direction.dot(&q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(&q) * inverse;
+    (t > t_min && t < t_max).then(|| (t, Vector3::new(1.0 - u - v, u, v)))
+}
+
+fn distance_squared_to_aabb(aabb: &Aabb, p: &Point3<f32>) -> f32 {
+    let clamped = p.sup(&aabb.min).inf(&aabb.max);
+    (clamped - p).norm_squared()
+}
+
+/// 三角形上离 p 最近的点，按 p 所在的 Voronoi 区域分情况计算
+fn closest_point_on_triangle(p: &Point3<f32>, [a, b, c]: &[Point3<f32>; 3]) -> Point3<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *a;
+    }
+    let bp = p - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return *b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return *c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denominator = 1.0 / (va + vb + vc);
+    a + ab * (vb * denominator) + ac * (vc * denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::primitive;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn african_head() -> Mesh {
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        crate::io::obj::load_mesh(path).expect("Failed to load OBJ file")
+    }
+
+    fn random_point(rng: &mut StdRng, scale: f32) -> Point3<f32> {
+        Point3::new(
+            rng.gen_range(-scale..scale),
+            rng.gen_range(-scale..scale),
+            rng.gen_range(-scale..scale),
+        )
+    }
+
+    /// 暴力遍历所有三角形求最近的交点
+    fn brute_force(mesh: &Mesh, ray: &Ray) -> Option<f32> {
+        mesh.triangles()
+            .filter_map(|t| {
+                intersect_triangle(ray, &mesh.triangle_positions(t), 0.0, f32::INFINITY)
+            })
+            .map(|(t, _)| t)
+            .min_by(f32::total_cmp)
+    }
+
+    #[test]
+    fn test_ray_cube() {
+        let mesh = primitive::cube(2.0);
+        let bvh = Bvh::build(&mesh);
+        assert_eq!(bvh.triangle_count(), 12);
+        let ray = Ray::new(Point3::new(0.5, 0.25, 5.0), -Vector3::z());
+        let hit = bvh.intersect(&mesh, &ray, 0.0, f32::INFINITY).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-5);
+        assert_eq!(mesh.face_normal(hit.face), Vector3::z());
+        // 用重心坐标插值顶点坐标得到交点
+        let point = hit
+            .vertices
+            .iter()
+            .zip(hit.barycentric.iter())
+            .fold(Vector3::zeros(), |s, (&v, &w)| {
+                s + mesh.positions[v].coords * w
+            });
+        assert!((Point3::from(point) - ray.at(hit.t)).norm() < 1e-5);
+
+        assert!(bvh.intersect(&mesh, &ray, 0.0, 3.9).is_none());
+        let miss = Ray::new(Point3::new(1.5, 0.0, 5.0), -Vector3::z());
+        assert!(bvh.intersect(&mesh, &miss, 0.0, f32::INFINITY).is_none());
+        assert!(bvh.occluded(&mesh, &ray, 0.0, f32::INFINITY));
+        assert!(!bvh.occluded(&mesh, &miss, 0.0, f32::INFINITY));
+    }
+
+    #[test]
+    fn test_intersect_matches_brute_force() {
+        let mesh = african_head();
+        let bvh = Bvh::build(&mesh);
+        assert_eq!(bvh.triangle_count(), mesh.triangle_count());
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut hits = 0;
+        for _ in 0..500 {
+            let origin = random_point(&mut rng, 2.0);
+            let target = random_point(&mut rng, 0.5);
+            let ray = Ray::new(origin, target - origin);
+            let expected = brute_force(&mesh, &ray);
+            let actual = bvh.intersect(&mesh, &ray, 0.0, f32::INFINITY).map(|h| h.t);
+            assert_eq!(expected, actual);
+            hits += expected.is_some() as usize;
+            assert_eq!(
+                bvh.occluded(&mesh, &ray, 0.0, f32::INFINITY),
+                expected.is_some()
+            );
+        }
+        assert!(hits > 100);
+    }
+
+    #[test]
+    fn test_closest_point() {
+        let mesh = african_head();
+        let bvh = Bvh::build(&mesh);
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..100 {
+            let p = random_point(&mut rng, 1.5);
+            let expected = mesh
+                .triangles()
+                .map(|t| (closest_point_on_triangle(&p, &mesh.triangle_positions(t)) - p).norm())
+                .fold(f32::INFINITY, f32::min);
+            let actual = bvh.closest_point(&mesh, &p).unwrap();
+            assert!((actual.distance - expected).abs() < 1e-6);
+            assert!(((actual.point - p).norm() - actual.distance).abs() < 1e-6);
+        }
+
+        let sphere = primitive::icosphere(1.0, 3);
+        let bvh = Bvh::build(&sphere);
+        let closest = bvh
+            .closest_point(&sphere, &Point3::new(0.0, 3.0, 0.0))
+            .unwrap();
+        assert!((closest.distance - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_refit() {
+        let mut mesh = primitive::cube(2.0);
+        let mut bvh = Bvh::build(&mesh);
+        mesh.translate(&Vector3::new(10.0, 0.0, 0.0));
+        bvh.refit(&mesh);
+        let bounds = bvh.bounds();
+        assert!((bounds.center() - Point3::new(10.0, 0.0, 0.0)).norm() < 1e-5);
+        let ray = Ray::new(Point3::new(10.3, 0.2, 5.0), -Vector3::z());
+        let hit = bvh.intersect(&mesh, &ray, 0.0, f32::INFINITY).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-5);
+        assert!(bvh
+            .intersect(
+                &mesh,
+                &Ray::new(Point3::new(0.0, 0.0, 5.0), -Vector3::z()),
+                0.0,
+                f32::INFINITY
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_empty() {
+        let mesh = Mesh::new("empty");
+        let mut bvh = Bvh::build(&mesh);
+        bvh.refit(&mesh);
+        assert!(bvh.bounds().is_empty());
+        let ray = Ray::new(Point3::origin(), Vector3::z());
+        assert!(bvh.intersect(&mesh, &ray, 0.0, f32::INFINITY).is_none());
+        assert!(bvh.closest_point(&mesh, &Point3::origin()).is_none());
+    }
+}
@@ -0,0 +1,435 @@
+pub mod bounds;
+pub mod bvh;
+pub mod morph;
+pub mod normal;
+pub mod primitive;
+pub mod repair;
+pub mod simplify;
+pub mod skin;
+pub mod subdivide;
+pub mod tangent;
+pub mod validate;
+
+use nalgebra::{Point3, Vector2, Vector3, Vector4};
+use std::collections::HashMap;
+
+/// 网格中的一个分组
+/// 对应 obj 中的 g 或者 gltf 中的 primitive，分组覆盖 faces 中连续的一段
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub name: String,
+    /// 材质在场景材质列表中的索引
+    pub material: Option<usize>,
+    /// 分组在 faces 中的起始位置
+    pub start: usize,
+    /// 分组包含的面数
+    pub count: usize,
+}
+
+/// 形变目标（blend shape），保存每个顶点相对于基础形状的偏移
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphTarget {
+    pub name: String,
+    /// 顶点坐标的偏移，和网格的 positions 等长
+    pub positions: Vec<Vector3<f32>>,
+    /// 法向量的偏移，为空或者和网格的 positions 等长
+    pub normals: Vec<Vector3<f32>>,
+}
+
+/// 多边形网格
+/// 顶点属性按照顶点索引对齐：normals/uvs/colors/tangents/joints/weights 要么为空，要么和 positions 等长
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub name: String,
+    /// 顶点坐标
+    pub positions: Vec<Point3<f32>>,
+    /// 顶点法向量
+    pub normals: Vec<Vector3<f32>>,
+    /// 纹理坐标
+    pub uvs: Vec<Vector2<f32>>,
+    /// 顶点颜色（RGBA，0~1）
+    pub colors: Vec<Vector4<f32>>,
+    /// 切线，w 分量为副切线的方向符号
+    pub tangents: Vec<Vector4<f32>>,
+    /// 影响顶点的骨骼在蒙皮 joints 中的索引，最多 4 个
+    pub joints: Vec<[u16; 4]>,
+    /// 对应 joints 的骨骼权重，和为 1
+    pub weights: Vec<Vector4<f32>>,
+    /// 形变目标，每个目标的偏移也按顶点索引对齐
+    pub targets: Vec<MorphTarget>,
+    /// 面，每个面是按逆时针顺序排列的顶点索引
+    pub faces: Vec<Vec<usize>>,
+    /// 面分组，为空时表示所有面属于同一个默认分组
+    pub groups: Vec<Group>,
+}
+
+impl Mesh {
+    pub fn new(name: &str) -> Self {
+        Mesh {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 顶点数量
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// 面的数量
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// 三角化之后的三角形数量
+    pub fn triangle_count(&self) -> usize {
+        self.faces.iter().map(|f| f.len().saturating_sub(2)).sum()
+    }
+
+    /// 是否所有的面都是三角形
+    pub fn is_triangulated(&self) -> bool {
+        self.faces.iter().all(|f| f.len() == 3)
+    }
+
+    /// 以扇形三角化的方式遍历所有三角形的顶点索引
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.faces
+            .iter()
+            .flat_map(|f| (1..f.len().saturating_sub(1)).map(move |i| [f[0], f[i], f[i + 1]]))
+    }
+
+    /// 获取三角形三个顶点的坐标
+    pub fn triangle_positions(&self, triangle: [usize; 3]) -> [Point3<f32>; 3] {
+        triangle.map(|i| self.positions[i])
+    }
+
+    /// 使用 Newell 方法计算面的法向量（已归一化，退化面返回零向量）
+    pub fn face_normal(&self, face: usize) -> Vector3<f32> {
+        let face = &self.faces[face];
+        let mut normal = Vector3::zeros();
+        for (i, &a) in face.iter().enumerate() {
+            let p0 = self.positions[a];
+            let p1 = self.positions[face[(i + 1) % face.len()]];
+            normal.x += (p0.y - p1.y) * (p0.z + p1.z);
+            normal.y += (p0.z - p1.z) * (p0.x + p1.x);
+            normal.z += (p0.x - p1.x) * (p0.y + p1.y);
+        }
+        normal
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros)
+    }
+
+    /// 将所有多边形扇形三角化，分组范围随之更新
+    pub fn triangulate(&mut self) {
+        if self.is_triangulated() {
+            return;
+        }
+        let mut faces = Vec::with_capacity(self.triangle_count());
+        // 每个旧面在新的 faces 中的起始位置
+        let mut offsets = Vec::with_capacity(self.faces.len() + 1);
+        for face in &self.faces {
+            offsets.push(faces.len());
+            for i in 1..face.len().saturating_sub(1) {
+                faces.push(vec![face[0], face[i], face[i + 1]]);
+            }
+        }
+        offsets.push(faces.len());
+        for group in self.groups.iter_mut() {
+            let start = offsets[group.start];
+            group.count = offsets[group.start + group.count] - start;
+            group.start = start;
+        }
+        self.faces = faces;
+    }
+
+    /// 复制一个顶点的所有属性，返回新顶点的索引
+    pub(crate) fn duplicate_vertex(&mut self, v: usize) -> usize {
+        self.positions.push(self.positions[v]);
+        if self.normals.len() > v {
+            self.normals.push(self.normals[v]);
+        }
+        if self.uvs.len() > v {
+            self.uvs.push(self.uvs[v]);
+        }
+        if self.colors.len() > v {
+            self.colors.push(self.colors[v]);
+        }
+        if self.tangents.len() > v {
+            self.tangents.push(self.tangents[v]);
+        }
+        if self.joints.len() > v {
+            self.joints.push(self.joints[v]);
+        }
+        if self.weights.len() > v {
+            self.weights.push(self.weights[v]);
+        }
+        for target in self.targets.iter_mut() {
+            if target.positions.len() > v {
+                target.positions.push(target.positions[v]);
+            }
+            if target.normals.len() > v {
+                target.normals.push(target.normals[v]);
+            }
+        }
+        self.positions.len() - 1
+    }
+
+    /// 按坐标给顶点编号，返回每个顶点的位置编号以及位置的数量
+    /// 坐标相同的顶点（例如 uv 接缝两侧的顶点）得到相同的编号
+    pub(crate) fn position_ids(&self) -> (Vec<usize>, usize) {
+        let mut ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let vertex_position = self
+            .positions
+            .iter()
+            .map(|p| {
+                let next = ids.len();
+                *ids.entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()])
+                    .or_insert(next)
+            })
+            .collect();
+        (vertex_position, ids.len())
+    }
+
+    /// 只保留 keep 返回 true 的面，分组范围随之更新，返回删除的面数
+    pub fn retain_faces(&mut self, mut keep: impl FnMut(usize, &[usize]) -> bool) -> usize {
+        let before = self.faces.len();
+        let kept: Vec<bool> = self
+            .faces
+            .iter()
+            .enumerate()
+            .map(|(f, face)| keep(f, face))
+            .collect();
+        for group in self.groups.iter_mut() {
+            let removed_before = kept[..group.start].iter().filter(|k| !**k).count();
+            let count = kept[group.start..group.start + group.count]
+                .iter()
+                .filter(|k| **k)
+                .count();
+            group.start -= removed_before;
+            group.count = count;
+        }
+        let mut index = 0;
+        self.faces.retain(|_| {
+            index += 1;
+            kept[index - 1]
+        });
+        before - self.faces.len()
+    }
+
+    /// 删除没有被任何面引用的顶点，返回删除的顶点数
+    pub fn remove_unused_vertices(&mut self) -> usize {
+        let count = self.positions.len();
+        let mut used = vec![false; count];
+        for &v in self.faces.iter().flatten() {
+            if v < count {
+                used[v] = true;
+            }
+        }
+        let mut remap = vec![usize::MAX; count];
+        let mut next = 0;
+        for v in 0..count {
+            if used[v] {
+                remap[v] = next;
+                next += 1;
+            }
+        }
+        if next == count {
+            return 0;
+        }
+        fn compact<T: Copy>(values: &mut Vec<T>, used: &[bool]) {
+            if values.len() == used.len() {
+                let mut index = 0;
+                values.retain(|_| {
+                    index += 1;
+                    used[index - 1]
+                });
+            }
+        }
+        compact(&mut self.positions, &used);
+        compact(&mut self.normals, &used);
+        compact(&mut self.uvs, &used);
+        compact(&mut self.colors, &used);
+        compact(&mut self.tangents, &used);
+        compact(&mut self.joints, &used);
+        compact(&mut self.weights, &used);
+        for target in self.targets.iter_mut() {
+            compact(&mut target.positions, &used);
+            compact(&mut target.normals, &used);
+        }
+        for v in self.faces.iter_mut().flatten() {
+            if *v < count {
+                *v = remap[*v];
+            }
+        }
+        count - next
+    }
+
+    /// 获取某个面所属的分组
+    pub fn face_group(&self, face: usize) -> Option<&Group> {
+        self.groups
+            .iter()
+            .find(|g| face >= g.start && face < g.start + g.count)
+    }
+
+    /// 将另一个网格作为一个新的分组追加到当前网格中
+    /// 如果两者的顶点属性不一致，缺失的属性使用默认值填充
+    pub fn append(&mut self, other: &Mesh, group_name: &str, material: Option<usize>) {
+        let offset = self.positions.len();
+        let other_count = other.positions.len();
+        fill_attribute(
+            &mut self.normals,
+            offset,
+            &other.normals,
+            other_count,
+            Vector3::zeros(),
+        );
+        fill_attribute(
+            &mut self.uvs,
+            offset,
+            &other.uvs,
+            other_count,
+            Vector2::zeros(),
+        );
+        fill_attribute(
+            &mut self.colors,
+            offset,
+            &other.colors,
+            other_count,
+            Vector4::repeat(1.0),
+        );
+        fill_attribute(
+            &mut self.tangents,
+            offset,
+            &other.tangents,
+            other_count,
+            Vector4::zeros(),
+        );
+        fill_attribute(&mut self.joints, offset, &other.joints, other_count, [0; 4]);
+        fill_attribute(
+            &mut self.weights,
+            offset,
+            &other.weights,
+            other_count,
+            Vector4::zeros(),
+        );
+        // 形变目标按顺序对应，另一个网格没有的目标偏移为 0
+        if self.targets.len() < other.targets.len() {
+            self.targets
+                .resize(other.targets.len(), MorphTarget::default());
+        }
+        for (i, target) in self.targets.iter_mut().enumerate() {
+            let source = other.targets.get(i);
+            if let Some(source) = source.filter(|_| target.name.is_empty()) {
+                target.name = source.name.clone();
+            }
+            fill_attribute(
+                &mut target.positions,
+                offset,
+                source.map_or(&[], |t| &t.positions),
+                other_count,
+                Vector3::zeros(),
+            );
+            fill_attribute(
+                &mut target.normals,
+                offset,
+                source.map_or(&[], |t| &t.normals),
+                other_count,
+                Vector3::zeros(),
+            );
+        }
+        self.positions.extend_from_slice(&other.positions);
+
+        // 之前没有分组的面归为一个默认分组，保证分组能够覆盖所有的面
+        if self.groups.is_empty() && !self.faces.is_empty() {
+            self.groups.push(Group {
+                name: self.name.clone(),
+                material: None,
+                start: 0,
+                count: self.faces.len(),
+            });
+        }
+        self.groups.push(Group {
+            name: group_name.to_string(),
+            material,
+            start: self.faces.len(),
+            count: other.faces.len(),
+        });
+        self.faces.extend(
+            other
+                .faces
+                .iter()
+                .map(|f| f.iter().map(|i| i + offset).collect::<Vec<_>>()),
+        );
+    }
+}
+
+/// 合并顶点属性，任意一边存在该属性时，另一边用默认值补齐
+fn fill_attribute<T: Clone>(
+    dst: &mut Vec<T>,
+    dst_count: usize,
+    src: &[T],
+    src_count: usize,
+    default: T,
+) {
+    if dst.is_empty() && src.is_empty() {
+        return;
+    }
+    dst.resize(dst_count, default.clone());
+    if src.is_empty() {
+        dst.extend(std::iter::repeat_n(default, src_count));
+    } else {
+        dst.extend_from_slice(src);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> Mesh {
+        let mut mesh = Mesh::new("quad");
+        mesh.positions = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        mesh.faces = vec![vec![0, 1, 2, 3]];
+        mesh
+    }
+
+    #[test]
+    fn test_triangles() {
+        let mesh = quad();
+        assert_eq!(mesh.triangle_count(), 2);
+        let triangles: Vec<_> = mesh.triangles().collect();
+        assert_eq!(triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.face_normal(0), Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_triangulate_with_groups() {
+        let mut mesh = quad();
+        mesh.append(&quad(), "second", Some(1));
+        assert_eq!(mesh.groups.len(), 2);
+        mesh.triangulate();
+        assert!(mesh.is_triangulated());
+        assert_eq!(mesh.face_count(), 4);
+        assert_eq!((mesh.groups[0].start, mesh.groups[0].count), (0, 2));
+        assert_eq!((mesh.groups[1].start, mesh.groups[1].count), (2, 2));
+        assert_eq!(mesh.face_group(3).unwrap().material, Some(1));
+        assert_eq!(mesh.faces[3], vec![4, 6, 7]);
+    }
+
+    #[test]
+    fn test_append_fill_attribute() {
+        let mut mesh = quad();
+        let mut other = quad();
+        other.uvs = vec![Vector2::new(0.5, 0.5); 4];
+        mesh.append(&other, "uv", None);
+        assert_eq!(mesh.uvs.len(), 8);
+        assert_eq!(mesh.uvs[0], Vector2::zeros());
+        assert_eq!(mesh.uvs[7], Vector2::new(0.5, 0.5));
+        assert!(mesh.normals.is_empty());
+    }
+}
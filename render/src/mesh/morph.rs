@@ -0,0 +1,124 @@
+use super::{Mesh, MorphTarget};
+
+impl Mesh {
+    /// 按权重叠加形变目标，返回新的网格，权重少于目标数时缺少的权重视为 0
+    /// 叠加了偏移的法向量会重新归一化
+    pub fn morph(&self, weights: &[f32]) -> Mesh {
+        let mut result = self.clone();
+        let mut normals_changed = false;
+        for (target, &weight) in self.targets.iter().zip(weights) {
+            if weight == 0.0 {
+                continue;
+            }
+            for (p, delta) in result.positions.iter_mut().zip(&target.positions) {
+                *p += delta * weight;
+            }
+            for (n, delta) in result.normals.iter_mut().zip(&target.normals) {
+                *n += delta * weight;
+                normals_changed = true;
+            }
+        }
+        if normals_changed {
+            for n in result.normals.iter_mut() {
+                *n = n.try_normalize(f32::EPSILON).unwrap_or(*n);
+            }
+        }
+        result
+    }
+
+    /// 用和当前网格顶点一一对应的另一个网格（比如同一个模型导出的不同表情）创建形变目标
+    /// 顶点数不同时返回 false；两者都有法向量时同时记录法向量的偏移
+    pub fn add_morph_target(&mut self, name: &str, shape: &Mesh) -> bool {
+        if shape.positions.len() != self.positions.len() {
+            return false;
+        }
+        let positions = shape
+            .positions
+            .iter()
+            .zip(&self.positions)
+            .map(|(a, b)| a - b)
+            .collect();
+        let normals = if !self.normals.is_empty() && shape.normals.len() == self.normals.len() {
+            shape
+                .normals
+                .iter()
+                .zip(&self.normals)
+                .map(|(a, b)| a - b)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.targets.push(MorphTarget {
+            name: name.to_string(),
+            positions,
+            normals,
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::primitive;
+    use nalgebra::{Point3, Vector3};
+
+    fn head() -> Mesh {
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        crate::io::obj::load_mesh(path).unwrap()
+    }
+
+    #[test]
+    fn test_morph_head() {
+        let mut mesh = head();
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        // 表情：头顶沿法向量鼓起，其余部分不动
+        let mut shape = mesh.clone();
+        for (p, n) in shape.positions.iter_mut().zip(&mesh.normals) {
+            if p.y > 0.5 {
+                *p += n * 0.1;
+            }
+        }
+        assert!(mesh.add_morph_target("bulge", &shape));
+        assert!(!mesh.add_morph_target("invalid", &primitive::cube(1.0)));
+        assert_eq!(mesh.targets.len(), 1);
+        assert!(mesh.validate().is_empty());
+
+        assert_eq!(mesh.morph(&[]).positions, mesh.positions);
+        assert_eq!(mesh.morph(&[1.0]).positions, shape.positions);
+        let half = mesh.morph(&[0.5]);
+        for v in 0..mesh.vertex_count() {
+            let expected = mesh.positions[v] + (shape.positions[v] - mesh.positions[v]) * 0.5;
+            assert!((half.positions[v] - expected).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_morph_normals() {
+        let mut mesh = Mesh::new("quad");
+        mesh.positions = vec![Point3::origin(); 2];
+        mesh.normals = vec![Vector3::z(); 2];
+        mesh.targets.push(MorphTarget {
+            name: "tilt".to_string(),
+            positions: vec![Vector3::x(), Vector3::zeros()],
+            normals: vec![Vector3::new(1.0, 0.0, -1.0), Vector3::zeros()],
+        });
+        let morphed = mesh.morph(&[0.5, 1.0]);
+        assert_eq!(morphed.positions[0], Point3::new(0.5, 0.0, 0.0));
+        assert!((morphed.normals[0] - Vector3::new(1.0, 0.0, 1.0).normalize()).norm() < 1e-6);
+        assert_eq!(morphed.normals[1], Vector3::z());
+
+        // 复制和删除顶点时偏移随之更新
+        let copy = mesh.duplicate_vertex(0);
+        assert_eq!(mesh.targets[0].positions[copy], Vector3::x());
+        mesh.faces = vec![vec![1, 2, 1]];
+        mesh.remove_unused_vertices();
+        assert_eq!(
+            mesh.targets[0].positions,
+            vec![Vector3::zeros(), Vector3::x()]
+        );
+    }
+}
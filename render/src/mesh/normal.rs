@@ -0,0 +1,211 @@
+use super::Mesh;
+use nalgebra::{Point3, Vector3};
+use std::collections::HashMap;
+
+/// 顶点法向量的加权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// 按相邻面的面积加权
+    Area,
+    /// 按顶点在相邻面中的夹角加权，结果不受三角化方式影响
+    Angle,
+}
+
+impl Mesh {
+    /// 重新计算平滑的顶点法向量，已有的法向量会被覆盖
+    ///
+    /// 坐标相同的顶点（例如 uv 接缝两侧的顶点）视为同一个位置一起平滑。
+    /// crease_angle（弧度）不为空时，与当前面法向量夹角超过阈值的相邻面不参与平滑，
+    /// 硬边两侧得到不同法向量的顶点会被拆分成多个顶点
+    pub fn compute_normals(&mut self, weighting: NormalWeighting, crease_angle: Option<f32>) {
+        let face_normals: Vec<Vector3<f32>> =
+            (0..self.faces.len()).map(|f| self.face_normal(f)).collect();
+        let face_areas: Vec<f32> = (0..self.faces.len()).map(|f| self.face_area(f)).collect();
+
+        // 按坐标把面的角（face, slot）归到同一个位置
+        let (vertex_position, position_count) = self.position_ids();
+        let mut corners: Vec<Vec<(usize, usize)>> = vec![Vec::new(); position_count];
+        for (f, face) in self.faces.iter().enumerate() {
+            for (slot, &v) in face.iter().enumerate() {
+                corners[vertex_position[v]].push((f, slot));
+            }
+        }
+
+        let cos_crease = crease_angle.map(f32::cos);
+        let weight = |f: usize, slot: usize| -> f32 {
+            match weighting {
+                NormalWeighting::Area => face_areas[f],
+                NormalWeighting::Angle => self.corner_angle(f, slot),
+            }
+        };
+
+        // 计算每个角的法向量
+        let mut corner_normals: Vec<Vec<Vector3<f32>>> = self
+            .faces
+            .iter()
+            .map(|f| vec![Vector3::zeros(); f.len()])
+            .collect();
+        for around in &corners {
+            for &(f, slot) in around {
+                let mut normal = Vector3::zeros();
+                for &(g, g_slot) in around {
+                    let smooth = match cos_crease {
+                        Some(cos) => face_normals[f].dot(&face_normals[g]) >= cos - 1e-6,
+                        None => true,
+                    };
+                    if smooth {
+                        normal += face_normals[g] * weight(g, g_slot);
+                    }
+                }
+                corner_normals[f][slot] = normal
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(face_normals[f]);
+            }
+        }
+
+        // 同一个顶点的角如果法向量不同，需要拆分顶点
+        let mut assigned: Vec<Option<Vector3<f32>>> = vec![None; self.positions.len()];
+        let mut split: HashMap<(usize, [u32; 3]), usize> = HashMap::new();
+        let mut normals = vec![Vector3::zeros(); self.positions.len()];
+        for (f, face_corners) in corner_normals.iter().enumerate() {
+            for (slot, &n) in face_corners.iter().enumerate() {
+                let v = self.faces[f][slot];
+                match assigned[v] {
+                    None => {
+                        assigned[v] = Some(n);
+                        normals[v] = n;
+                    }
+                    Some(existing) if (existing - n).norm() <= 1e-5 => {}
+                    Some(_) => {
+                        let key = (v, [n.x.to_bits(), n.y.to_bits(), n.z.to_bits()]);
+                        let index = *split.entry(key).or_insert_with(|| {
+                            normals.push(n);
+                            self.duplicate_vertex(v)
+                        });
+                        self.faces[f][slot] = index;
+                    }
+                }
+            }
+        }
+        self.normals = normals;
+    }
+
+    /// 网格没有法向量时按夹角加权生成法向量
+    pub fn ensure_normals(&mut self, crease_angle: Option<f32>) {
+        if self.normals.len() != self.positions.len() {
+            self.compute_normals(NormalWeighting::Angle, crease_angle);
+        }
+    }
+
+    /// 多边形面积
+    pub fn face_area(&self, face: usize) -> f32 {
+        let face = &self.faces[face];
+        let p0 = self.positions[face[0]];
+        let mut sum = Vector3::zeros();
+        for i in 1..face.len().saturating_sub(1) {
+            sum += (self.positions[face[i]] - p0).cross(&(self.positions[face[i + 1]] - p0));
+        }
+        sum.norm() * 0.5
+    }
+
+    /// 面中第 slot 个顶点处两条边的夹角（弧度）
+    pub fn corner_angle(&self, face: usize, slot: usize) -> f32 {
+        let face = &self.faces[face];
+        let n = face.len();
+        let p: Point3<f32> = self.positions[face[slot]];
+        let prev = self.positions[face[(slot + n - 1) % n]] - p;
+        let next = self.positions[face[(slot + 1) % n]] - p;
+        match (
+            prev.try_normalize(f32::EPSILON),
+            next.try_normalize(f32::EPSILON),
+        ) {
+            (Some(a), Some(b)) => a.dot(&b).clamp(-1.0, 1.0).acos(),
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 轴对齐的长方体，8 个共享顶点，6 个四边形面
+    fn cuboid(x: f32, y: f32, z: f32) -> Mesh {
+        let mut mesh = Mesh::new("cuboid");
+        for i in 0..8 {
+            mesh.positions.push(Point3::new(
+                if i & 1 == 0 { 0.0 } else { x },
+                if i & 2 == 0 { 0.0 } else { y },
+                if i & 4 == 0 { 0.0 } else { z },
+            ));
+        }
+        mesh.faces = vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+        ];
+        mesh
+    }
+
+    #[test]
+    fn test_smooth_normals() {
+        let mut mesh = cuboid(1.0, 1.0, 1.0);
+        mesh.compute_normals(NormalWeighting::Area, None);
+        assert_eq!(mesh.vertex_count(), 8);
+        let expected = Vector3::new(1.0, 1.0, 1.0).normalize();
+        assert!((mesh.normals[7] - expected).norm() < 1e-5);
+        assert!((mesh.normals[0] + expected).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_crease_angle_split() {
+        let mut mesh = cuboid(1.0, 1.0, 1.0);
+        mesh.compute_normals(NormalWeighting::Angle, Some(30f32.to_radians()));
+        // 每个角拆分成 3 个顶点
+        assert_eq!(mesh.vertex_count(), 24);
+        for f in 0..mesh.face_count() {
+            let face_normal = mesh.face_normal(f);
+            for &v in &mesh.faces[f] {
+                assert!((mesh.normals[v] - face_normal).norm() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighting() {
+        // 长方体角上三个面的面积不同，但夹角都是 90 度
+        let mut angle = cuboid(4.0, 1.0, 1.0);
+        angle.compute_normals(NormalWeighting::Angle, None);
+        assert!((angle.normals[7] - Vector3::new(1.0, 1.0, 1.0).normalize()).norm() < 1e-5);
+
+        let mut area = cuboid(4.0, 1.0, 1.0);
+        area.compute_normals(NormalWeighting::Area, None);
+        let n = area.normals[7];
+        assert!(n.y > n.x && (n.y - n.z).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_ensure_normals() {
+        let mut mesh = cuboid(1.0, 1.0, 1.0);
+        mesh.triangulate();
+        mesh.ensure_normals(Some(30f32.to_radians()));
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        // 已经有法向量时不重新计算
+        let before = mesh.clone();
+        mesh.ensure_normals(None);
+        assert_eq!(mesh, before);
+    }
+
+    #[test]
+    fn test_uv_seam_smoothed_together() {
+        let mut mesh = cuboid(1.0, 1.0, 1.0);
+        // 复制顶点 7 模拟 uv 接缝
+        mesh.positions.push(mesh.positions[7]);
+        mesh.faces[5] = vec![1, 3, 8, 5];
+        mesh.compute_normals(NormalWeighting::Angle, None);
+        assert!((mesh.normals[7] - mesh.normals[8]).norm() < 1e-5);
+    }
+}
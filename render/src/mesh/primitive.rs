@@ -0,0 +1,732 @@
+//! 程序化生成的基本几何体
+//!
+//! 所有几何体都以 y 轴向上，中心在原点，面按逆时针顺序朝外，带有法向量和纹理坐标
+
+use super::Mesh;
+use nalgebra::{Point3, Vector2, Vector3};
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+/// XZ 平面上的矩形，法向量朝 +y，每个方向分成 segments 段
+pub fn plane(width: f32, depth: f32, segments: usize) -> Mesh {
+    let mut mesh = Mesh::new("plane");
+    let segments = segments.max(1);
+    add_grid(&mut mesh, segments, segments, |u, v| {
+        (
+            Point3::new((u - 0.5) * width, 0.0, (0.5 - v) * depth),
+            Vector3::y(),
+        )
+    });
+    mesh
+}
+
+/// 边长为 size 的立方体，每个面有独立的顶点和完整的 0~1 纹理坐标
+pub fn cube(size: f32) -> Mesh {
+    let mut mesh = Mesh::new("cube");
+    let half = size * 0.5;
+    // 每个面的法向量以及 u、v 方向，u x v = n
+    let faces = [
+        (Vector3::x(), -Vector3::z(), Vector3::y()),
+        (-Vector3::x(), Vector3::z(), Vector3::y()),
+        (Vector3::y(), Vector3::x(), -Vector3::z()),
+        (-Vector3::y(), Vector3::x(), Vector3::z()),
+        (Vector3::z(), Vector3::x(), Vector3::y()),
+        (-Vector3::z(), -Vector3::x(), Vector3::y()),
+    ];
+    for (n, right, up) in faces {
+        add_grid(&mut mesh, 1, 1, |u, v| {
+            let p = n * half + right * (u - 0.5) * size + up * (v - 0.5) * size;
+            (Point3::from(p), n)
+        });
+    }
+    mesh
+}
+
+/// 经纬度划分的球体，segments 为经线方向的分段数，rings 为纬线方向的分段数
+/// 纹理坐标 u 沿经度，v 从南极的 0 到北极的 1
+pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Mesh {
+    let mut mesh = Mesh::new("uv_sphere");
+    add_grid(&mut mesh, segments.max(3), rings.max(2), |u, v| {
+        let n = sphere_direction(u, v);
+        (Point3::from(n * radius), n)
+    });
+    mesh
+}
+
+/// 由正二十面体细分得到的球体，三角形的大小比经纬度球更均匀
+/// 纹理坐标和 uv_sphere 使用相同的球面映射，接缝和极点处的顶点会被拆分
+pub fn icosphere(radius: f32, subdivisions: usize) -> Mesh {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let mut points: Vec<Vector3<f32>> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .iter()
+    .map(|&p| Vector3::from(p).normalize())
+    .collect();
+    let mut triangles: Vec<[usize; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    // 每次把一个三角形分成四个，新顶点投影到球面上
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize| -> usize {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push((points[a] + points[b]).normalize());
+                points.len() - 1
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut mesh = Mesh::new("icosphere");
+    for n in &points {
+        mesh.positions.push(Point3::from(n * radius));
+        mesh.normals.push(*n);
+        let u = (-n.z).atan2(n.x).rem_euclid(TAU) / TAU;
+        let v = 1.0 - n.y.clamp(-1.0, 1.0).acos() / PI;
+        mesh.uvs.push(Vector2::new(u, v));
+    }
+
+    // 跨过 u = 0 接缝的三角形需要把 u 较小的顶点拆分出来加 1
+    let mut wrapped: HashMap<usize, usize> = HashMap::new();
+    for triangle in triangles.iter_mut() {
+        let us = triangle.map(|i| mesh.uvs[i].x);
+        let max = us.iter().cloned().fold(f32::MIN, f32::max);
+        for i in triangle.iter_mut() {
+            if max - mesh.uvs[*i].x > 0.5 && !is_pole(&mesh.normals[*i]) {
+                *i = *wrapped.entry(*i).or_insert_with(|| {
+                    let copy = mesh.duplicate_vertex(*i);
+                    mesh.uvs[copy].x += 1.0;
+                    copy
+                });
+            }
+        }
+    }
+    // 极点的 u 没有意义，每个三角形使用另外两个顶点的平均值
+    for triangle in triangles.iter_mut() {
+        for slot in 0..3 {
+            let pole = triangle[slot];
+            if is_pole(&mesh.normals[pole]) {
+                let others = [triangle[(slot + 1) % 3], triangle[(slot + 2) % 3]];
+                let u = (mesh.uvs[others[0]].x + mesh.uvs[others[1]].x) * 0.5;
+                let copy = mesh.duplicate_vertex(pole);
+                mesh.uvs[copy].x = u;
+                triangle[slot] = copy;
+            }
+        }
+    }
+    mesh.faces = triangles.iter().map(|t| t.to_vec()).collect();
+    mesh
+}
+
+fn is_pole(n: &Vector3<f32>) -> bool {
+    n.y.abs() > 1.0 - 1e-6
+}
+
+/// 沿 y 轴的圆柱，带有上下两个底面
+pub fn cylinder(radius: f32, height: f32, segments: usize) -> Mesh {
+    let mut mesh = Mesh::new("cylinder");
+    let segments = segments.max(3);
+    add_grid(&mut mesh, segments, 1, |u, v| {
+        let n = ring_direction(u);
+        (Point3::new(0.0, (v - 0.5) * height, 0.0) + n * radius, n)
+    });
+    add_disk(&mut mesh, radius, height * 0.5, segments, true);
+    add_disk(&mut mesh, radius, -height * 0.5, segments, false);
+    mesh
+}
+
+/// 沿 y 轴的圆锥，顶点在 +y，带有底面
+/// 顶点在每一列上单独生成，使用对应经度的侧面法向量
+pub fn cone(radius: f32, height: f32, segments: usize) -> Mesh {
+    let mut mesh = Mesh::new("cone");
+    let segments = segments.max(3);
+    add_grid(&mut mesh, segments, 1, |u, v| {
+        let d = ring_direction(u);
+        let n = (d * height + Vector3::y() * radius).normalize();
+        let p = Point3::new(0.0, (v - 0.5) * height, 0.0) + d * radius * (1.0 - v);
+        (p, n)
+    });
+    add_disk(&mut mesh, radius, -height * 0.5, segments, false);
+    mesh
+}
+
+/// 绕 y 轴的圆环，major 为圆环中心线的半径，minor 为截面圆的半径
+pub fn torus(major: f32, minor: f32, major_segments: usize, minor_segments: usize) -> Mesh {
+    let mut mesh = Mesh::new("torus");
+    add_grid(
+        &mut mesh,
+        major_segments.max(3),
+        minor_segments.max(3),
+        |u, v| {
+            let d = ring_direction(u);
+            let (sin, cos) = (v * TAU).sin_cos();
+            let n = d * cos + Vector3::y() * sin;
+            (Point3::from(d * major + n * minor), n)
+        },
+    );
+    mesh
+}
+
+/// 犹他茶壶，由 32 个双三次 Bézier 曲面片组成，每个曲面片划分成 tessellation x tessellation 的网格
+/// 壶嘴朝 +x，壶底在 y = 0 上，高度为 3.15
+/// 每个曲面片的纹理坐标是曲面的参数，法向量由曲面的偏导数计算
+pub fn teapot(tessellation: usize) -> Mesh {
+    let mut mesh = Mesh::new("teapot");
+    let n = tessellation.max(1);
+    for &(patch, symmetry) in TEAPOT_PATCHES {
+        let mirrors: &[(f32, f32)] = match symmetry {
+            4 => &[(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)],
+            _ => &[(1.0, 1.0), (1.0, -1.0)],
+        };
+        for &(sx, sy) in mirrors {
+            // 原始数据是 z 轴向上，旋转到 y 轴向上；+0.0 把镜像产生的 -0.0 统一成 0.0
+            let control: Vec<Vector3<f32>> = patch
+                .iter()
+                .map(|&i| {
+                    let [x, y, z] = TEAPOT_VERTICES[i];
+                    Vector3::new(x * sx + 0.0, z, -y * sy + 0.0)
+                })
+                .collect();
+            // 原始曲面片的行方向 x 列方向朝里，镜像一次会翻转方向
+            let flipped = sx * sy < 0.0;
+            add_grid(&mut mesh, n, n, |s, t| {
+                let (row, column) = if flipped { (s, t) } else { (t, s) };
+                let (p, d_row, d_column) = bezier_patch(&control, row, column);
+                let (ds, dt) = if flipped {
+                    (d_row, d_column)
+                } else {
+                    (d_column, d_row)
+                };
+                let degenerate = ds.norm_squared() < 1e-10 || dt.norm_squared() < 1e-10;
+                let normal = match ds.cross(&dt).try_normalize(1e-12) {
+                    Some(normal) if !degenerate => normal,
+                    // 曲面片退化成一点的位置（壶盖顶部和壶底中心）稍微偏移参数
+                    _ => {
+                        let eps = 1e-3;
+                        let row = row.clamp(eps, 1.0 - eps);
+                        let column = column.clamp(eps, 1.0 - eps);
+                        let (_, d_row, d_column) = bezier_patch(&control, row, column);
+                        let cross = d_row.cross(&d_column);
+                        if flipped { cross } else { -cross }.normalize()
+                    }
+                };
+                (Point3::from(p), normal)
+            });
+        }
+    }
+    mesh
+}
+
+/// 单位球面上的方向，u 沿经度，v 从南极到北极
+fn sphere_direction(u: f32, v: f32) -> Vector3<f32> {
+    let theta = (1.0 - v) * PI;
+    let (sin, cos) = theta.sin_cos();
+    ring_direction(u) * sin + Vector3::y() * cos
+}
+
+/// XZ 平面上的单位方向，u 从 0 到 1 时从 +x 开始从上往下看逆时针旋转一圈
+fn ring_direction(u: f32) -> Vector3<f32> {
+    let (sin, cos) = (u * TAU).sin_cos();
+    Vector3::new(cos, 0.0, -sin)
+}
+
+/// 添加一个参数化的网格曲面，f 根据纹理坐标 (u, v) 返回位置和法向量
+/// u 向右 v 向上时三角形按逆时针排列，即法向量的方向为 dP/du x dP/dv
+/// 退化的三角形（例如极点处有两个顶点重合）会被丢弃
+fn add_grid(
+    mesh: &mut Mesh,
+    columns: usize,
+    rows: usize,
+    f: impl Fn(f32, f32) -> (Point3<f32>, Vector3<f32>),
+) {
+    let offset = mesh.positions.len();
+    for j in 0..=rows {
+        for i in 0..=columns {
+            let (u, v) = (i as f32 / columns as f32, j as f32 / rows as f32);
+            let (p, n) = f(u, v);
+            mesh.positions.push(p);
+            mesh.normals.push(n);
+            mesh.uvs.push(Vector2::new(u, v));
+        }
+    }
+    let index = |i: usize, j: usize| offset + j * (columns + 1) + i;
+    for j in 0..rows {
+        for i in 0..columns {
+            let (a, b, c, d) = (
+                index(i, j),
+                index(i + 1, j),
+                index(i + 1, j + 1),
+                index(i, j + 1),
+            );
+            for triangle in [[a, b, c], [a, c, d]] {
+                let [p0, p1, p2] = mesh.triangle_positions(triangle);
+                let longest = [p1 - p0, p2 - p1, p0 - p2]
+                    .iter()
+                    .map(|e| e.norm_squared())
+                    .fold(0.0, f32::max);
+                if (p1 - p0).cross(&(p2 - p0)).norm() > longest * 1e-5 {
+                    mesh.faces.push(triangle.to_vec());
+                }
+            }
+        }
+    }
+}
+
+/// 添加 XZ 平面上的圆盘，up 为 true 时朝 +y，纹理坐标是圆盘在单位正方形中的投影
+fn add_disk(mesh: &mut Mesh, radius: f32, y: f32, segments: usize, up: bool) {
+    let normal = if up { Vector3::y() } else { -Vector3::y() };
+    let center = mesh.positions.len();
+    mesh.positions.push(Point3::new(0.0, y, 0.0));
+    mesh.normals.push(normal);
+    mesh.uvs.push(Vector2::new(0.5, 0.5));
+    for k in 0..segments {
+        let d = ring_direction(k as f32 / segments as f32);
+        mesh.positions.push(Point3::new(0.0, y, 0.0) + d * radius);
+        mesh.normals.push(normal);
+        let uv = if up {
+            Vector2::new(0.5 + d.x * 0.5, 0.5 - d.z * 0.5)
+        } else {
+            Vector2::new(0.5 + d.x * 0.5, 0.5 + d.z * 0.5)
+        };
+        mesh.uvs.push(uv);
+    }
+    for k in 0..segments {
+        let (a, b) = (center + 1 + k, center + 1 + (k + 1) % segments);
+        let face = if up {
+            vec![center, a, b]
+        } else {
+            vec![center, b, a]
+        };
+        mesh.faces.push(face);
+    }
+}
+
+/// 三次 Bernstein 基函数及其导数
+fn bernstein(t: f32) -> ([f32; 4], [f32; 4]) {
+    let s = 1.0 - t;
+    (
+        [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t],
+        [
+            -3.0 * s * s,
+            3.0 * s * s - 6.0 * t * s,
+            6.0 * t * s - 3.0 * t * t,
+            3.0 * t * t,
+        ],
+    )
+}
+
+/// 计算 4x4 控制点的 Bézier 曲面片上的点，以及沿行方向和列方向的偏导数
+fn bezier_patch(
+    control: &[Vector3<f32>],
+    row: f32,
+    column: f32,
+) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+    let (br, dbr) = bernstein(row);
+    let (bc, dbc) = bernstein(column);
+    let mut p = Vector3::zeros();
+    let mut d_row = Vector3::zeros();
+    let mut d_column = Vector3::zeros();
+    for i in 0..4 {
+        for j in 0..4 {
+            let c = control[i * 4 + j];
+            p += c * br[i] * bc[j];
+            d_row += c * dbr[i] * bc[j];
+            d_column += c * br[i] * dbc[j];
+        }
+    }
+    (p, d_row, d_column)
+}
+
+/// 茶壶的曲面片（控制点索引）以及对称的数量：4 表示关于 x、y 轴都对称，2 表示只关于 y 轴对称
+const TEAPOT_PATCHES: &[([usize; 16], u8)] = &[
+    // 壶口边缘
+    (
+        [102, 103, 104, 105, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        4,
+    ),
+    // 壶身
+    (
+        [
+            12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+        ],
+        4,
+    ),
+    (
+        [
+            24, 25, 26, 27, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40,
+        ],
+        4,
+    ),
+    // 壶盖
+    (
+        [
+            96, 96, 96, 96, 97, 98, 99, 100, 101, 101, 101, 101, 0, 1, 2, 3,
+        ],
+        4,
+    ),
+    (
+        [
+            0, 1, 2, 3, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117,
+        ],
+        4,
+    ),
+    // 壶底
+    (
+        [
+            118, 118, 118, 118, 124, 122, 119, 121, 123, 126, 125, 120, 40, 39, 38, 37,
+        ],
+        4,
+    ),
+    // 把手
+    (
+        [
+            41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56,
+        ],
+        2,
+    ),
+    (
+        [
+            53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 28, 65, 66, 67,
+        ],
+        2,
+    ),
+    // 壶嘴
+    (
+        [
+            68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83,
+        ],
+        2,
+    ),
+    (
+        [
+            80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95,
+        ],
+        2,
+    ),
+];
+
+/// 茶壶的控制点，z 轴向上
+const TEAPOT_VERTICES: [[f32; 3]; 127] = [
+    [0.2, 0.0, 2.7],
+    [0.2, -0.112, 2.7],
+    [0.112, -0.2, 2.7],
+    [0.0, -0.2, 2.7],
+    [1.3375, 0.0, 2.53125],
+    [1.3375, -0.749, 2.53125],
+    [0.749, -1.3375, 2.53125],
+    [0.0, -1.3375, 2.53125],
+    [1.4375, 0.0, 2.53125],
+    [1.4375, -0.805, 2.53125],
+    [0.805, -1.4375, 2.53125],
+    [0.0, -1.4375, 2.53125],
+    [1.5, 0.0, 2.4],
+    [1.5, -0.84, 2.4],
+    [0.84, -1.5, 2.4],
+    [0.0, -1.5, 2.4],
+    [1.75, 0.0, 1.875],
+    [1.75, -0.98, 1.875],
+    [0.98, -1.75, 1.875],
+    [0.0, -1.75, 1.875],
+    [2.0, 0.0, 1.35],
+    [2.0, -1.12, 1.35],
+    [1.12, -2.0, 1.35],
+    [0.0, -2.0, 1.35],
+    [2.0, 0.0, 0.9],
+    [2.0, -1.12, 0.9],
+    [1.12, -2.0, 0.9],
+    [0.0, -2.0, 0.9],
+    [-2.0, 0.0, 0.9],
+    [2.0, 0.0, 0.45],
+    [2.0, -1.12, 0.45],
+    [1.12, -2.0, 0.45],
+    [0.0, -2.0, 0.45],
+    [1.5, 0.0, 0.225],
+    [1.5, -0.84, 0.225],
+    [0.84, -1.5, 0.225],
+    [0.0, -1.5, 0.225],
+    [1.5, 0.0, 0.15],
+    [1.5, -0.84, 0.15],
+    [0.84, -1.5, 0.15],
+    [0.0, -1.5, 0.15],
+    [-1.6, 0.0, 2.025],
+    [-1.6, -0.3, 2.025],
+    [-1.5, -0.3, 2.25],
+    [-1.5, 0.0, 2.25],
+    [-2.3, 0.0, 2.025],
+    [-2.3, -0.3, 2.025],
+    [-2.5, -0.3, 2.25],
+    [-2.5, 0.0, 2.25],
+    [-2.7, 0.0, 2.025],
+    [-2.7, -0.3, 2.025],
+    [-3.0, -0.3, 2.25],
+    [-3.0, 0.0, 2.25],
+    [-2.7, 0.0, 1.8],
+    [-2.7, -0.3, 1.8],
+    [-3.0, -0.3, 1.8],
+    [-3.0, 0.0, 1.8],
+    [-2.7, 0.0, 1.575],
+    [-2.7, -0.3, 1.575],
+    [-3.0, -0.3, 1.35],
+    [-3.0, 0.0, 1.35],
+    [-2.5, 0.0, 1.125],
+    [-2.5, -0.3, 1.125],
+    [-2.65, -0.3, 0.9375],
+    [-2.65, 0.0, 0.9375],
+    [-2.0, -0.3, 0.9],
+    [-1.9, -0.3, 0.6],
+    [-1.9, 0.0, 0.6],
+    [1.7, 0.0, 1.425],
+    [1.7, -0.66, 1.425],
+    [1.7, -0.66, 0.6],
+    [1.7, 0.0, 0.6],
+    [2.6, 0.0, 1.425],
+    [2.6, -0.66, 1.425],
+    [3.1, -0.66, 0.825],
+    [3.1, 0.0, 0.825],
+    [2.3, 0.0, 2.1],
+    [2.3, -0.25, 2.1],
+    [2.4, -0.25, 2.025],
+    [2.4, 0.0, 2.025],
+    [2.7, 0.0, 2.4],
+    [2.7, -0.25, 2.4],
+    [3.3, -0.25, 2.4],
+    [3.3, 0.0, 2.4],
+    [2.8, 0.0, 2.475],
+    [2.8, -0.25, 2.475],
+    [3.525, -0.25, 2.49375],
+    [3.525, 0.0, 2.49375],
+    [2.9, 0.0, 2.475],
+    [2.9, -0.15, 2.475],
+    [3.45, -0.15, 2.5125],
+    [3.45, 0.0, 2.5125],
+    [2.8, 0.0, 2.4],
+    [2.8, -0.15, 2.4],
+    [3.2, -0.15, 2.4],
+    [3.2, 0.0, 2.4],
+    [0.0, 0.0, 3.15],
+    [0.8, 0.0, 3.15],
+    [0.8, -0.45, 3.15],
+    [0.45, -0.8, 3.15],
+    [0.0, -0.8, 3.15],
+    [0.0, 0.0, 2.85],
+    [1.4, 0.0, 2.4],
+    [1.4, -0.784, 2.4],
+    [0.784, -1.4, 2.4],
+    [0.0, -1.4, 2.4],
+    [0.4, 0.0, 2.55],
+    [0.4, -0.224, 2.55],
+    [0.224, -0.4, 2.55],
+    [0.0, -0.4, 2.55],
+    [1.3, 0.0, 2.55],
+    [1.3, -0.728, 2.55],
+    [0.728, -1.3, 2.55],
+    [0.0, -1.3, 2.55],
+    [1.3, 0.0, 2.4],
+    [1.3, -0.728, 2.4],
+    [0.728, -1.3, 2.4],
+    [0.0, -1.3, 2.4],
+    [0.0, 0.0, 0.0],
+    [1.425, -0.798, 0.0],
+    [1.5, 0.0, 0.075],
+    [1.425, 0.0, 0.0],
+    [0.798, -1.425, 0.0],
+    [0.0, -1.5, 0.075],
+    [0.0, -1.425, 0.0],
+    [1.5, -0.84, 0.075],
+    [0.84, -1.5, 0.075],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(mesh: &Mesh) -> f32 {
+        (0..mesh.face_count()).map(|f| mesh.face_area(f)).sum()
+    }
+
+    /// 检查属性完整，法向量为单位向量，并且和所在面的朝向一致
+    fn assert_valid(mesh: &Mesh) {
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        assert_eq!(mesh.uvs.len(), mesh.vertex_count());
+        assert!(mesh.is_triangulated());
+        for n in &mesh.normals {
+            assert!((n.norm() - 1.0).abs() < 1e-4);
+        }
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let normal = mesh.face_normal(f);
+            for &v in face {
+                assert!(mesh.normals[v].dot(&normal) > 0.0);
+            }
+        }
+    }
+
+    /// 检查每个面都背离 center 朝外
+    fn assert_outward(mesh: &Mesh, center: impl Fn(&Point3<f32>) -> Point3<f32>) {
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let sum = face
+                .iter()
+                .fold(Vector3::zeros(), |s, &v| s + mesh.positions[v].coords);
+            let centroid = Point3::from(sum / face.len() as f32);
+            assert!(mesh.face_normal(f).dot(&(centroid - center(&centroid))) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_plane() {
+        let mesh = plane(2.0, 3.0, 4);
+        assert_valid(&mesh);
+        assert_eq!(mesh.vertex_count(), 25);
+        assert_eq!(mesh.face_count(), 32);
+        assert!((area(&mesh) - 6.0).abs() < 1e-5);
+        assert!(mesh.normals.iter().all(|n| *n == Vector3::y()));
+        // u 沿 +x，v 沿 -z
+        assert_eq!(mesh.positions[0], Point3::new(-1.0, 0.0, 1.5));
+        assert_eq!(mesh.uvs[24], Vector2::new(1.0, 1.0));
+        assert_eq!(mesh.positions[24], Point3::new(1.0, 0.0, -1.5));
+    }
+
+    #[test]
+    fn test_cube() {
+        let mesh = cube(2.0);
+        assert_valid(&mesh);
+        assert_outward(&mesh, |_| Point3::origin());
+        assert_eq!(mesh.vertex_count(), 24);
+        assert_eq!(mesh.face_count(), 12);
+        assert!((area(&mesh) - 24.0).abs() < 1e-5);
+        for p in &mesh.positions {
+            assert_eq!(p.coords.amax(), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_spheres() {
+        let uv = uv_sphere(2.0, 64, 32);
+        // 极点处的退化三角形被丢弃
+        assert_eq!(uv.face_count(), 64 * 32 * 2 - 64 * 2);
+        let ico = icosphere(2.0, 4);
+        assert_eq!(ico.face_count(), 20 * 256);
+        let expected = 4.0 * PI * 4.0;
+        for mesh in [&uv, &ico] {
+            assert_valid(mesh);
+            assert_outward(mesh, |_| Point3::origin());
+            assert!((area(mesh) - expected).abs() / expected < 0.01);
+            for p in &mesh.positions {
+                assert!((p.coords.norm() - 2.0).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_icosphere_uv_seam() {
+        let mesh = icosphere(1.0, 2);
+        for face in &mesh.faces {
+            let us: Vec<f32> = face.iter().map(|&v| mesh.uvs[v].x).collect();
+            let span = us.iter().cloned().fold(f32::MIN, f32::max)
+                - us.iter().cloned().fold(f32::MAX, f32::min);
+            assert!(span < 0.5);
+        }
+        for (p, uv) in mesh.positions.iter().zip(&mesh.uvs) {
+            assert!((uv.y - (1.0 - p.y.clamp(-1.0, 1.0).acos() / PI)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_cylinder_and_cone() {
+        let cylinder = cylinder(1.0, 2.0, 128);
+        assert_valid(&cylinder);
+        assert_outward(&cylinder, |p| {
+            if p.y.abs() > 0.999 {
+                Point3::origin()
+            } else {
+                Point3::new(0.0, p.y, 0.0)
+            }
+        });
+        let expected = 2.0 * PI * 2.0 + 2.0 * PI;
+        assert!((area(&cylinder) - expected).abs() / expected < 0.01);
+
+        let cone = cone(1.0, 1.0, 128);
+        assert_valid(&cone);
+        assert_outward(&cone, |_| Point3::new(0.0, -0.25, 0.0));
+        let expected = PI * 2f32.sqrt() + PI;
+        assert!((area(&cone) - expected).abs() / expected < 0.01);
+    }
+
+    #[test]
+    fn test_torus() {
+        let mesh = torus(2.0, 0.5, 64, 32);
+        assert_valid(&mesh);
+        // 每个面背离截面圆的圆心
+        assert_outward(&mesh, |p| {
+            let d = Vector3::new(p.x, 0.0, p.z).normalize();
+            Point3::from(d * 2.0)
+        });
+        let expected = 4.0 * PI * PI * 2.0 * 0.5;
+        assert!((area(&mesh) - expected).abs() / expected < 0.01);
+    }
+
+    #[test]
+    fn test_teapot() {
+        let mesh = teapot(8);
+        assert_valid(&mesh);
+        // 4 x 6 + 2 x 4 个曲面片
+        let patches = 32;
+        assert!(mesh.face_count() <= patches * 8 * 8 * 2);
+        assert_eq!(mesh.vertex_count(), patches * 9 * 9);
+
+        let (min, max) = mesh.positions.iter().fold(
+            (Point3::new(f32::MAX, f32::MAX, f32::MAX), Point3::origin()),
+            |(min, max), p| (min.inf(p), max.sup(p)),
+        );
+        assert!((min.y).abs() < 1e-6 && (max.y - 3.15).abs() < 1e-5);
+        assert!((min.x + 3.0).abs() < 0.1 && (max.x - 3.43).abs() < 0.1);
+        assert!((min.z + 2.0).abs() < 1e-5 && (max.z - 2.0).abs() < 1e-5);
+
+        // 散度定理：朝外的封闭曲面围成的体积为正
+        let volume: f32 = mesh
+            .triangles()
+            .map(|t| {
+                let [a, b, c] = mesh.triangle_positions(t);
+                a.coords.dot(&b.coords.cross(&c.coords)) / 6.0
+            })
+            .sum();
+        assert!(volume > 0.0);
+    }
+}
@@ -0,0 +1,366 @@
+use super::validate::Issue;
+use super::Mesh;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// 焊接顶点时比较其他属性使用的误差
+const ATTRIBUTE_TOLERANCE: f32 = 1e-5;
+
+impl Mesh {
+    /// 依次删除退化的面、焊接顶点、删除重复的面并统一朝向，返回修复之后仍然存在的问题
+    pub fn repair(&mut self, tolerance: f32) -> Vec<Issue> {
+        self.remove_degenerate_faces();
+        self.weld(tolerance);
+        // 焊接之后可能产生新的退化面
+        self.remove_degenerate_faces();
+        self.remove_duplicate_faces();
+        self.unify_orientation();
+        self.remove_unused_vertices();
+        self.validate()
+    }
+
+    /// 合并距离不超过 tolerance 并且其他属性都相同的顶点，返回删除的顶点数
+    /// tolerance 为 0 时只合并坐标完全相同的顶点，uv 接缝两侧的顶点因为纹理坐标不同不会被合并
+    pub fn weld(&mut self, tolerance: f32) -> usize {
+        let count = self.positions.len();
+        let cell = |c: f32| -> i64 {
+            if tolerance > 0.0 {
+                (c / tolerance).floor() as i64
+            } else {
+                // 加 0 使 -0 和 0 落在同一个单元中
+                (c + 0.0).to_bits() as i64
+            }
+        };
+        // 网格单元 -> 落在其中的代表顶点
+        let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        let mut remap: Vec<usize> = (0..count).collect();
+        for (v, &p) in self.positions.iter().enumerate() {
+            if !p.iter().all(|c| c.is_finite()) {
+                continue;
+            }
+            let key = [cell(p.x), cell(p.y), cell(p.z)];
+            // 有误差时相邻的单元中也可能有距离足够近的顶点
+            let range = if tolerance > 0.0 { -1..=1 } else { 0..=0 };
+            let mut found = None;
+            'search: for dx in range.clone() {
+                for dy in range.clone() {
+                    for dz in range.clone() {
+                        let neighbor = [key[0] + dx, key[1] + dy, key[2] + dz];
+                        for &r in grid.get(&neighbor).into_iter().flatten() {
+                            if (self.positions[r] - p).norm() <= tolerance
+                                && self.same_attributes(r, v)
+                            {
+                                found = Some(r);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+            match found {
+                Some(r) => remap[v] = r,
+                None => grid.entry(key).or_default().push(v),
+            }
+        }
+        for v in self.faces.iter_mut().flatten() {
+            if *v < count {
+                *v = remap[*v];
+            }
+        }
+        self.remove_unused_vertices()
+    }
+
+    fn same_attributes(&self, a: usize, b: usize) -> bool {
+        fn close<const N: usize>(values: &[nalgebra::SVector<f32, N>], a: usize, b: usize) -> bool {
+            values.len() <= a.max(b) || (values[a] - values[b]).amax() <= ATTRIBUTE_TOLERANCE
+        }
+        close(&self.normals, a, b)
+            && close(&self.uvs, a, b)
+            && close(&self.colors, a, b)
+            && close(&self.tangents, a, b)
+            && self.joints.get(a) == self.joints.get(b)
+            && close(&self.weights, a, b)
+            && self
+                .targets
+                .iter()
+                .all(|t| close(&t.positions, a, b) && close(&t.normals, a, b))
+    }
+
+    /// 删除退化的面，返回删除的面数
+    /// 面中连续重复的顶点会先被合并，之后删除引用了不存在的顶点、
+    /// 顶点坐标无效、少于 3 个不同位置或者面积为 0 的面
+    pub fn remove_degenerate_faces(&mut self) -> usize {
+        for face in self.faces.iter_mut() {
+            face.dedup();
+            while face.len() > 1 && face.first() == face.last() {
+                face.pop();
+            }
+        }
+        let count = self.positions.len();
+        let (vertex_position, _) = self.position_ids();
+        let valid: Vec<bool> = self
+            .faces
+            .iter()
+            .enumerate()
+            .map(|(f, face)| {
+                if face.len() < 3 || face.iter().any(|&v| v >= count) {
+                    return false;
+                }
+                if !face
+                    .iter()
+                    .all(|&v| self.positions[v].iter().all(|c| c.is_finite()))
+                {
+                    return false;
+                }
+                let mut ids: Vec<usize> = face.iter().map(|&v| vertex_position[v]).collect();
+                ids.sort_unstable();
+                ids.windows(2).all(|w| w[0] != w[1]) && self.face_area(f) > 0.0
+            })
+            .collect();
+        self.retain_faces(|f, _| valid[f])
+    }
+
+    /// 删除和之前的面使用相同顶点位置的面（不考虑顶点顺序），返回删除的面数
+    pub fn remove_duplicate_faces(&mut self) -> usize {
+        let count = self.positions.len();
+        let (vertex_position, _) = self.position_ids();
+        let mut seen = HashSet::new();
+        self.retain_faces(|_, face| {
+            if face.iter().any(|&v| v >= count) {
+                return true;
+            }
+            let mut key: Vec<usize> = face.iter().map(|&v| vertex_position[v]).collect();
+            key.sort_unstable();
+            seen.insert(key)
+        })
+    }
+
+    /// 统一面的朝向，返回翻转的面数
+    ///
+    /// 在每个连通区域内从第一个面开始，沿流形边让相邻的面以相反的方向经过共享的边。
+    /// 封闭的区域在统一之后如果围成的体积为负，说明整体朝里，会再整体翻转一次
+    pub fn unify_orientation(&mut self) -> usize {
+        let count = self.positions.len();
+        let (vertex_position, _) = self.position_ids();
+        let valid = |face: &Vec<usize>| face.len() >= 3 && face.iter().all(|&v| v < count);
+
+        // 边 -> 经过这条边的面以及方向是否和编号顺序一致
+        let mut edges: HashMap<(usize, usize), Vec<(usize, bool)>> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            if !valid(face) {
+                continue;
+            }
+            for (i, &a) in face.iter().enumerate() {
+                let (pa, pb) = (
+                    vertex_position[a],
+                    vertex_position[face[(i + 1) % face.len()]],
+                );
+                if pa != pb {
+                    edges
+                        .entry((pa.min(pb), pa.max(pb)))
+                        .or_default()
+                        .push((f, pa < pb));
+                }
+            }
+        }
+        let face_edges = |face: &Vec<usize>| -> Vec<(usize, usize)> {
+            (0..face.len())
+                .map(|i| {
+                    let (pa, pb) = (
+                        vertex_position[face[i]],
+                        vertex_position[face[(i + 1) % face.len()]],
+                    );
+                    (pa.min(pb), pa.max(pb))
+                })
+                .filter(|(a, b)| a != b)
+                .collect()
+        };
+
+        let mut flip = vec![false; self.faces.len()];
+        let mut visited = vec![false; self.faces.len()];
+        for start in 0..self.faces.len() {
+            if visited[start] || !valid(&self.faces[start]) {
+                continue;
+            }
+            visited[start] = true;
+            let mut component = vec![start];
+            let mut closed = true;
+            let mut queue = VecDeque::from([start]);
+            while let Some(f) = queue.pop_front() {
+                for key in face_edges(&self.faces[f]) {
+                    let users = &edges[&key];
+                    if users.len() != 2 {
+                        closed = false;
+                        continue;
+                    }
+                    let (mine, other) = if users[0].0 == f {
+                        (users[0], users[1])
+                    } else {
+                        (users[1], users[0])
+                    };
+                    if visited[other.0] {
+                        continue;
+                    }
+                    visited[other.0] = true;
+                    // 原始方向相同时两个面的翻转状态需要相反
+                    flip[other.0] = flip[f] ^ (mine.1 == other.1);
+                    component.push(other.0);
+                    queue.push_back(other.0);
+                }
+            }
+
+            if closed {
+                let mut volume = 0.0;
+                for &f in &component {
+                    let face = &self.faces[f];
+                    let p0 = self.positions[face[0]].coords;
+                    let mut sum = 0.0;
+                    for i in 1..face.len() - 1 {
+                        let p1 = self.positions[face[i]].coords;
+                        let p2 = self.positions[face[i + 1]].coords;
+                        sum += p0.dot(&p1.cross(&p2));
+                    }
+                    volume += if flip[f] { -sum } else { sum };
+                }
+                if volume < 0.0 {
+                    for &f in &component {
+                        flip[f] = !flip[f];
+                    }
+                }
+            }
+        }
+
+        let mut flipped = 0;
+        for (face, flip) in self.faces.iter_mut().zip(flip) {
+            if flip {
+                face.reverse();
+                flipped += 1;
+            }
+        }
+        flipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{primitive, Group};
+    use nalgebra::{Point3, Vector3};
+
+    /// 所有面的法向量都背离原点
+    fn outward(mesh: &Mesh) -> bool {
+        (0..mesh.face_count()).all(|f| {
+            let face = &mesh.faces[f];
+            let centroid: Vector3<f32> = face
+                .iter()
+                .map(|&v| mesh.positions[v].coords)
+                .sum::<Vector3<f32>>()
+                / face.len() as f32;
+            mesh.face_normal(f).dot(&centroid) > 0.0
+        })
+    }
+
+    #[test]
+    fn test_weld() {
+        let mut mesh = primitive::cube(2.0);
+        // 每个面的法向量不同，不能合并
+        assert_eq!(mesh.weld(0.0), 0);
+        mesh.normals.clear();
+        mesh.uvs.clear();
+        assert_eq!(mesh.weld(0.0), 16);
+        assert_eq!(mesh.vertex_count(), 8);
+        assert!(mesh.validate().is_empty());
+
+        let mut mesh = primitive::cube(2.0);
+        mesh.normals.clear();
+        mesh.uvs.clear();
+        for (i, p) in mesh.positions.iter_mut().enumerate() {
+            p.x += i as f32 * 1e-6;
+        }
+        assert_eq!(mesh.clone().weld(0.0), 0);
+        assert_eq!(mesh.weld(1e-3), 16);
+    }
+
+    #[test]
+    fn test_remove_degenerate_faces() {
+        let mut mesh = primitive::plane(1.0, 1.0, 1);
+        mesh.positions.push(Point3::new(f32::NAN, 0.0, 0.0));
+        mesh.faces = vec![
+            vec![0, 1, 1, 2],
+            vec![0, 1, 9],
+            vec![0, 2, 2],
+            vec![0, 1, 4],
+            vec![0, 2, 3, 0],
+        ];
+        mesh.groups = vec![
+            Group {
+                name: "a".to_string(),
+                material: None,
+                start: 0,
+                count: 3,
+            },
+            Group {
+                name: "b".to_string(),
+                material: None,
+                start: 3,
+                count: 2,
+            },
+        ];
+        assert_eq!(mesh.remove_degenerate_faces(), 3);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert_eq!((mesh.groups[0].start, mesh.groups[0].count), (0, 1));
+        assert_eq!((mesh.groups[1].start, mesh.groups[1].count), (1, 1));
+        assert_eq!(mesh.remove_unused_vertices(), 1);
+        assert!(mesh.validate().is_empty());
+    }
+
+    #[test]
+    fn test_remove_duplicate_faces() {
+        let mut mesh = primitive::plane(1.0, 1.0, 1);
+        mesh.faces.push(vec![3, 1, 0]);
+        mesh.faces.push(vec![2, 0, 3]);
+        assert_eq!(mesh.remove_duplicate_faces(), 2);
+        assert_eq!(mesh.face_count(), 2);
+    }
+
+    #[test]
+    fn test_unify_orientation() {
+        let mut mesh = primitive::icosphere(1.0, 2);
+        for f in (0..mesh.face_count()).step_by(3) {
+            mesh.faces[f].reverse();
+        }
+        assert!(!mesh.validate().is_empty());
+        assert_eq!(mesh.unify_orientation(), mesh.face_count().div_ceil(3));
+        assert!(mesh.validate().is_empty());
+        assert!(outward(&mesh));
+
+        // 整体朝里的封闭网格会被整体翻转
+        let mut mesh = primitive::cube(1.0);
+        for face in mesh.faces.iter_mut() {
+            face.reverse();
+        }
+        assert_eq!(mesh.unify_orientation(), 12);
+        assert!(outward(&mesh));
+
+        // 开放的网格只保证和第一个面一致
+        let mut mesh = primitive::plane(1.0, 1.0, 4);
+        mesh.faces[5].reverse();
+        assert_eq!(mesh.unify_orientation(), 1);
+        assert!(mesh.validate().is_empty());
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut mesh = primitive::uv_sphere(1.0, 16, 8);
+        mesh.normals.clear();
+        mesh.uvs.clear();
+        let copy = mesh.faces[3].clone();
+        mesh.faces.push(copy);
+        mesh.faces[10].reverse();
+        mesh.faces.push(vec![0, 0, 1]);
+        assert!(!mesh.validate().is_empty());
+        assert!(mesh.repair(1e-5).is_empty());
+        assert!(outward(&mesh));
+        // 经纬度球的接缝和极点被焊接
+        assert_eq!(mesh.vertex_count(), 16 * 7 + 2);
+    }
+}
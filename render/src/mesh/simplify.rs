@@ -0,0 +1,604 @@
+use super::{Group, Mesh, MorphTarget};
+use nalgebra::{Matrix4, Point3, Vector3, Vector4};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+/// 边界约束平面的权重，越大边界越不容易收缩
+const BOUNDARY_WEIGHT: f64 = 10.0;
+
+/// 网格简化参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimplifyOptions {
+    /// 目标三角形数量，达到后停止
+    pub target_triangles: usize,
+    /// 允许的最大误差，即顶点到原始相邻平面距离的平方和，超过时停止
+    pub max_error: f32,
+    /// 锁定边界上的顶点
+    pub preserve_boundary: bool,
+    /// 锁定 uv 接缝上的顶点（同一坐标有多个不同属性的顶点）
+    pub preserve_uv_seams: bool,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        SimplifyOptions {
+            target_triangles: 0,
+            max_error: f32::INFINITY,
+            preserve_boundary: false,
+            preserve_uv_seams: false,
+        }
+    }
+}
+
+impl Mesh {
+    /// 基于二次误差度量（QEM）的边折叠简化
+    ///
+    /// 折叠时顶点移动到边的另一个端点上（half-edge collapse），所以保留下来的顶点属性不需要插值。
+    /// 坐标相同的顶点视为同一个位置，uv 接缝两侧会一起移动而不会裂开。
+    /// 返回三角化后的新网格，分组随三角形一起保留，面积为 0 的三角形会被删除
+    pub fn simplify(&self, options: &SimplifyOptions) -> Mesh {
+        let mut mesh = self.clone();
+        mesh.triangulate();
+        let mut simplifier = Simplifier::new(&mesh, options);
+        simplifier.run(options);
+        simplifier.build(&mesh)
+    }
+}
+
+/// 候选的折叠操作：把位置 from 移动到位置 to
+struct Collapse {
+    error: f64,
+    /// 边长的平方，误差相同时优先折叠短边
+    length: f64,
+    from: usize,
+    to: usize,
+    /// 生成候选时两个位置的版本号，用于延迟删除过期的候选
+    stamps: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    /// BinaryHeap 是最大堆，误差小的优先，误差相同时边短的优先，最后按位置编号排序保证结果确定
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .error
+            .total_cmp(&self.error)
+            .then_with(|| other.length.total_cmp(&self.length))
+            .then_with(|| (other.from, other.to).cmp(&(self.from, self.to)))
+    }
+}
+
+struct Simplifier<'a> {
+    mesh: &'a Mesh,
+    /// 顶点 -> 位置
+    vertex_position: Vec<usize>,
+    /// 位置 -> 属于该位置的顶点
+    position_vertices: Vec<Vec<usize>>,
+    points: Vec<Point3<f64>>,
+    quadrics: Vec<Matrix4<f64>>,
+    locked: Vec<bool>,
+    removed: Vec<bool>,
+    stamps: Vec<u32>,
+    /// 三角形的顶点索引
+    triangles: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    live_count: usize,
+    /// 位置 -> 相邻的三角形（可能包含已经删除的三角形）
+    position_triangles: Vec<Vec<usize>>,
+    heap: BinaryHeap<Collapse>,
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(mesh: &'a Mesh, options: &SimplifyOptions) -> Self {
+        let mut ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut points = Vec::new();
+        let mut position_vertices: Vec<Vec<usize>> = Vec::new();
+        let vertex_position: Vec<usize> = mesh
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(v, p)| {
+                let id = *ids
+                    .entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()])
+                    .or_insert_with(|| {
+                        points.push(p.cast::<f64>());
+                        position_vertices.push(Vec::new());
+                        points.len() - 1
+                    });
+                position_vertices[id].push(v);
+                id
+            })
+            .collect();
+
+        let count = points.len();
+        let triangles: Vec<[usize; 3]> = mesh.faces.iter().map(|f| [f[0], f[1], f[2]]).collect();
+        let mut simplifier = Simplifier {
+            mesh,
+            vertex_position,
+            position_vertices,
+            points,
+            quadrics: vec![Matrix4::zeros(); count],
+            locked: vec![false; count],
+            removed: vec![false; count],
+            stamps: vec![0; count],
+            live_count: triangles.len(),
+            alive: vec![true; triangles.len()],
+            triangles,
+            position_triangles: vec![Vec::new(); count],
+            heap: BinaryHeap::new(),
+        };
+
+        // 每个位置的误差二次型是相邻三角形平面的二次型之和
+        // 边按顺序遍历，使约束平面的累加顺序和候选的顺序都是确定的
+        let mut edges: BTreeMap<(usize, usize), (usize, usize)> = BTreeMap::new();
+        for t in 0..simplifier.triangles.len() {
+            let p = simplifier.triangle_positions(t);
+            // 退化的三角形没有平面，也不参与折叠，直接删除
+            let Some((_, plane)) = simplifier.plane(p) else {
+                simplifier.alive[t] = false;
+                simplifier.live_count -= 1;
+                continue;
+            };
+            for i in 0..3 {
+                simplifier.quadrics[p[i]] += plane;
+                simplifier.position_triangles[p[i]].push(t);
+                let (a, b) = (p[i], p[(i + 1) % 3]);
+                edges.entry((a.min(b), a.max(b))).or_insert((0, t)).0 += 1;
+            }
+        }
+
+        // 只被一个三角形使用的边是边界，加入垂直于三角形的约束平面防止边界收缩
+        for (&(a, b), &(uses, t)) in &edges {
+            if uses != 1 {
+                continue;
+            }
+            if options.preserve_boundary {
+                simplifier.locked[a] = true;
+                simplifier.locked[b] = true;
+            }
+            let Some((normal, _)) = simplifier.plane(simplifier.triangle_positions(t)) else {
+                continue;
+            };
+            let edge = simplifier.points[b] - simplifier.points[a];
+            if let Some(n) = edge.cross(&normal).try_normalize(f64::EPSILON) {
+                let plane = plane_quadric(&n, &simplifier.points[a]) * BOUNDARY_WEIGHT;
+                simplifier.quadrics[a] += plane;
+                simplifier.quadrics[b] += plane;
+            }
+        }
+
+        if options.preserve_uv_seams {
+            for (p, vertices) in simplifier.position_vertices.iter().enumerate() {
+                if vertices.len() > 1 {
+                    simplifier.locked[p] = true;
+                }
+            }
+        }
+
+        for &(a, b) in edges.keys() {
+            simplifier.push(a, b);
+            simplifier.push(b, a);
+        }
+        simplifier
+    }
+
+    fn triangle_positions(&self, t: usize) -> [usize; 3] {
+        self.triangles[t].map(|v| self.vertex_position[v])
+    }
+
+    /// 三角形的单位法向量以及平面的二次型，退化三角形返回 None
+    fn plane(&self, p: [usize; 3]) -> Option<(Vector3<f64>, Matrix4<f64>)> {
+        let [a, b, c] = p.map(|i| self.points[i]);
+        let normal = (b - a).cross(&(c - a)).try_normalize(f64::EPSILON)?;
+        Some((normal, plane_quadric(&normal, &a)))
+    }
+
+    fn error(&self, from: usize, to: usize) -> f64 {
+        let q = self.quadrics[from] + self.quadrics[to];
+        let p = self.points[to].to_homogeneous();
+        (p.transpose() * q * p)[0].max(0.0)
+    }
+
+    fn push(&mut self, from: usize, to: usize) {
+        if self.locked[from] || self.removed[from] || self.removed[to] {
+            return;
+        }
+        self.heap.push(Collapse {
+            error: self.error(from, to),
+            length: (self.points[to] - self.points[from]).norm_squared(),
+            from,
+            to,
+            stamps: (self.stamps[from], self.stamps[to]),
+        });
+    }
+
+    /// 和某个位置相邻的位置
+    fn neighbors(&self, p: usize) -> Vec<usize> {
+        let mut result = Vec::new();
+        for &t in &self.position_triangles[p] {
+            if !self.alive[t] {
+                continue;
+            }
+            for q in self.triangle_positions(t) {
+                if q != p && !result.contains(&q) {
+                    result.push(q);
+                }
+            }
+        }
+        result
+    }
+
+    /// 检查折叠是否会产生非流形结构或者翻转三角形
+    fn can_collapse(&self, from: usize, to: usize) -> bool {
+        // link condition：两个端点的公共邻居数量必须等于共享这条边的三角形数量
+        let to_neighbors = self.neighbors(to);
+        let common = self
+            .neighbors(from)
+            .iter()
+            .filter(|q| to_neighbors.contains(q))
+            .count();
+        let shared = self.position_triangles[from]
+            .iter()
+            .filter(|&&t| self.alive[t] && self.triangle_positions(t).contains(&to))
+            .count();
+        if common != shared {
+            return false;
+        }
+
+        for &t in &self.position_triangles[from] {
+            if !self.alive[t] {
+                continue;
+            }
+            let p = self.triangle_positions(t);
+            if p.contains(&to) {
+                continue;
+            }
+            let moved = p.map(|q| if q == from { to } else { q });
+            let (Some((before, _)), Some((after, _))) = (self.plane(p), self.plane(moved)) else {
+                return false;
+            };
+            if before.dot(&after) < 0.2 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 为位置 from 的顶点选择位置 to 上的顶点
+    ///
+    /// 优先使用被折叠的边所在三角形里的顶点，这样接缝同一侧的属性保持连续，
+    /// 否则选择 uv 最接近的顶点
+    fn map_vertex(&self, vertex: usize, to: usize) -> usize {
+        for &t in &self.position_triangles[self.vertex_position[vertex]] {
+            let triangle = self.triangles[t];
+            if self.alive[t] && triangle.contains(&vertex) {
+                if let Some(&w) = triangle.iter().find(|&&w| self.vertex_position[w] == to) {
+                    return w;
+                }
+            }
+        }
+        let candidates = &self.position_vertices[to];
+        if self.mesh.uvs.is_empty() {
+            return candidates[0];
+        }
+        let uv = self.mesh.uvs[vertex];
+        *candidates
+            .iter()
+            .min_by(|&&a, &&b| {
+                let da = (self.mesh.uvs[a] - uv).norm_squared();
+                let db = (self.mesh.uvs[b] - uv).norm_squared();
+                da.total_cmp(&db)
+            })
+            .unwrap()
+    }
+
+    fn collapse(&mut self, from: usize, to: usize) {
+        // 先确定顶点的映射，再删除共享这条边的三角形
+        let mapping: HashMap<usize, usize> = self.position_vertices[from]
+            .iter()
+            .map(|&v| (v, self.map_vertex(v, to)))
+            .collect();
+        let triangles = std::mem::take(&mut self.position_triangles[from]);
+        for &t in &triangles {
+            if !self.alive[t] {
+                continue;
+            }
+            if self.triangle_positions(t).contains(&to) {
+                self.alive[t] = false;
+                self.live_count -= 1;
+                continue;
+            }
+            for v in self.triangles[t].iter_mut() {
+                if let Some(&w) = mapping.get(v) {
+                    *v = w;
+                }
+            }
+            self.position_triangles[to].push(t);
+        }
+        let q = self.quadrics[from];
+        self.quadrics[to] += q;
+        self.removed[from] = true;
+        // 只有 to 的二次型变了，邻居之间的其他边仍然有效，不需要更新邻居的版本号
+        self.stamps[to] += 1;
+
+        for n in self.neighbors(to) {
+            self.push(n, to);
+            self.push(to, n);
+        }
+    }
+
+    fn run(&mut self, options: &SimplifyOptions) {
+        while self.live_count > options.target_triangles {
+            let Some(candidate) = self.heap.pop() else {
+                break;
+            };
+            let (from, to) = (candidate.from, candidate.to);
+            if self.removed[from]
+                || self.removed[to]
+                || candidate.stamps != (self.stamps[from], self.stamps[to])
+            {
+                continue;
+            }
+            if candidate.error > options.max_error as f64 {
+                break;
+            }
+            if self.can_collapse(from, to) {
+                self.collapse(from, to);
+            }
+        }
+    }
+
+    /// 用剩下的三角形构造新的网格，只保留被使用的顶点
+    fn build(&self, mesh: &Mesh) -> Mesh {
+        let mut result = Mesh::new(&mesh.name);
+        result.targets = mesh
+            .targets
+            .iter()
+            .map(|t| MorphTarget {
+                name: t.name.clone(),
+                ..Default::default()
+            })
+            .collect();
+        let mut remap: Vec<Option<usize>> = vec![None; mesh.positions.len()];
+        let mut add_vertex = |result: &mut Mesh, v: usize| -> usize {
+            *remap[v].get_or_insert_with(|| {
+                result.positions.push(mesh.positions[v]);
+                if !mesh.normals.is_empty() {
+                    result.normals.push(mesh.normals[v]);
+                }
+                if !mesh.uvs.is_empty() {
+                    result.uvs.push(mesh.uvs[v]);
+                }
+                if !mesh.colors.is_empty() {
+                    result.colors.push(mesh.colors[v]);
+                }
+                if !mesh.tangents.is_empty() {
+                    result.tangents.push(mesh.tangents[v]);
+                }
+                if !mesh.joints.is_empty() {
+                    result.joints.push(mesh.joints[v]);
+                }
+                if !mesh.weights.is_empty() {
+                    result.weights.push(mesh.weights[v]);
+                }
+                for (target, source) in result.targets.iter_mut().zip(&mesh.targets) {
+                    if !source.positions.is_empty() {
+                        target.positions.push(source.positions[v]);
+                    }
+                    if !source.normals.is_empty() {
+                        target.normals.push(source.normals[v]);
+                    }
+                }
+                result.positions.len() - 1
+            })
+        };
+
+        for (t, triangle) in self.triangles.iter().enumerate() {
+            if self.alive[t] {
+                let face = triangle
+                    .iter()
+                    .map(|&v| add_vertex(&mut result, v))
+                    .collect();
+                result.faces.push(face);
+            }
+        }
+
+        let mut start = 0;
+        for group in &mesh.groups {
+            let count = (group.start..group.start + group.count)
+                .filter(|&t| self.alive[t])
+                .count();
+            result.groups.push(Group {
+                name: group.name.clone(),
+                material: group.material,
+                start,
+                count,
+            });
+            start += count;
+        }
+        result
+    }
+}
+
+/// 平面 n·x + d = 0 的误差二次型
+fn plane_quadric(normal: &Vector3<f64>, point: &Point3<f64>) -> Matrix4<f64> {
+    let plane = Vector4::new(normal.x, normal.y, normal.z, -normal.dot(&point.coords));
+    plane * plane.transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector2;
+
+    /// n x n 个四边形组成的网格，z 由函数给出
+    fn grid(n: usize, height: impl Fn(f32, f32) -> f32) -> Mesh {
+        let mut mesh = Mesh::new("grid");
+        for j in 0..=n {
+            for i in 0..=n {
+                let (x, y) = (i as f32 / n as f32, j as f32 / n as f32);
+                mesh.positions.push(Point3::new(x, y, height(x, y)));
+                mesh.uvs.push(Vector2::new(x, y));
+            }
+        }
+        for j in 0..n {
+            for i in 0..n {
+                let a = j * (n + 1) + i;
+                mesh.faces.push(vec![a, a + 1, a + n + 2]);
+                mesh.faces.push(vec![a, a + n + 2, a + n + 1]);
+            }
+        }
+        mesh
+    }
+
+    fn assert_not_flipped(mesh: &Mesh) {
+        for f in 0..mesh.face_count() {
+            assert!(mesh.face_normal(f).z > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_simplify_plane_to_target() {
+        let mesh = grid(10, |_, _| 0.0);
+        let simplified = mesh.simplify(&SimplifyOptions {
+            target_triangles: 20,
+            ..Default::default()
+        });
+        assert!(simplified.face_count() <= 20);
+        assert!(simplified.positions.iter().all(|p| p.z == 0.0));
+        assert_not_flipped(&simplified);
+    }
+
+    #[test]
+    fn test_simplify_unit_grid_to_target() {
+        // 间距为 1 的平面上所有折叠的误差都正好为 0，只能靠候选的顺序推进
+        let mut mesh = grid(10, |_, _| 0.0);
+        for p in mesh.positions.iter_mut() {
+            *p *= 10.0;
+        }
+        let simplified = mesh.simplify(&SimplifyOptions {
+            target_triangles: 4,
+            ..Default::default()
+        });
+        assert!(simplified.face_count() <= 4, "{}", simplified.face_count());
+        assert_not_flipped(&simplified);
+    }
+
+    #[test]
+    fn test_degenerate_triangle() {
+        // 同一行上三个共线的顶点组成面积为 0 的三角形
+        let mut mesh = grid(10, |_, _| 0.0);
+        mesh.faces.push(vec![36, 37, 38]);
+        let simplified = mesh.simplify(&SimplifyOptions {
+            target_triangles: 4,
+            ..Default::default()
+        });
+        assert!(simplified.face_count() <= 4, "{}", simplified.face_count());
+        assert!(simplified.validate().is_empty());
+        assert_not_flipped(&simplified);
+    }
+
+    #[test]
+    fn test_preserve_boundary() {
+        let mesh = grid(10, |_, _| 0.0);
+        let simplified = mesh.simplify(&SimplifyOptions {
+            target_triangles: 0,
+            preserve_boundary: true,
+            ..Default::default()
+        });
+        assert!(simplified.face_count() < mesh.face_count());
+        let on_boundary = |p: &Point3<f32>| p.x == 0.0 || p.x == 1.0 || p.y == 0.0 || p.y == 1.0;
+        let boundary = mesh.positions.iter().filter(|p| on_boundary(p)).count();
+        assert_eq!(
+            simplified
+                .positions
+                .iter()
+                .filter(|p| on_boundary(p))
+                .count(),
+            boundary
+        );
+        assert_not_flipped(&simplified);
+    }
+
+    #[test]
+    fn test_max_error() {
+        let wave = |x: f32, y: f32| (x * 12.0).sin() * (y * 12.0).cos() * 0.1;
+        let mesh = grid(16, wave);
+        let options = SimplifyOptions {
+            target_triangles: 0,
+            max_error: 1e-6,
+            preserve_boundary: true,
+            ..Default::default()
+        };
+        let wavy = mesh.simplify(&options);
+        let flat = grid(16, |_, _| 0.0).simplify(&options);
+        assert!(wavy.face_count() > flat.face_count() * 2);
+    }
+
+    #[test]
+    fn test_preserve_uv_seams() {
+        // 复制 x = 0.5 这一列顶点并修改 uv，构造一条接缝
+        let mut mesh = grid(8, |_, _| 0.0);
+        let seam: Vec<usize> = (0..mesh.vertex_count())
+            .filter(|&v| mesh.positions[v].x == 0.5)
+            .collect();
+        let right: Vec<bool> = mesh
+            .faces
+            .iter()
+            .map(|face| face.iter().all(|&i| mesh.positions[i].x >= 0.5))
+            .collect();
+        for &v in &seam {
+            let copy = mesh.duplicate_vertex(v);
+            mesh.uvs[copy].x += 1.0;
+            for (face, _) in mesh.faces.iter_mut().zip(&right).filter(|(_, &r)| r) {
+                for i in face.iter_mut().filter(|i| **i == v) {
+                    *i = copy;
+                }
+            }
+        }
+        let simplified = mesh.simplify(&SimplifyOptions {
+            target_triangles: 0,
+            preserve_boundary: true,
+            preserve_uv_seams: true,
+            ..Default::default()
+        });
+        let seam_count = simplified.positions.iter().filter(|p| p.x == 0.5).count();
+        assert_eq!(seam_count, seam.len() * 2);
+        assert!(simplified.face_count() < mesh.face_count());
+    }
+
+    #[test]
+    fn test_simplify_african_head() {
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mesh = crate::io::obj::load_mesh(path).expect("Failed to load OBJ file");
+        let mut target = mesh.face_count();
+        let mut lod = mesh.clone();
+        // LOD 链，每一级减少一半
+        for _ in 0..3 {
+            target /= 2;
+            lod = lod.simplify(&SimplifyOptions {
+                target_triangles: target,
+                ..Default::default()
+            });
+            assert!(lod.face_count() <= target);
+            assert_eq!(lod.groups[0].count, lod.face_count());
+            assert!(lod.faces.iter().flatten().all(|&v| v < lod.vertex_count()));
+        }
+    }
+}
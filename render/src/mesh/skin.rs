@@ -0,0 +1,202 @@
+use super::Mesh;
+use crate::math;
+use nalgebra::{Matrix3, Matrix4, Vector4};
+
+/// 每个顶点最多受影响的骨骼数量
+pub const MAX_INFLUENCES: usize = 4;
+
+impl Mesh {
+    /// 是否有完整的骨骼索引和权重
+    pub fn is_skinned(&self) -> bool {
+        !self.positions.is_empty()
+            && self.joints.len() == self.positions.len()
+            && self.weights.len() == self.positions.len()
+    }
+
+    /// 把每个顶点的权重归一化到和为 1，权重全为 0 的顶点保持不变
+    pub fn normalize_weights(&mut self) {
+        for weights in self.weights.iter_mut() {
+            let sum = weights.sum();
+            if sum > 0.0 {
+                *weights /= sum;
+            }
+        }
+    }
+
+    /// 第 v 个顶点按权重混合后的骨骼矩阵，网格没有蒙皮数据或者没有骨骼矩阵时返回 None
+    pub fn skin_matrix(&self, v: usize, joint_matrices: &[Matrix4<f32>]) -> Option<Matrix4<f32>> {
+        if joint_matrices.is_empty() || !self.is_skinned() {
+            return None;
+        }
+        Some(blend_matrix(
+            &self.joints[v],
+            &self.weights[v],
+            joint_matrices,
+        ))
+    }
+
+    /// 线性混合蒙皮（LBS），返回摆好姿势的网格
+    ///
+    /// joint_matrices 是每个骨骼从绑定姿势到当前姿势的变换（见 Skin::joint_matrices），
+    /// 位置、法向量和切线用按权重混合后的矩阵变换；没有蒙皮数据时返回原网格的副本。
+    /// 渲染时蒙皮在顶点着色器中完成（见 shader::surface_vertex），这里用于需要蒙皮结果的其他计算
+    pub fn skin(&self, joint_matrices: &[Matrix4<f32>]) -> Mesh {
+        let mut result = self.clone();
+        for v in 0..self.positions.len() {
+            let Some(matrix) = self.skin_matrix(v, joint_matrices) else {
+                break;
+            };
+            result.positions[v] = matrix.transform_point(&self.positions[v]);
+            if let Some(normal) = result.normals.get_mut(v) {
+                *normal = (math::normal_matrix(&matrix) * *normal)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(*normal);
+            }
+            if let Some(tangent) = result.tangents.get_mut(v) {
+                let linear: Matrix3<f32> = matrix.fixed_view::<3, 3>(0, 0).into();
+                if let Some(t) = (linear * tangent.xyz()).try_normalize(f32::EPSILON) {
+                    *tangent = t.push(tangent.w);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// 按权重混合骨骼矩阵，权重按实际参与混合的骨骼归一化
+/// 超出范围的骨骼索引被忽略，没有有效的骨骼时返回单位矩阵
+pub fn blend_matrix(
+    joints: &[u16; MAX_INFLUENCES],
+    weights: &Vector4<f32>,
+    joint_matrices: &[Matrix4<f32>],
+) -> Matrix4<f32> {
+    let mut matrix = Matrix4::zeros();
+    let mut total = 0.0;
+    for (&joint, &weight) in joints.iter().zip(weights.iter()) {
+        if weight <= 0.0 {
+            continue;
+        }
+        if let Some(m) = joint_matrices.get(joint as usize) {
+            matrix += m * weight;
+            total += weight;
+        }
+    }
+    if total > 0.0 {
+        matrix / total
+    } else {
+        Matrix4::identity()
+    }
+}
+
+/// 混合多个顶点的骨骼影响，用于细分等需要生成新顶点的操作
+/// 同一个骨骼的权重相加，只保留权重最大的 4 个骨骼并归一化
+pub fn mix_influences<'a>(
+    influences: impl IntoIterator<Item = (&'a [u16; MAX_INFLUENCES], &'a Vector4<f32>)>,
+) -> ([u16; MAX_INFLUENCES], Vector4<f32>) {
+    let mut totals: Vec<(u16, f32)> = Vec::new();
+    for (joints, weights) in influences {
+        for (&joint, &weight) in joints.iter().zip(weights.iter()) {
+            if weight <= 0.0 {
+                continue;
+            }
+            match totals.iter_mut().find(|(j, _)| *j == joint) {
+                Some((_, total)) => *total += weight,
+                None => totals.push((joint, weight)),
+            }
+        }
+    }
+    totals.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    totals.truncate(MAX_INFLUENCES);
+    let sum: f32 = totals.iter().map(|(_, w)| w).sum();
+    let mut joints = [0; MAX_INFLUENCES];
+    let mut weights = Vector4::zeros();
+    for (i, &(joint, weight)) in totals.iter().enumerate() {
+        joints[i] = joint;
+        weights[i] = weight / sum;
+    }
+    (joints, weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{Point3, UnitQuaternion, Vector3};
+
+    /// 沿 x 轴的一根细长条，左半边绑定到骨骼 0，右半边绑定到骨骼 1，中间各占一半
+    fn bar() -> Mesh {
+        let mut mesh = Mesh::new("bar");
+        for x in [-1.0, 0.0, 1.0] {
+            mesh.positions.push(Point3::new(x, 0.0, 0.0));
+            mesh.positions.push(Point3::new(x, 0.1, 0.0));
+            mesh.normals.extend([Vector3::z(); 2]);
+            let weight = (x + 1.0) / 2.0;
+            mesh.joints.extend([[0, 1, 0, 0]; 2]);
+            mesh.weights
+                .extend([Vector4::new(1.0 - weight, weight, 0.0, 0.0); 2]);
+        }
+        mesh.faces = vec![vec![0, 2, 3, 1], vec![2, 4, 5, 3]];
+        mesh
+    }
+
+    #[test]
+    fn test_blend_matrix() {
+        let matrices = [
+            Matrix4::new_translation(&Vector3::new(2.0, 0.0, 0.0)),
+            Matrix4::new_translation(&Vector3::new(0.0, 4.0, 0.0)),
+        ];
+        let blended = blend_matrix(&[0, 1, 0, 0], &Vector4::new(0.5, 0.5, 0.0, 0.0), &matrices);
+        let p = blended.transform_point(&Point3::origin());
+        assert_eq!(p, Point3::new(1.0, 2.0, 0.0));
+        // 越界的骨骼不参与混合，剩下的权重重新归一化
+        let blended = blend_matrix(&[0, 9, 0, 0], &Vector4::new(0.5, 0.5, 0.0, 0.0), &matrices);
+        assert_eq!(blended, matrices[0]);
+        assert_eq!(
+            blend_matrix(&[0; 4], &Vector4::zeros(), &matrices),
+            Matrix4::identity()
+        );
+    }
+
+    #[test]
+    fn test_skin() {
+        let mesh = bar();
+        assert!(mesh.is_skinned());
+        // 骨骼 1 绕 z 轴旋转 90 度，骨骼 0 不动
+        let rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 90f32.to_radians());
+        let matrices = [Matrix4::identity(), rotation.to_homogeneous()];
+        let posed = mesh.skin(&matrices);
+        assert_eq!(posed.positions[0], mesh.positions[0]);
+        assert!((posed.positions[4] - Point3::new(0.0, 1.0, 0.0)).norm() < 1e-6);
+        // 中间的顶点在两个骨骼的结果之间
+        assert!((posed.positions[2] - Point3::new(0.0, 0.0, 0.0)).norm() < 1e-6);
+        assert!((posed.positions[3] - Point3::new(-0.05, 0.05, 0.0)).norm() < 1e-6);
+        assert!(posed
+            .normals
+            .iter()
+            .all(|n| (n - Vector3::z()).norm() < 1e-6));
+        assert_eq!(posed.faces, mesh.faces);
+
+        // 绑定姿势下结果不变，没有蒙皮数据时原样返回
+        assert_eq!(mesh.skin(&[Matrix4::identity(); 2]), mesh);
+        let mut unskinned = mesh.clone();
+        unskinned.weights.clear();
+        assert_eq!(unskinned.skin(&matrices), unskinned);
+    }
+
+    #[test]
+    fn test_weights() {
+        let mut mesh = bar();
+        mesh.weights[0] = Vector4::new(2.0, 2.0, 0.0, 0.0);
+        mesh.weights[1] = Vector4::zeros();
+        mesh.normalize_weights();
+        assert_eq!(mesh.weights[0], Vector4::new(0.5, 0.5, 0.0, 0.0));
+        assert_eq!(mesh.weights[1], Vector4::zeros());
+
+        // 同一个骨骼的权重合并，保留最大的 4 个
+        let a = ([0, 1, 2, 3], Vector4::new(0.4, 0.3, 0.2, 0.1));
+        let b = ([4, 1, 5, 0], Vector4::new(0.5, 0.3, 0.1, 0.1));
+        let (joints, weights) = mix_influences([(&a.0, &a.1), (&b.0, &b.1)]);
+        assert_eq!(joints, [1, 0, 4, 2]);
+        assert!((weights.sum() - 1.0).abs() < 1e-6);
+        assert!((weights[0] - 0.6 / 1.8).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,587 @@
+use super::normal::NormalWeighting;
+use super::{skin, Group, Mesh, MorphTarget};
+use nalgebra::{Point3, Vector2, Vector3, Vector4};
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+
+/// 细分参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubdivisionOptions {
+    /// 细分的次数
+    pub levels: usize,
+    /// 相邻面法向量夹角（弧度）超过阈值的边作为折痕，保持锐利
+    pub crease_angle: Option<f32>,
+    /// 额外指定的折痕边，使用输入网格的顶点索引
+    pub sharp_edges: Vec<[usize; 2]>,
+}
+
+impl Default for SubdivisionOptions {
+    fn default() -> Self {
+        SubdivisionOptions {
+            levels: 1,
+            crease_angle: None,
+            sharp_edges: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scheme {
+    Loop,
+    CatmullClark,
+}
+
+impl Mesh {
+    /// Loop 细分，每一级把一个三角形分成四个，非三角形的面会先被三角化
+    ///
+    /// 边界和折痕上使用一维的三次 B 样条规则，和三条以上折痕相连的顶点作为角点保持不动。
+    /// uv 和颜色线性插值，法向量在细分之后重新计算，切线会被清空
+    pub fn subdivide_loop(&self, options: &SubdivisionOptions) -> Mesh {
+        subdivide(self, options, Scheme::Loop)
+    }
+
+    /// Catmull–Clark 细分，每一级把 n 边形分成 n 个四边形
+    ///
+    /// 边界、折痕以及属性的处理方式和 Loop 细分相同
+    pub fn subdivide_catmull_clark(&self, options: &SubdivisionOptions) -> Mesh {
+        subdivide(self, options, Scheme::CatmullClark)
+    }
+}
+
+fn subdivide(mesh: &Mesh, options: &SubdivisionOptions, scheme: Scheme) -> Mesh {
+    let mut current = mesh.clone();
+    if scheme == Scheme::Loop {
+        current.triangulate();
+    }
+    let had_normals = !current.normals.is_empty();
+    current.normals.clear();
+    current.tangents.clear();
+
+    let mut sharp = options.sharp_edges.clone();
+    if let Some(angle) = options.crease_angle {
+        let topology = Topology::new(&current);
+        let cos = angle.cos();
+        for face in &topology.face_edges {
+            for &(a, b, key) in face {
+                if let [f, g] = topology.edges[&key][..] {
+                    if current.face_normal(f).dot(&current.face_normal(g)) < cos - 1e-6 {
+                        sharp.push([a, b]);
+                    }
+                }
+            }
+        }
+    }
+
+    for _ in 0..options.levels {
+        (current, sharp) = step(&current, &sharp, scheme);
+    }
+    if had_normals {
+        current.compute_normals(NormalWeighting::Angle, options.crease_angle);
+    }
+    current
+}
+
+/// 由两个位置编号组成的无向边
+type EdgeKey = (usize, usize);
+
+fn edge_key(a: usize, b: usize) -> EdgeKey {
+    (a.min(b), a.max(b))
+}
+
+/// 按位置编号建立的邻接关系
+struct Topology {
+    vertex_position: Vec<usize>,
+    /// 每个位置的坐标
+    points: Vec<Point3<f32>>,
+    /// 位置组成的边 -> 使用这条边的面
+    edges: HashMap<EdgeKey, Vec<usize>>,
+    /// 每个面的边：(起点顶点, 终点顶点, 边)
+    face_edges: Vec<Vec<(usize, usize, EdgeKey)>>,
+    /// 每个位置相邻的位置
+    neighbors: Vec<Vec<usize>>,
+    /// 每个位置相邻的面
+    faces: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    fn new(mesh: &Mesh) -> Self {
+        let (vertex_position, count) = mesh.position_ids();
+        let mut points = vec![Point3::origin(); count];
+        for (v, &p) in vertex_position.iter().enumerate() {
+            points[p] = mesh.positions[v];
+        }
+        let mut edges: HashMap<EdgeKey, Vec<usize>> = HashMap::new();
+        let mut neighbors = vec![Vec::new(); count];
+        let mut faces = vec![Vec::new(); count];
+        let mut face_edges = Vec::with_capacity(mesh.faces.len());
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let mut list = Vec::with_capacity(face.len());
+            if face.len() >= 3 {
+                for (i, &a) in face.iter().enumerate() {
+                    let b = face[(i + 1) % face.len()];
+                    let (pa, pb) = (vertex_position[a], vertex_position[b]);
+                    let key = edge_key(pa, pb);
+                    let users = edges.entry(key).or_default();
+                    if users.is_empty() {
+                        neighbors[pa].push(pb);
+                        neighbors[pb].push(pa);
+                    }
+                    users.push(f);
+                    faces[pa].push(f);
+                    list.push((a, b, key));
+                }
+            }
+            face_edges.push(list);
+        }
+        Topology {
+            vertex_position,
+            points,
+            edges,
+            face_edges,
+            neighbors,
+            faces,
+        }
+    }
+}
+
+/// 执行一级细分，返回新的网格以及新网格中的折痕边
+fn step(mesh: &Mesh, sharp_edges: &[[usize; 2]], scheme: Scheme) -> (Mesh, Vec<[usize; 2]>) {
+    let topology = Topology::new(mesh);
+    let creases: HashSet<EdgeKey> = sharp_edges
+        .iter()
+        .map(|&[a, b]| edge_key(topology.vertex_position[a], topology.vertex_position[b]))
+        .collect();
+    // 边界边和非流形边也按折痕处理
+    let is_sharp = |key: &EdgeKey| topology.edges[key].len() != 2 || creases.contains(key);
+
+    let face_points: Vec<Point3<f32>> = mesh
+        .faces
+        .iter()
+        .map(|face| centroid(face.iter().map(|&v| mesh.positions[v])))
+        .collect();
+
+    let mut edge_points: HashMap<EdgeKey, Point3<f32>> = HashMap::new();
+    for (&key, users) in &topology.edges {
+        let (a, b) = (topology.points[key.0], topology.points[key.1]);
+        let point = if is_sharp(&key) {
+            a + (b - a) * 0.5
+        } else {
+            match scheme {
+                Scheme::CatmullClark => {
+                    centroid([a, b, face_points[users[0]], face_points[users[1]]].into_iter())
+                }
+                Scheme::Loop => {
+                    let opposite = |f: usize| {
+                        let p = mesh.faces[f]
+                            .iter()
+                            .map(|&v| topology.vertex_position[v])
+                            .find(|&p| p != key.0 && p != key.1)
+                            .unwrap_or(key.0);
+                        topology.points[p].coords
+                    };
+                    Point3::from(
+                        (a.coords + b.coords) * 0.375
+                            + (opposite(users[0]) + opposite(users[1])) * 0.125,
+                    )
+                }
+            }
+        };
+        edge_points.insert(key, point);
+    }
+
+    let vertex_points: Vec<Point3<f32>> = (0..topology.points.len())
+        .map(|p| {
+            let point = topology.points[p];
+            let neighbors = &topology.neighbors[p];
+            let sharp: Vec<usize> = neighbors
+                .iter()
+                .copied()
+                .filter(|&q| is_sharp(&edge_key(p, q)))
+                .collect();
+            match sharp.len() {
+                // 折痕上的顶点只受折痕两端的影响
+                2 => Point3::from(
+                    (topology.points[sharp[0]].coords
+                        + topology.points[sharp[1]].coords
+                        + point.coords * 6.0)
+                        / 8.0,
+                ),
+                n if n > 2 => point,
+                _ if neighbors.is_empty() => point,
+                _ => {
+                    let n = neighbors.len() as f32;
+                    match scheme {
+                        Scheme::CatmullClark => {
+                            let q = centroid(topology.faces[p].iter().map(|&f| face_points[f]));
+                            let r = centroid(
+                                neighbors
+                                    .iter()
+                                    .map(|&q| point + (topology.points[q] - point) * 0.5),
+                            );
+                            Point3::from((q.coords + r.coords * 2.0 + point.coords * (n - 3.0)) / n)
+                        }
+                        Scheme::Loop => {
+                            let beta = (0.625 - (0.375 + 0.25 * (2.0 * PI / n).cos()).powi(2)) / n;
+                            let sum = neighbors
+                                .iter()
+                                .fold(point.coords * 0.0, |s, &q| s + topology.points[q].coords);
+                            Point3::from(point.coords * (1.0 - n * beta) + sum * beta)
+                        }
+                    }
+                }
+            }
+        })
+        .collect();
+
+    // 顶点属性按照原来的顶点区分，接缝两侧得到坐标相同但属性不同的顶点
+    let mut result = Mesh::new(&mesh.name);
+    result.targets = mesh
+        .targets
+        .iter()
+        .map(|t| MorphTarget {
+            name: t.name.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut builder = Builder {
+        source: mesh,
+        result,
+        corners: HashMap::new(),
+        edges: HashMap::new(),
+    };
+    let mut next_sharp = Vec::new();
+    let mut offsets = Vec::with_capacity(mesh.faces.len() + 1);
+    for (f, face) in mesh.faces.iter().enumerate() {
+        offsets.push(builder.result.faces.len());
+        let edges = &topology.face_edges[f];
+        if edges.is_empty() {
+            continue;
+        }
+        let corners: Vec<usize> = face
+            .iter()
+            .map(|&v| builder.corner(v, vertex_points[topology.vertex_position[v]]))
+            .collect();
+        let mids: Vec<usize> = edges
+            .iter()
+            .map(|&(a, b, key)| builder.edge(a, b, edge_points[&key]))
+            .collect();
+        for (i, &(_, _, key)) in edges.iter().enumerate() {
+            if is_sharp(&key) {
+                let j = (i + 1) % face.len();
+                next_sharp.push([corners[i], mids[i]]);
+                next_sharp.push([mids[i], corners[j]]);
+            }
+        }
+
+        let faces = &mut builder.result.faces;
+        let n = face.len();
+        match scheme {
+            Scheme::Loop => {
+                faces.push(vec![corners[0], mids[0], mids[2]]);
+                faces.push(vec![mids[0], corners[1], mids[1]]);
+                faces.push(vec![mids[2], mids[1], corners[2]]);
+                faces.push(vec![mids[0], mids[1], mids[2]]);
+            }
+            Scheme::CatmullClark => {
+                let center = builder.face(face, face_points[f]);
+                for i in 0..n {
+                    builder.result.faces.push(vec![
+                        corners[i],
+                        mids[i],
+                        center,
+                        mids[(i + n - 1) % n],
+                    ]);
+                }
+            }
+        }
+    }
+    offsets.push(builder.result.faces.len());
+
+    let mut result = builder.result;
+    result.groups = mesh
+        .groups
+        .iter()
+        .map(|g| Group {
+            name: g.name.clone(),
+            material: g.material,
+            start: offsets[g.start],
+            count: offsets[g.start + g.count] - offsets[g.start],
+        })
+        .collect();
+    (result, next_sharp)
+}
+
+fn centroid(points: impl Iterator<Item = Point3<f32>>) -> Point3<f32> {
+    let (sum, count) = points.fold((Point3::origin().coords, 0), |(s, n), p| {
+        (s + p.coords, n + 1)
+    });
+    Point3::from(sum / count.max(1) as f32)
+}
+
+/// 生成细分后的顶点，uv、颜色和形变目标的偏移取来源顶点的平均值（偏移是近似值），骨骼权重按来源顶点混合
+struct Builder<'a> {
+    source: &'a Mesh,
+    result: Mesh,
+    /// 原来的顶点 -> 新顶点
+    corners: HashMap<usize, usize>,
+    /// 原来的边（顶点索引）-> 边上的新顶点
+    edges: HashMap<(usize, usize), usize>,
+}
+
+impl Builder<'_> {
+    fn push(&mut self, point: Point3<f32>, sources: &[usize]) -> usize {
+        let weight = 1.0 / sources.len() as f32;
+        if !self.source.uvs.is_empty() {
+            let uv: Vector2<f32> = sources.iter().map(|&v| self.source.uvs[v]).sum();
+            self.result.uvs.push(uv * weight);
+        }
+        if !self.source.colors.is_empty() {
+            let color: Vector4<f32> = sources.iter().map(|&v| self.source.colors[v]).sum();
+            self.result.colors.push(color * weight);
+        }
+        for (target, source) in self.result.targets.iter_mut().zip(&self.source.targets) {
+            if !source.positions.is_empty() {
+                let delta: Vector3<f32> = sources.iter().map(|&v| source.positions[v]).sum();
+                target.positions.push(delta * weight);
+            }
+            if !source.normals.is_empty() {
+                let delta: Vector3<f32> = sources.iter().map(|&v| source.normals[v]).sum();
+                target.normals.push(delta * weight);
+            }
+        }
+        if self.source.is_skinned() {
+            let (joints, weights) = skin::mix_influences(
+                sources
+                    .iter()
+                    .map(|&v| (&self.source.joints[v], &self.source.weights[v])),
+            );
+            self.result.joints.push(joints);
+            self.result.weights.push(weights);
+        }
+        self.result.positions.push(point);
+        self.result.positions.len() - 1
+    }
+
+    fn corner(&mut self, v: usize, point: Point3<f32>) -> usize {
+        if let Some(&index) = self.corners.get(&v) {
+            return index;
+        }
+        let index = self.push(point, &[v]);
+        self.corners.insert(v, index);
+        index
+    }
+
+    fn edge(&mut self, a: usize, b: usize, point: Point3<f32>) -> usize {
+        let key = edge_key(a, b);
+        if let Some(&index) = self.edges.get(&key) {
+            return index;
+        }
+        let index = self.push(point, &[a, b]);
+        self.edges.insert(key, index);
+        index
+    }
+
+    fn face(&mut self, face: &[usize], point: Point3<f32>) -> usize {
+        self.push(point, face)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+
+    /// 中心在原点、边长为 2 的立方体，8 个共享顶点，6 个四边形面
+    fn cube() -> Mesh {
+        let mut mesh = Mesh::new("cube");
+        for i in 0..8 {
+            mesh.positions.push(Point3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+            ));
+        }
+        mesh.faces = vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+        ];
+        mesh
+    }
+
+    /// 顶点在坐标轴上的正八面体
+    fn octahedron() -> Mesh {
+        let mut mesh = Mesh::new("octahedron");
+        mesh.positions = vec![
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(-1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(0.0, -1.0, 0.0),
+            Point3::new(0.0, 0.0, 1.0),
+            Point3::new(0.0, 0.0, -1.0),
+        ];
+        mesh.faces = vec![
+            vec![0, 2, 4],
+            vec![2, 1, 4],
+            vec![1, 3, 4],
+            vec![3, 0, 4],
+            vec![2, 0, 5],
+            vec![1, 2, 5],
+            vec![3, 1, 5],
+            vec![0, 3, 5],
+        ];
+        mesh
+    }
+
+    fn unique_positions(mesh: &Mesh) -> usize {
+        mesh.position_ids().1
+    }
+
+    #[test]
+    fn test_catmull_clark_cube() {
+        let mesh = cube().subdivide_catmull_clark(&Default::default());
+        // 8 个角点 + 12 个边点 + 6 个面点
+        assert_eq!(mesh.vertex_count(), 26);
+        assert_eq!(mesh.face_count(), 24);
+        assert!(mesh.faces.iter().all(|f| f.len() == 4));
+        let corner = Point3::new(5.0, 5.0, 5.0) / 9.0;
+        assert!(mesh.positions.iter().any(|p| (p - corner).norm() < 1e-6));
+        for f in 0..mesh.face_count() {
+            let center = centroid(mesh.faces[f].iter().map(|&v| mesh.positions[v]));
+            assert!(mesh.face_normal(f).dot(&center.coords) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_loop_octahedron() {
+        let options = SubdivisionOptions {
+            levels: 2,
+            ..Default::default()
+        };
+        let mesh = octahedron().subdivide_loop(&options);
+        assert_eq!(mesh.face_count(), 8 * 16);
+        // 封闭网格的欧拉示性数为 2：V - E + F = 2，E = 3F / 2
+        assert_eq!(
+            mesh.vertex_count() + mesh.face_count() - mesh.face_count() * 3 / 2,
+            2
+        );
+
+        let mesh = octahedron().subdivide_loop(&Default::default());
+        // 度为 4 的顶点：beta = 31 / 256，相邻顶点之和为 0
+        let expected = Point3::new(1.0 - 4.0 * 31.0 / 256.0, 0.0, 0.0);
+        assert!((mesh.positions[0] - expected).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_crease_angle() {
+        let options = SubdivisionOptions {
+            levels: 2,
+            crease_angle: Some(30f32.to_radians()),
+            ..Default::default()
+        };
+        let mesh = cube().subdivide_catmull_clark(&options);
+        // 所有的棱都是折痕，细分之后仍然是立方体
+        for p in &mesh.positions {
+            assert!((p.coords.amax() - 1.0).abs() < 1e-6);
+        }
+        assert!(mesh
+            .positions
+            .iter()
+            .any(|p| *p == Point3::new(1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn test_sharp_edges() {
+        // 底面的四条棱组成一圈折痕
+        let options = SubdivisionOptions {
+            levels: 3,
+            sharp_edges: vec![[0, 2], [2, 3], [3, 1], [1, 0]],
+            ..Default::default()
+        };
+        let mesh = cube().subdivide_catmull_clark(&options);
+        // 折痕围成的底面保持平整，9 x 9 个顶点都在 z = -1 上
+        let bottom = |mesh: &Mesh| {
+            mesh.positions
+                .iter()
+                .filter(|p| (p.z + 1.0).abs() < 1e-6)
+                .count()
+        };
+        assert_eq!(bottom(&mesh), 81);
+        let smooth = cube().subdivide_catmull_clark(&SubdivisionOptions {
+            levels: 3,
+            ..Default::default()
+        });
+        assert_eq!(bottom(&smooth), 0);
+    }
+
+    #[test]
+    fn test_boundary() {
+        let mut mesh = Mesh::new("triangle");
+        mesh.positions = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        mesh.faces = vec![vec![0, 1, 2]];
+        // 每个角受一个骨骼影响
+        mesh.joints = vec![[0, 0, 0, 0], [1, 0, 0, 0], [2, 0, 0, 0]];
+        mesh.weights = vec![Vector4::x(); 3];
+        let options = SubdivisionOptions {
+            levels: 3,
+            ..Default::default()
+        };
+        let mesh = mesh.subdivide_loop(&options);
+        assert_eq!(mesh.face_count(), 64);
+        assert!(mesh.positions.iter().all(|p| p.z == 0.0));
+        assert!(mesh.is_skinned());
+        assert!(mesh.weights.iter().all(|w| (w.sum() - 1.0).abs() < 1e-5));
+        assert_eq!(
+            (mesh.joints[0], mesh.weights[0]),
+            ([0, 0, 0, 0], Vector4::x())
+        );
+        for f in 0..mesh.face_count() {
+            assert!(mesh.face_normal(f).z > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_uv_seam_and_groups() {
+        let mut mesh = cube();
+        mesh.uvs = vec![Vector2::zeros(); 8];
+        // 复制顶点 7 模拟 uv 接缝
+        let copy = mesh.duplicate_vertex(7);
+        mesh.uvs[copy] = Vector2::new(1.0, 1.0);
+        mesh.faces[5] = vec![1, 3, copy, 5];
+        mesh.normals = vec![Vector3::z(); mesh.vertex_count()];
+        mesh.groups = vec![
+            Group {
+                name: "a".to_string(),
+                material: None,
+                start: 0,
+                count: 2,
+            },
+            Group {
+                name: "b".to_string(),
+                material: Some(0),
+                start: 2,
+                count: 4,
+            },
+        ];
+        let options = SubdivisionOptions {
+            levels: 2,
+            ..Default::default()
+        };
+        let result = mesh.subdivide_catmull_clark(&options);
+        // 接缝两侧的顶点坐标相同，不会产生裂缝
+        let reference = cube().subdivide_catmull_clark(&options);
+        assert_eq!(unique_positions(&result), reference.vertex_count());
+        assert!(result.vertex_count() > reference.vertex_count());
+        assert_eq!(result.uvs.len(), result.vertex_count());
+        assert_eq!(result.normals.len(), result.vertex_count());
+        assert_eq!((result.groups[0].start, result.groups[0].count), (0, 32));
+        assert_eq!((result.groups[1].start, result.groups[1].count), (32, 64));
+    }
+}
@@ -0,0 +1,176 @@
+use super::Mesh;
+use nalgebra::Vector4;
+use std::collections::HashMap;
+
+/// 提供给 mikktspace 的几何数据
+/// mikktspace 只接受三角形和四边形，多于四个顶点的面会扇形拆分成三角形
+struct TangentGeometry<'a> {
+    mesh: &'a Mesh,
+    /// mikktspace 中的每个面对应的网格角 (face, slot)
+    corners: Vec<Vec<(usize, usize)>>,
+    /// 每个网格角上生成的切线
+    tangents: Vec<Vec<Vector4<f32>>>,
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.corners.len()
+    }
+
+    fn num_vertices_of_face(&self, face: usize) -> usize {
+        self.corners[face].len()
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        let (f, slot) = self.corners[face][vert];
+        self.mesh.positions[self.mesh.faces[f][slot]].coords.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        let (f, slot) = self.corners[face][vert];
+        self.mesh.normals[self.mesh.faces[f][slot]].into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let (f, slot) = self.corners[face][vert];
+        self.mesh.uvs[self.mesh.faces[f][slot]].into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let (f, slot) = self.corners[face][vert];
+        self.tangents[f][slot] = Vector4::from(tangent);
+    }
+}
+
+impl Mesh {
+    /// 使用 MikkTSpace 算法生成切线，和主流烘焙工具生成的法线贴图保持一致
+    ///
+    /// 切线的 w 分量是副切线的符号：bitangent = cross(normal, tangent) * w，
+    /// uv 镜像的区域 w 为 -1。uv 退化的三角形由 mikktspace 使用相邻面的结果或者默认方向补齐。
+    /// 同一个顶点在不同面上得到不同切线时（例如镜像 uv 的接缝），顶点会被拆分。
+    /// 需要网格已经有法向量和纹理坐标，否则返回 false
+    pub fn compute_tangents(&mut self) -> bool {
+        let count = self.positions.len();
+        if count == 0 || self.normals.len() != count || self.uvs.len() != count {
+            return false;
+        }
+
+        let mut corners = Vec::with_capacity(self.faces.len());
+        for (f, face) in self.faces.iter().enumerate() {
+            match face.len() {
+                3 | 4 => corners.push((0..face.len()).map(|slot| (f, slot)).collect()),
+                n if n > 4 => {
+                    for i in 1..n - 1 {
+                        corners.push(vec![(f, 0), (f, i), (f, i + 1)]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut geometry = TangentGeometry {
+            mesh: self,
+            corners,
+            tangents: self
+                .faces
+                .iter()
+                .map(|f| vec![Vector4::zeros(); f.len()])
+                .collect(),
+        };
+        if !bevy_mikktspace::generate_tangents(&mut geometry) {
+            return false;
+        }
+        let corner_tangents = geometry.tangents;
+
+        // 将角上的切线写回顶点，结果不一致的顶点需要拆分
+        let mut assigned: Vec<Option<Vector4<f32>>> = vec![None; count];
+        let mut split: HashMap<(usize, [u32; 4]), usize> = HashMap::new();
+        self.tangents = vec![Vector4::zeros(); count];
+        for (f, face_tangents) in corner_tangents.iter().enumerate() {
+            for (slot, &t) in face_tangents.iter().enumerate() {
+                let v = self.faces[f][slot];
+                match assigned[v] {
+                    None => {
+                        assigned[v] = Some(t);
+                        self.tangents[v] = t;
+                    }
+                    Some(existing) if (existing - t).norm() <= 1e-5 => {}
+                    Some(_) => {
+                        let key = (v, [t.x, t.y, t.z, t.w].map(f32::to_bits));
+                        let index = *split.entry(key).or_insert_with(|| {
+                            let index = self.duplicate_vertex(v);
+                            self.tangents[index] = t;
+                            index
+                        });
+                        self.faces[f][slot] = index;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{Point3, Vector2, Vector3};
+
+    /// 两个并排的四边形，x 从 0 到 2，中间一列顶点共享
+    /// u_right 为右侧四边形右边界的 u，等于 0 时右侧的 uv 是镜像的
+    fn strip(u_right: f32) -> Mesh {
+        let mut mesh = Mesh::new("strip");
+        for (x, u) in [(0.0, 0.0), (1.0, 1.0), (2.0, u_right)] {
+            for y in [0.0, 1.0] {
+                mesh.positions.push(Point3::new(x, y, 0.0));
+                mesh.uvs.push(Vector2::new(u, y));
+            }
+        }
+        mesh.normals = vec![Vector3::new(0.0, 0.0, 1.0); 6];
+        mesh.faces = vec![vec![0, 2, 3, 1], vec![2, 4, 5, 3]];
+        mesh
+    }
+
+    #[test]
+    fn test_tangents() {
+        let mut mesh = strip(2.0);
+        assert!(mesh.compute_tangents());
+        assert_eq!(mesh.vertex_count(), 6);
+        for t in &mesh.tangents {
+            assert!((t - Vector4::new(1.0, 0.0, 0.0, 1.0)).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_mirrored_uv() {
+        let mut mesh = strip(0.0);
+        assert!(mesh.compute_tangents());
+        // 中间一列顶点在接缝两侧的切线方向相反，需要拆分
+        assert_eq!(mesh.vertex_count(), 8);
+        for &v in &mesh.faces[1] {
+            let t = mesh.tangents[v];
+            assert!((t - Vector4::new(-1.0, 0.0, 0.0, -1.0)).norm() < 1e-5);
+        }
+        for &v in &mesh.faces[0] {
+            assert_eq!(mesh.tangents[v].w, 1.0);
+        }
+    }
+
+    #[test]
+    fn test_degenerate_uv() {
+        let mut mesh = strip(2.0);
+        mesh.uvs = vec![Vector2::new(0.5, 0.5); 6];
+        assert!(mesh.compute_tangents());
+        for (t, n) in mesh.tangents.iter().zip(&mesh.normals) {
+            assert!(t.iter().all(|v| v.is_finite()));
+            assert!(t.xyz().dot(n).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_missing_uv() {
+        let mut mesh = strip(2.0);
+        mesh.uvs.clear();
+        assert!(!mesh.compute_tangents());
+        assert!(mesh.tangents.is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+use super::Mesh;
+use std::collections::HashMap;
+use std::fmt;
+
+/// 网格检查发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// 顶点属性的数量和顶点数不一致
+    AttributeLength {
+        attribute: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    /// 顶点坐标包含 NaN 或者无穷大
+    InvalidPosition { vertex: usize },
+    /// 面引用了不存在的顶点
+    IndexOutOfRange { face: usize, index: usize },
+    /// 面的顶点少于 3 个、有重合的顶点或者面积为 0
+    DegenerateFace { face: usize },
+    /// 和之前的某个面使用完全相同的顶点（不考虑顺序）
+    DuplicateFace { face: usize, original: usize },
+    /// 被两个以上的面共享的边，a、b 为边两端的顶点
+    NonManifoldEdge { a: usize, b: usize, faces: usize },
+    /// 共享一条边的两个面以相同的方向经过这条边，说明其中一个面的朝向是反的
+    InconsistentWinding {
+        a: usize,
+        b: usize,
+        faces: [usize; 2],
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::AttributeLength {
+                attribute,
+                len,
+                expected,
+            } => write!(f, "{} has {} values, expected {}", attribute, len, expected),
+            Issue::InvalidPosition { vertex } => {
+                write!(f, "vertex {} has a non-finite position", vertex)
+            }
+            Issue::IndexOutOfRange { face, index } => {
+                write!(f, "face {} references missing vertex {}", face, index)
+            }
+            Issue::DegenerateFace { face } => write!(f, "face {} is degenerate", face),
+            Issue::DuplicateFace { face, original } => {
+                write!(f, "face {} duplicates face {}", face, original)
+            }
+            Issue::NonManifoldEdge { a, b, faces } => {
+                write!(f, "edge {}-{} is shared by {} faces", a, b, faces)
+            }
+            Issue::InconsistentWinding { a, b, faces } => write!(
+                f,
+                "faces {} and {} traverse edge {}-{} in the same direction",
+                faces[0], faces[1], a, b
+            ),
+        }
+    }
+}
+
+impl Mesh {
+    /// 检查网格中的问题，没有问题时返回空列表
+    ///
+    /// 拓扑相关的检查按坐标合并顶点，所以 uv 接缝不会被当成边界；
+    /// 引用了不存在顶点的面不参与其他检查
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let count = self.positions.len();
+        for (attribute, len) in [
+            ("normals", self.normals.len()),
+            ("uvs", self.uvs.len()),
+            ("colors", self.colors.len()),
+            ("tangents", self.tangents.len()),
+            ("joints", self.joints.len()),
+            ("weights", self.weights.len()),
+        ]
+        .into_iter()
+        .chain(self.targets.iter().flat_map(|t| {
+            [
+                ("morph target positions", t.positions.len()),
+                ("morph target normals", t.normals.len()),
+            ]
+        })) {
+            if len != 0 && len != count {
+                issues.push(Issue::AttributeLength {
+                    attribute,
+                    len,
+                    expected: count,
+                });
+            }
+        }
+        for (vertex, p) in self.positions.iter().enumerate() {
+            if !p.iter().all(|c| c.is_finite()) {
+                issues.push(Issue::InvalidPosition { vertex });
+            }
+        }
+
+        let (vertex_position, _) = self.position_ids();
+        // 边（位置编号，按从小到大排列）-> 经过这条边的面以及方向是否和编号顺序一致
+        let mut edges: HashMap<(usize, usize), Vec<(usize, bool)>> = HashMap::new();
+        // 边两端的一个代表顶点，用于报告
+        let mut edge_vertices: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut seen_faces: HashMap<Vec<usize>, usize> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            if let Some(&index) = face.iter().find(|&&v| v >= count) {
+                issues.push(Issue::IndexOutOfRange { face: f, index });
+                continue;
+            }
+            let mut key: Vec<usize> = face.iter().map(|&v| vertex_position[v]).collect();
+            key.sort_unstable();
+            let repeated = key.windows(2).any(|w| w[0] == w[1]);
+            if face.len() < 3 || repeated || self.face_area(f) == 0.0 {
+                issues.push(Issue::DegenerateFace { face: f });
+                continue;
+            }
+            if let Some(&original) = seen_faces.get(&key) {
+                issues.push(Issue::DuplicateFace { face: f, original });
+                continue;
+            }
+            seen_faces.insert(key, f);
+
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                let (pa, pb) = (vertex_position[a], vertex_position[b]);
+                let key = (pa.min(pb), pa.max(pb));
+                edges.entry(key).or_default().push((f, pa < pb));
+                edge_vertices
+                    .entry(key)
+                    .or_insert(if pa < pb { (a, b) } else { (b, a) });
+            }
+        }
+
+        let mut topology: Vec<((usize, usize), Issue)> = Vec::new();
+        for (key, users) in &edges {
+            let (a, b) = edge_vertices[key];
+            match users[..] {
+                [(f, forward_f), (g, forward_g)] if forward_f == forward_g => {
+                    topology.push((
+                        *key,
+                        Issue::InconsistentWinding {
+                            a,
+                            b,
+                            faces: [f.min(g), f.max(g)],
+                        },
+                    ));
+                }
+                _ if users.len() > 2 => topology.push((
+                    *key,
+                    Issue::NonManifoldEdge {
+                        a,
+                        b,
+                        faces: users.len(),
+                    },
+                )),
+                _ => {}
+            }
+        }
+        // HashMap 的遍历顺序不固定，按边排序使结果稳定
+        topology.sort_by_key(|(key, _)| *key);
+        issues.extend(topology.into_iter().map(|(_, issue)| issue));
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::primitive;
+    use nalgebra::{Point3, Vector2};
+
+    #[test]
+    fn test_valid_meshes() {
+        assert!(primitive::cube(1.0).validate().is_empty());
+        assert!(primitive::icosphere(1.0, 2).validate().is_empty());
+        let path = format!(
+            "{}/../resource/obj/african_head.obj",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mesh = crate::io::obj::load_mesh(path).unwrap();
+        assert!(!mesh.validate().iter().any(|i| matches!(
+            i,
+            Issue::IndexOutOfRange { .. } | Issue::InvalidPosition { .. }
+        )));
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let mut mesh = Mesh::new("invalid");
+        mesh.positions = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(f32::NAN, 0.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+        ];
+        mesh.uvs = vec![Vector2::zeros(); 2];
+        mesh.faces = vec![
+            vec![0, 1, 2],
+            vec![0, 1, 7],
+            vec![0, 1, 1],
+            vec![0, 1, 4],
+            vec![1, 2, 0],
+            vec![0, 1],
+        ];
+        let issues = mesh.validate();
+        assert!(issues.contains(&Issue::AttributeLength {
+            attribute: "uvs",
+            len: 2,
+            expected: 5
+        }));
+        assert!(issues.contains(&Issue::InvalidPosition { vertex: 3 }));
+        assert!(issues.contains(&Issue::IndexOutOfRange { face: 1, index: 7 }));
+        assert!(issues.contains(&Issue::DegenerateFace { face: 2 }));
+        // 三个点共线，面积为 0
+        assert!(issues.contains(&Issue::DegenerateFace { face: 3 }));
+        assert!(issues.contains(&Issue::DuplicateFace {
+            face: 4,
+            original: 0
+        }));
+        assert!(issues.contains(&Issue::DegenerateFace { face: 5 }));
+        assert_eq!(issues.len(), 7);
+        assert_eq!(issues[2].to_string(), "face 1 references missing vertex 7");
+    }
+
+    #[test]
+    fn test_topology() {
+        let mut mesh = primitive::cube(1.0);
+        mesh.normals.clear();
+        mesh.uvs.clear();
+        // 翻转一个三角形
+        mesh.faces[0].reverse();
+        let issues = mesh.validate();
+        assert_eq!(issues.len(), 3);
+        assert!(issues
+            .iter()
+            .all(|i| matches!(i, Issue::InconsistentWinding { faces, .. } if faces.contains(&0))));
+
+        // 第三个面共享同一条边
+        let mut mesh = primitive::plane(1.0, 1.0, 1);
+        mesh.normals.clear();
+        mesh.uvs.clear();
+        mesh.positions.push(Point3::new(0.0, 1.0, 0.0));
+        mesh.faces.push(vec![0, 3, 4]);
+        let issues = mesh.validate();
+        assert!(matches!(
+            issues[..],
+            [Issue::NonManifoldEdge {
+                a: 0,
+                b: 3,
+                faces: 3
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_malformed_obj() {
+        let source = "v 0 0 0\nv 1 0 0\nv nan 1 0\nv 0 1 0\nf 1 2 4\nf 1 2 3\nf 4 2 1\nf 1 1 2\n";
+        let mut mesh = crate::io::obj::read(std::io::Cursor::new(source)).unwrap();
+        let issues = mesh.validate();
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, Issue::InvalidPosition { .. })));
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, Issue::DuplicateFace { .. })));
+        assert!(mesh.repair(0.0).is_empty());
+        assert_eq!(mesh.face_count(), 1);
+        assert_eq!(mesh.vertex_count(), 3);
+
+        // 越界的索引在读取时报错，而不是在之后使用时 panic
+        assert!(crate::io::obj::read(std::io::Cursor::new("v 0 0 0\nf 1 2 3\n")).is_err());
+    }
+}
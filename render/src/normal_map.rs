@@ -0,0 +1,166 @@
+//! 法线贴图
+
+use crate::texture::Texture;
+use nalgebra::{Matrix3, Vector2, Vector3, Vector4};
+
+/// 法线贴图中法向量所在的空间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalSpace {
+    /// 切线空间，绿色通道指向 +v（OpenGL 约定，gltf 和 Blender 使用）
+    #[default]
+    TangentOpenGl,
+    /// 切线空间，绿色通道指向 -v（DirectX 约定，3ds Max 和虚幻引擎使用）
+    TangentDirectX,
+    /// 模型空间，法向量直接以模型坐标保存，不需要切线
+    Object,
+}
+
+/// 法线贴图，颜色 c 编码的法向量为 c * 2 - 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalMap {
+    pub texture: Texture,
+    pub space: NormalSpace,
+    /// 切线空间法向量 x、y 分量的缩放，和 gltf 的 normalTexture.scale 对应
+    pub scale: f32,
+}
+
+impl NormalMap {
+    pub fn new(texture: Texture, space: NormalSpace) -> Self {
+        NormalMap {
+            texture,
+            space,
+            scale: 1.0,
+        }
+    }
+
+    /// 在 uv 处采样并解码，切线空间统一转换为 OpenGL 约定，返回的向量未归一化
+    pub fn decode(&self, uv: &Vector2<f32>, dx: &Vector2<f32>, dy: &Vector2<f32>) -> Vector3<f32> {
+        let color = self.texture.sample_grad(uv, dx, dy);
+        let n = color.xyz() * 2.0 - Vector3::repeat(1.0);
+        match self.space {
+            NormalSpace::TangentOpenGl => Vector3::new(n.x * self.scale, n.y * self.scale, n.z),
+            NormalSpace::TangentDirectX => Vector3::new(n.x * self.scale, -n.y * self.scale, n.z),
+            NormalSpace::Object => n,
+        }
+    }
+
+    /// 在 uv 处采样得到世界空间的法向量
+    ///
+    /// normal、tangent 是插值后的世界空间法向量和切线，切线的 w 是副切线的符号；
+    /// normal_matrix 把模型空间的法向量变换到世界空间，只用于模型空间的贴图。
+    /// 切线为 0（网格没有切线）时无法构造切线空间，返回原来的法向量
+    pub fn perturb(
+        &self,
+        uv: &Vector2<f32>,
+        dx: &Vector2<f32>,
+        dy: &Vector2<f32>,
+        normal: &Vector3<f32>,
+        tangent: &Vector4<f32>,
+        normal_matrix: &Matrix3<f32>,
+    ) -> Vector3<f32> {
+        let n = self.decode(uv, dx, dy);
+        let perturbed = match self.space {
+            NormalSpace::Object => normal_matrix * n,
+            NormalSpace::TangentOpenGl | NormalSpace::TangentDirectX => {
+                let Some(frame) = tangent_frame(normal, tangent) else {
+                    return *normal;
+                };
+                frame * n
+            }
+        };
+        perturbed.try_normalize(f32::EPSILON).unwrap_or(*normal)
+    }
+}
+
+/// 由法向量和切线构造 TBN 矩阵，列依次为切线、副切线和法向量
+/// 插值后的切线不再和法向量垂直，先用 Gram-Schmidt 正交化
+pub fn tangent_frame(normal: &Vector3<f32>, tangent: &Vector4<f32>) -> Option<Matrix3<f32>> {
+    let n = normal.try_normalize(f32::EPSILON)?;
+    let t = tangent.xyz();
+    let t = (t - n * n.dot(&t)).try_normalize(f32::EPSILON)?;
+    let sign = if tangent.w < 0.0 { -1.0 } else { 1.0 };
+    let b = n.cross(&t) * sign;
+    Some(Matrix3::from_columns(&[t, b, n]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    /// 颜色均匀的法线贴图
+    fn uniform(color: [u8; 3], space: NormalSpace) -> NormalMap {
+        let [r, g, b] = color;
+        let image = RgbaImage::from_pixel(4, 4, Rgba([r, g, b, 255]));
+        NormalMap::new(Texture::new(&DynamicImage::ImageRgba8(image)), space)
+    }
+
+    fn perturb(map: &NormalMap, normal: Vector3<f32>, tangent: Vector4<f32>) -> Vector3<f32> {
+        let uv = Vector2::new(0.5, 0.5);
+        let zero = Vector2::zeros();
+        map.perturb(&uv, &zero, &zero, &normal, &tangent, &Matrix3::identity())
+    }
+
+    fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).norm() < 0.01
+    }
+
+    #[test]
+    fn test_flat() {
+        // 朝向 +z 的平坦颜色不改变法向量，不管切线是什么方向
+        let map = uniform([128, 128, 255], NormalSpace::TangentOpenGl);
+        let normal = Vector3::new(1.0, 1.0, 0.0).normalize();
+        let tangent = Vector4::new(0.0, 0.0, 1.0, 1.0);
+        assert!(close(perturb(&map, normal, tangent), normal));
+    }
+
+    #[test]
+    fn test_green_channel() {
+        // 表面朝向 +z，切线 +x；绿色通道偏向 +v
+        let normal = Vector3::z();
+        let tangent = Vector4::new(1.0, 0.0, 0.0, 1.0);
+        let expected = Vector3::new(0.0, 1.0, 1.0).normalize();
+        let color = [128, 218, 218];
+        let opengl = uniform(color, NormalSpace::TangentOpenGl);
+        assert!(close(perturb(&opengl, normal, tangent), expected));
+        // DirectX 的绿色通道方向相反
+        let directx = uniform(color, NormalSpace::TangentDirectX);
+        let flipped = Vector3::new(0.0, -1.0, 1.0).normalize();
+        assert!(close(perturb(&directx, normal, tangent), flipped));
+        // uv 镜像时副切线反向
+        let mirrored = Vector4::new(1.0, 0.0, 0.0, -1.0);
+        assert!(close(perturb(&opengl, normal, mirrored), flipped));
+        // 没有切线时保持原来的法向量
+        assert_eq!(perturb(&opengl, normal, Vector4::zeros()), normal);
+
+        let mut scaled = opengl.clone();
+        scaled.scale = 0.0;
+        assert!(close(perturb(&scaled, normal, tangent), normal));
+    }
+
+    #[test]
+    fn test_object_space() {
+        // 模型空间的 +x，模型绕 z 轴旋转 90 度后朝向 +y
+        let map = uniform([255, 128, 128], NormalSpace::Object);
+        let rotation = Matrix3::new(0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let uv = Vector2::new(0.5, 0.5);
+        let zero = Vector2::zeros();
+        let n = map.perturb(
+            &uv,
+            &zero,
+            &zero,
+            &Vector3::z(),
+            &Vector4::zeros(),
+            &rotation,
+        );
+        assert!(close(n, Vector3::y()));
+    }
+
+    #[test]
+    fn test_tangent_frame() {
+        let frame = tangent_frame(&Vector3::z(), &Vector4::new(1.0, 0.0, 0.5, 1.0)).unwrap();
+        assert!(close(frame.column(0).into(), Vector3::x()));
+        assert!(close(frame.column(1).into(), Vector3::y()));
+        assert!(tangent_frame(&Vector3::z(), &Vector4::new(0.0, 0.0, 1.0, 1.0)).is_none());
+    }
+}
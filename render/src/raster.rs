@@ -0,0 +1,442 @@
+use crate::math;
+use crate::mesh::Mesh;
+use crate::shader::{self, Derivatives, Shader, Varyings};
+use image::{Rgba, RgbaImage};
+use nalgebra::{Point3, Vector4};
+
+/// 背面剔除方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cull {
+    /// 不剔除
+    None,
+    /// 剔除背面（屏幕上顺时针排列的三角形的反面）
+    Back,
+    /// 剔除正面
+    Front,
+}
+
+/// 颜色缓冲和深度缓冲
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    pub color: RgbaImage,
+    /// 每个像素的深度，范围 [0, 1]，越小越近
+    pub depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Framebuffer {
+            color: RgbaImage::new(width, height),
+            depth: vec![f32::INFINITY; (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.color.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.color.height()
+    }
+
+    /// 用指定颜色填充颜色缓冲，深度缓冲重置为无穷远
+    pub fn clear(&mut self, color: Rgba<u8>) {
+        for pixel in self.color.pixels_mut() {
+            *pixel = color;
+        }
+        self.depth.fill(f32::INFINITY);
+    }
+
+    pub fn depth_at(&self, x: u32, y: u32) -> f32 {
+        self.depth[(y * self.width() + x) as usize]
+    }
+
+    /// 光栅化屏幕空间的三角形，x、y 为像素坐标（原点在左上角），z 为 [0, 1] 的深度
+    /// 在像素中心采样，通过深度测试的像素写入 color，返回写入的像素数
+    pub fn draw_triangle(
+        &mut self,
+        points: [Point3<f32>; 3],
+        cull: Cull,
+        color: Rgba<u8>,
+    ) -> usize {
+        self.rasterize(points, cull, |_, _| [Some(color); 4])
+    }
+
+    /// 用着色器绘制三角形，triangles 是 vertices 中的索引
+    ///
+    /// 每个顶点只运行一次顶点着色器；三角形在裁剪空间中按近平面裁剪，
+    /// 插值量按透视校正的重心坐标插值后交给片元着色器，偏导数由 2x2 像素块计算，返回写入的像素数
+    pub fn draw<S: Shader>(
+        &mut self,
+        shader: &S,
+        vertices: &[S::Vertex],
+        triangles: impl IntoIterator<Item = [usize; 3]>,
+        cull: Cull,
+    ) -> usize {
+        let outputs: Vec<(Vector4<f32>, S::Varyings)> =
+            vertices.iter().map(|v| shader.vertex(v)).collect();
+        self.draw_transformed(shader, &outputs, triangles, cull)
+    }
+
+    /// 用已经运行过顶点着色器的输出绘制三角形，triangles 是 outputs 中的索引，其他和 draw 相同
+    /// 同一批顶点分成多次绘制时（比如按材质拆分），顶点着色器只需要运行一次
+    pub fn draw_transformed<S: Shader>(
+        &mut self,
+        shader: &S,
+        outputs: &[(Vector4<f32>, S::Varyings)],
+        triangles: impl IntoIterator<Item = [usize; 3]>,
+        cull: Cull,
+    ) -> usize {
+        let viewport = math::viewport(self.width() as f32, self.height() as f32);
+        let mut count = 0;
+        for triangle in triangles {
+            let [Some(a), Some(b), Some(c)] = triangle.map(|v| outputs.get(v)) else {
+                continue;
+            };
+            let polygon = clip_near([a.clone(), b.clone(), c.clone()]);
+            for i in 1..polygon.len().saturating_sub(1) {
+                let corners = [&polygon[0], &polygon[i], &polygon[i + 1]];
+                let points = corners.map(|(clip, _)| {
+                    let ndc = Point3::from(clip.xyz() / clip.w);
+                    viewport.transform_point(&ndc)
+                });
+                let inv_w = corners.map(|(clip, _)| 1.0 / clip.w);
+                let values = corners.map(|(_, varyings)| varyings.clone());
+                count += self.rasterize(points, cull, |quad, active| {
+                    let varyings = quad.map(|screen| {
+                        // 屏幕空间的重心坐标除以 w 之后才是裁剪空间中的线性插值
+                        let weights = [0, 1, 2].map(|i| screen[i] * inv_w[i]);
+                        let sum = weights[0] + weights[1] + weights[2];
+                        // 块中三角形外的像素离得太远时可能没有意义，退回屏幕空间插值
+                        let weights = if sum > 0.0 {
+                            weights.map(|w| w / sum)
+                        } else {
+                            screen
+                        };
+                        S::Varyings::interpolate(&values, weights)
+                    });
+                    let derivatives = Derivatives::from_quad(&varyings);
+                    [0, 1, 2, 3].map(|i| {
+                        if !active[i] {
+                            return None;
+                        }
+                        let color = shader.fragment(&varyings[i], &derivatives)?;
+                        Some(shader::to_rgba(&color))
+                    })
+                });
+            }
+        }
+        count
+    }
+
+    /// 用以顶点索引为输入的着色器绘制网格的所有面
+    pub fn draw_mesh<S: Shader<Vertex = usize>>(
+        &mut self,
+        shader: &S,
+        mesh: &Mesh,
+        cull: Cull,
+    ) -> usize {
+        let vertices: Vec<usize> = (0..mesh.vertex_count()).collect();
+        self.draw(shader, &vertices, mesh.triangles(), cull)
+    }
+
+    /// 以 2x2 像素块为单位光栅化屏幕空间的三角形
+    ///
+    /// shade 得到块中 4 个像素（左上、右上、左下、右下）的重心坐标，以及哪些像素被三角形覆盖
+    /// 并且通过了深度测试；块中其他像素的重心坐标在三角形外，只用来计算偏导数。
+    /// shade 返回每个像素的颜色，None 表示丢弃，只写入覆盖的像素
+    fn rasterize(
+        &mut self,
+        points: [Point3<f32>; 3],
+        cull: Cull,
+        mut shade: impl FnMut([[f32; 3]; 4], [bool; 4]) -> [Option<Rgba<u8>>; 4],
+    ) -> usize {
+        let [a, b, c] = points;
+        // 屏幕 y 轴向下，世界空间中逆时针的正面在屏幕上面积为负
+        let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        let culled = match cull {
+            Cull::None => area == 0.0,
+            Cull::Back => area >= 0.0,
+            Cull::Front => area <= 0.0,
+        };
+        if culled || !area.is_finite() {
+            return 0;
+        }
+
+        let (width, height) = (self.width(), self.height());
+        let min = |f: fn(&Point3<f32>) -> f32| points.iter().map(f).fold(f32::INFINITY, f32::min);
+        let max =
+            |f: fn(&Point3<f32>) -> f32| points.iter().map(f).fold(f32::NEG_INFINITY, f32::max);
+        // 包围盒中第一个和最后一个像素中心可能落在三角形内的像素
+        let x0 = (min(|p| p.x) - 0.5).ceil().max(0.0) as u32;
+        let y0 = (min(|p| p.y) - 0.5).ceil().max(0.0) as u32;
+        let x1 = ((max(|p| p.x) - 0.5).floor() + 1.0).clamp(0.0, width as f32) as u32;
+        let y1 = ((max(|p| p.y) - 0.5).floor() + 1.0).clamp(0.0, height as f32) as u32;
+
+        let edge = |a: &Point3<f32>, b: &Point3<f32>, x: f32, y: f32| {
+            (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+        };
+        let mut count = 0;
+        // 块的左上角对齐到偶数坐标
+        for qy in ((y0 & !1)..y1).step_by(2) {
+            for qx in ((x0 & !1)..x1).step_by(2) {
+                let mut weights = [[0.0; 3]; 4];
+                let mut depths = [0.0; 4];
+                let mut active = [false; 4];
+                for i in 0..4 {
+                    let (x, y) = (qx + i as u32 % 2, qy + i as u32 / 2);
+                    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                    let w = [
+                        edge(&b, &c, px, py) / area,
+                        edge(&c, &a, px, py) / area,
+                        edge(&a, &b, px, py) / area,
+                    ];
+                    let z = w[0] * a.z + w[1] * b.z + w[2] * c.z;
+                    weights[i] = w;
+                    depths[i] = z;
+                    active[i] = x < width
+                        && y < height
+                        && w.iter().all(|&w| w >= 0.0)
+                        && (0.0..=1.0).contains(&z)
+                        && z < self.depth[(y * width + x) as usize];
+                }
+                if !active.contains(&true) {
+                    continue;
+                }
+                let colors = shade(weights, active);
+                for i in 0..4 {
+                    let (x, y) = (qx + i as u32 % 2, qy + i as u32 / 2);
+                    if let (true, Some(color)) = (active[i], colors[i]) {
+                        self.depth[(y * width + x) as usize] = depths[i];
+                        self.color.put_pixel(x, y, color);
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+}
+
+/// 用近平面（z = -w）裁剪裁剪空间中的三角形，返回凸多边形的顶点，完全在近平面之前时为空
+fn clip_near<V: Varyings>(triangle: [(Vector4<f32>, V); 3]) -> Vec<(Vector4<f32>, V)> {
+    let distance = |clip: &Vector4<f32>| clip.z + clip.w;
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let (a, b) = (&triangle[i], &triangle[(i + 1) % 3]);
+        let (da, db) = (distance(&a.0), distance(&b.0));
+        if da >= 0.0 {
+            polygon.push(a.clone());
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            let t = da / (da - db);
+            let clip = a.0 + (b.0 - a.0) * t;
+            let varyings =
+                V::interpolate(&[a.1.clone(), b.1.clone(), b.1.clone()], [1.0 - t, t, 0.0]);
+            polygon.push((clip, varyings));
+        }
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{Camera, Pipeline};
+    use nalgebra::{Matrix4, Vector2};
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// 屏幕上逆时针看起来是顺时针：y 轴向下
+    fn square(z: f32) -> [[Point3<f32>; 3]; 2] {
+        let p = |x, y| Point3::new(x, y, z);
+        [
+            [p(0.0, 10.0), p(10.0, 10.0), p(10.0, 0.0)],
+            [p(0.0, 10.0), p(10.0, 0.0), p(0.0, 0.0)],
+        ]
+    }
+
+    #[test]
+    fn test_fill_without_gaps_or_overlap() {
+        let mut framebuffer = Framebuffer::new(16, 16);
+        let drawn: usize = square(0.5)
+            .into_iter()
+            .map(|t| framebuffer.draw_triangle(t, Cull::Back, RED))
+            .sum();
+        // 共享的对角线上的像素只写入一次
+        assert_eq!(drawn, 100);
+        assert_eq!(framebuffer.color.get_pixel(9, 9), &RED);
+        assert_eq!(framebuffer.color.get_pixel(10, 0), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_depth_and_cull() {
+        let mut framebuffer = Framebuffer::new(16, 16);
+        for t in square(0.5) {
+            framebuffer.draw_triangle(t, Cull::Back, RED);
+        }
+        // 更远的三角形被遮挡
+        for t in square(0.7) {
+            assert_eq!(framebuffer.draw_triangle(t, Cull::Back, BLUE), 0);
+        }
+        // 背面被剔除
+        let [a, b, c] = square(0.1)[0];
+        assert_eq!(framebuffer.draw_triangle([a, c, b], Cull::Back, BLUE), 0);
+        assert!(framebuffer.draw_triangle([a, c, b], Cull::Front, BLUE) > 0);
+        assert_eq!(framebuffer.color.get_pixel(8, 8), &BLUE);
+        assert!((framebuffer.depth_at(8, 8) - 0.1).abs() < 1e-6);
+
+        // 在深度范围之外
+        framebuffer.clear(Rgba([0, 0, 0, 255]));
+        let [a, b, c] = square(1.5)[0];
+        assert_eq!(framebuffer.draw_triangle([a, b, c], Cull::None, RED), 0);
+        // 完全在屏幕外
+        let offscreen = [a, b, c].map(|p| p + nalgebra::Vector3::new(100.0, 0.0, -1.0));
+        assert_eq!(framebuffer.draw_triangle(offscreen, Cull::None, RED), 0);
+    }
+
+    /// 地面上 z 方向的条纹，x < 0 的一半被丢弃
+    struct Stripes {
+        pipeline: Pipeline,
+        discard_left: bool,
+    }
+
+    impl Shader for Stripes {
+        type Vertex = Point3<f32>;
+        type Varyings = Point3<f32>;
+
+        fn vertex(&self, p: &Point3<f32>) -> (Vector4<f32>, Point3<f32>) {
+            (self.pipeline.clip(p), *p)
+        }
+
+        fn fragment(&self, p: &Point3<f32>, _: &Derivatives<Point3<f32>>) -> Option<shader::Color> {
+            if self.discard_left && p.x < 0.0 {
+                return None;
+            }
+            Some(if p.z.floor() as i32 % 2 == 0 {
+                Vector4::new(1.0, 0.0, 0.0, 1.0)
+            } else {
+                Vector4::new(0.0, 0.0, 1.0, 1.0)
+            })
+        }
+    }
+
+    /// 相机在原点看向 -z，视角 90 度；地面 y = -1 从相机后面一直延伸到远处
+    fn floor(discard_left: bool) -> (Stripes, [Point3<f32>; 4]) {
+        let camera = Camera::perspective(
+            Point3::origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            nalgebra::Vector3::y(),
+            90f32.to_radians(),
+            0.1,
+            100.0,
+        );
+        let shader = Stripes {
+            pipeline: Pipeline::new(Matrix4::identity(), &camera, 64, 64),
+            discard_left,
+        };
+        let p = |x, z| Point3::new(x, -1.0, z);
+        (
+            shader,
+            [p(-20.0, 5.0), p(20.0, 5.0), p(20.0, -50.0), p(-20.0, -50.0)],
+        )
+    }
+
+    #[test]
+    fn test_perspective_correct_varyings() {
+        let (shader, vertices) = floor(false);
+        let mut framebuffer = Framebuffer::new(64, 64);
+        // 跨过近平面的三角形被裁剪而不是整个丢弃
+        let drawn = framebuffer.draw(&shader, &vertices, [[0, 1, 2], [0, 2, 3]], Cull::None);
+        assert!(drawn > 0);
+        assert_eq!(framebuffer.color.get_pixel(32, 10), &Rgba([0, 0, 0, 0]));
+        let mut checked = 0;
+        for y in 33..64 {
+            // 像素中心的视线和地面的交点
+            let ndc_y = 1.0 - 2.0 * (y as f32 + 0.5) / 64.0;
+            let z = 1.0 / ndc_y;
+            if (z - z.round()).abs() < 0.05 {
+                continue;
+            }
+            let expected = if z.floor() as i32 % 2 == 0 { RED } else { BLUE };
+            assert_eq!(framebuffer.color.get_pixel(32, y), &expected, "row {}", y);
+            checked += 1;
+        }
+        assert!(checked > 20);
+    }
+
+    #[test]
+    fn test_discard() {
+        let (shader, vertices) = floor(true);
+        let mut framebuffer = Framebuffer::new(64, 64);
+        framebuffer.draw(&shader, &vertices, [[0, 1, 2], [0, 2, 3]], Cull::None);
+        // 被丢弃的片元不写入颜色和深度
+        assert_eq!(framebuffer.color.get_pixel(10, 60), &Rgba([0, 0, 0, 0]));
+        assert_eq!(framebuffer.depth_at(10, 60), f32::INFINITY);
+        assert_ne!(framebuffer.color.get_pixel(54, 60), &Rgba([0, 0, 0, 0]));
+        assert!(framebuffer.depth_at(54, 60) < 1.0);
+    }
+
+    #[test]
+    fn test_draw_transformed() {
+        let (shader, vertices) = floor(false);
+        let mut expected = Framebuffer::new(64, 64);
+        expected.draw(&shader, &vertices, [[0, 1, 2], [0, 2, 3]], Cull::None);
+        // 顶点着色器的输出在两次绘制之间共用
+        let outputs: Vec<_> = vertices.iter().map(|v| shader.vertex(v)).collect();
+        let mut framebuffer = Framebuffer::new(64, 64);
+        let drawn = framebuffer.draw_transformed(&shader, &outputs, [[0, 1, 2]], Cull::None)
+            + framebuffer.draw_transformed(&shader, &outputs, [[0, 2, 3]], Cull::None);
+        assert!(drawn > 0);
+        assert_eq!(framebuffer.color, expected.color);
+    }
+
+    /// 顶点直接给出裁剪空间坐标和像素坐标，片元检查像素坐标的偏导数
+    struct PixelCoords;
+
+    impl Shader for PixelCoords {
+        type Vertex = (Vector4<f32>, Vector2<f32>);
+        type Varyings = Vector2<f32>;
+
+        fn vertex(&self, vertex: &Self::Vertex) -> (Vector4<f32>, Vector2<f32>) {
+            *vertex
+        }
+
+        fn fragment(
+            &self,
+            _: &Vector2<f32>,
+            d: &Derivatives<Vector2<f32>>,
+        ) -> Option<shader::Color> {
+            let exact = (d.dx - Vector2::x()).norm() < 1e-3 && (d.dy - Vector2::y()).norm() < 1e-3;
+            Some(if exact {
+                Vector4::repeat(1.0)
+            } else {
+                Vector4::new(1.0, 0.0, 0.0, 1.0)
+            })
+        }
+    }
+
+    #[test]
+    fn test_quad_derivatives() {
+        // 16x16 的屏幕，w = 2 时 NDC 的 [-1, 1] 对应裁剪空间的 [-2, 2]
+        let vertex = |x: f32, y: f32| {
+            let ndc = Vector2::new(x / 8.0 - 1.0, 1.0 - y / 8.0);
+            (
+                Vector4::new(ndc.x * 2.0, ndc.y * 2.0, 0.0, 2.0),
+                Vector2::new(x, y),
+            )
+        };
+        let vertices = [vertex(1.0, 1.0), vertex(3.0, 15.0), vertex(14.0, 6.0)];
+        let mut framebuffer = Framebuffer::new(16, 16);
+        let drawn = framebuffer.draw(&PixelCoords, &vertices, [[0, 1, 2]], Cull::None);
+        assert!(drawn > 50);
+        let white = framebuffer
+            .color
+            .pixels()
+            .filter(|p| **p == Rgba([255; 4]))
+            .count();
+        // 三角形边缘的块中有三角形外的像素，偏导数仍然正确
+        assert_eq!(white, drawn);
+    }
+}
@@ -0,0 +1,782 @@
+use crate::animation::Animation;
+use crate::camera::{Camera, Pipeline};
+use crate::light::{BlinnPhong, Light, WorldLight};
+use crate::material::Material;
+use crate::math;
+use crate::mesh::Mesh;
+use crate::normal_map::{NormalMap, NormalSpace};
+use crate::raster::{Cull, Framebuffer};
+use crate::shader::{surface_vertex, BlinnPhongShader};
+use crate::texture::{Filter, MipFilter, Texture};
+use image::DynamicImage;
+use nalgebra::{Matrix3, Matrix4, Point3, UnitQuaternion, Vector3};
+use std::borrow::Cow;
+
+/// 场景节点
+/// 节点的变换由平移、旋转、缩放（TRS）组成，相对于父节点
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: String,
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+    /// 挂载的网格在场景 meshes 中的索引
+    pub mesh: Option<usize>,
+    /// 挂载的光源在场景 lights 中的索引
+    pub light: Option<usize>,
+    /// 挂载的相机在场景 cameras 中的索引
+    pub camera: Option<usize>,
+    /// 网格使用的蒙皮在场景 skins 中的索引
+    pub skin: Option<usize>,
+    /// 网格每个形变目标的权重，缺少的权重为 0
+    pub morph_weights: Vec<f32>,
+    /// 子节点在场景 nodes 中的索引
+    pub children: Vec<usize>,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Node {
+            name: String::new(),
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: Vector3::repeat(1.0),
+            mesh: None,
+            light: None,
+            camera: None,
+            skin: None,
+            morph_weights: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl Node {
+    pub fn new(name: &str) -> Self {
+        Node {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 局部变换矩阵 T * R * S
+    pub fn local_matrix(&self) -> Matrix4<f32> {
+        Matrix4::new_translation(&self.translation)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+}
+
+/// 蒙皮，把网格顶点的骨骼索引对应到场景中的节点
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Skin {
+    pub name: String,
+    /// 骨骼节点在场景 nodes 中的索引，网格顶点的 joints 是这个列表中的索引
+    pub joints: Vec<usize>,
+    /// 把网格从绑定姿势变换到每个骨骼局部空间的矩阵，缺少时为单位矩阵
+    pub inverse_bind_matrices: Vec<Matrix4<f32>>,
+}
+
+impl Skin {
+    /// 每个骨骼从绑定姿势到当前姿势的变换，用于 Mesh::skin
+    ///
+    /// world_matrices 为所有节点的世界矩阵，mesh_world 为挂载网格的节点的世界矩阵；
+    /// 结果在网格节点的局部空间中，再乘上 mesh_world 之后只剩下骨骼的变换，
+    /// 即蒙皮网格不受所在节点变换的影响（和 gltf 一致）
+    pub fn joint_matrices(
+        &self,
+        world_matrices: &[Matrix4<f32>],
+        mesh_world: &Matrix4<f32>,
+    ) -> Vec<Matrix4<f32>> {
+        let inverse = mesh_world.try_inverse().unwrap_or_else(Matrix4::identity);
+        self.joints
+            .iter()
+            .enumerate()
+            .map(|(i, &joint)| {
+                let world = world_matrices
+                    .get(joint)
+                    .copied()
+                    .unwrap_or_else(Matrix4::identity);
+                let inverse_bind = self
+                    .inverse_bind_matrices
+                    .get(i)
+                    .copied()
+                    .unwrap_or_else(Matrix4::identity);
+                inverse * world * inverse_bind
+            })
+            .collect()
+    }
+}
+
+/// 场景，保存节点层级以及节点引用的网格、材质、图片
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub nodes: Vec<Node>,
+    /// 根节点在 nodes 中的索引
+    pub roots: Vec<usize>,
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub images: Vec<DynamicImage>,
+    pub lights: Vec<Light>,
+    /// 相机在节点的局部空间中定义，一般位于原点看向 -z
+    pub cameras: Vec<Camera>,
+    pub animations: Vec<Animation>,
+    pub skins: Vec<Skin>,
+}
+
+/// 绘制一个节点上挂载的网格所需的信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawCall {
+    pub node: usize,
+    /// 网格在场景 meshes 中的索引
+    pub mesh: usize,
+    /// 模型矩阵，即节点的世界矩阵
+    pub world: Matrix4<f32>,
+    /// 把法向量变换到世界空间的矩阵
+    pub normal: Matrix3<f32>,
+    /// 蒙皮的骨骼矩阵，节点没有蒙皮时为空
+    pub joints: Vec<Matrix4<f32>>,
+}
+
+impl Scene {
+    /// 按名字查找节点
+    pub fn find_node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    /// 查找节点的父节点
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes.iter().position(|n| n.children.contains(&node))
+    }
+
+    /// 添加节点，parent 为 None 时作为根节点，返回新节点的索引
+    pub fn add_node(&mut self, node: Node, parent: Option<usize>) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(index),
+            None => self.roots.push(index),
+        }
+        index
+    }
+
+    /// 从根节点开始深度优先遍历，visit 的参数为节点索引、节点和节点的世界矩阵
+    /// 每个节点最多访问一次，层级中的环和越界的子节点索引会被忽略
+    pub fn traverse<F>(&self, mut visit: F)
+    where
+        F: FnMut(usize, &Node, &Matrix4<f32>),
+    {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack: Vec<(usize, Matrix4<f32>)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&root| (root, Matrix4::identity()))
+            .collect();
+        while let Some((index, parent)) = stack.pop() {
+            if index >= self.nodes.len() || visited[index] {
+                continue;
+            }
+            visited[index] = true;
+            let node = &self.nodes[index];
+            let world = parent * node.local_matrix();
+            visit(index, node, &world);
+            stack.extend(node.children.iter().rev().map(|&child| (child, world)));
+        }
+    }
+
+    /// 所有节点的世界矩阵，不在层级中的节点为单位矩阵
+    pub fn world_matrices(&self) -> Vec<Matrix4<f32>> {
+        let mut matrices = vec![Matrix4::identity(); self.nodes.len()];
+        self.traverse(|index, _, world| matrices[index] = *world);
+        matrices
+    }
+
+    /// 按遍历顺序收集所有挂载了网格的节点，有蒙皮的节点同时计算骨骼矩阵
+    pub fn draw_calls(&self) -> Vec<DrawCall> {
+        let mut calls = Vec::new();
+        self.traverse(|index, node, world| {
+            if let Some(mesh) = node.mesh.filter(|&m| m < self.meshes.len()) {
+                calls.push(DrawCall {
+                    node: index,
+                    mesh,
+                    world: *world,
+                    normal: math::normal_matrix(world),
+                    joints: Vec::new(),
+                });
+            }
+        });
+        let skin = |call: &DrawCall| self.nodes[call.node].skin.and_then(|s| self.skins.get(s));
+        if calls.iter().any(|call| skin(call).is_some()) {
+            let world_matrices = self.world_matrices();
+            for call in calls.iter_mut() {
+                if let Some(skin) = skin(call) {
+                    call.joints = skin.joint_matrices(&world_matrices, &call.world);
+                }
+            }
+        }
+        calls
+    }
+
+    /// 所有挂载到节点上的光源，位置和方向变换到世界空间
+    pub fn world_lights(&self) -> Vec<WorldLight> {
+        let mut lights = Vec::new();
+        self.traverse(|_, node, world| {
+            if let Some(light) = node.light.and_then(|l| self.lights.get(l)) {
+                lights.push(WorldLight {
+                    light: light.clone(),
+                    position: world.transform_point(&Point3::origin()),
+                    direction: world
+                        .transform_vector(&-Vector3::z())
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or(-Vector3::z()),
+                });
+            }
+        });
+        lights
+    }
+
+    /// 挂载在某个节点上的相机，位置和朝向变换到世界空间
+    pub fn world_camera(&self, node: usize) -> Option<Camera> {
+        let camera = self
+            .nodes
+            .get(node)?
+            .camera
+            .and_then(|c| self.cameras.get(c))?;
+        let world = self.world_matrices()[node];
+        Some(Camera {
+            eye: world.transform_point(&camera.eye),
+            target: world.transform_point(&camera.target),
+            up: world.transform_vector(&camera.up),
+            ..*camera
+        })
+    }
+
+    /// 第一个挂载了相机的节点上的相机
+    pub fn active_camera(&self) -> Option<Camera> {
+        let mut first = None;
+        self.traverse(|index, node, _| {
+            if first.is_none() && node.camera.is_some() {
+                first = Some(index);
+            }
+        });
+        self.world_camera(first?)
+    }
+
+    /// 把所有动画在 time 时刻的值写入节点
+    pub fn animate(&mut self, time: f32) {
+        for animation in &self.animations {
+            animation.apply(&mut self.nodes, time);
+        }
+    }
+
+    /// 绘制调用实际使用的网格：叠加节点上的形变目标，没有形变时直接借用原网格
+    /// 蒙皮不在这里处理，由顶点着色器用绘制调用的骨骼矩阵完成
+    pub fn posed_mesh(&self, call: &DrawCall) -> Cow<'_, Mesh> {
+        let mesh = &self.meshes[call.mesh];
+        let weights = &self.nodes[call.node].morph_weights;
+        if !mesh.targets.is_empty() && weights.iter().any(|&w| w != 0.0) {
+            Cow::Owned(mesh.morph(weights))
+        } else {
+            Cow::Borrowed(mesh)
+        }
+    }
+
+    /// 用 images 创建材质的基础颜色纹理和法线贴图，图片索引无效时忽略
+    fn material_maps(&self, material: &Material) -> (Option<Texture>, Option<NormalMap>) {
+        let texture = |index: Option<usize>, srgb| {
+            let mut texture = Texture::new(self.images.get(index?)?);
+            texture.filter = Filter::Trilinear;
+            texture.generate_mipmaps(MipFilter::Box, srgb);
+            Some(texture)
+        };
+        let normal_map = texture(material.normal_texture, false).map(|texture| NormalMap {
+            scale: material.normal_scale,
+            ..NormalMap::new(texture, NormalSpace::TangentOpenGl)
+        });
+        (texture(material.base_color_texture, true), normal_map)
+    }
+
+    /// 把所有的绘制调用提交给光栅化器
+    ///
+    /// 网格先用 posed_mesh 叠加形变，在顶点着色器中蒙皮，使用顶点法向量逐像素计算 Blinn-Phong 光照，
+    /// 材质参数由面所在分组的材质换算（见 BlinnPhong::from_material），没有材质时为白色的非金属；
+    /// 场景中没有光源时使用沿视线方向照射的头灯，双面材质不做背面剔除；
+    /// 使用材质的基础颜色纹理和法线贴图，网格没有切线时为法线贴图生成切线
+    pub fn render(&self, camera: &Camera, framebuffer: &mut Framebuffer) {
+        let mut lights = self.world_lights();
+        if lights.is_empty() {
+            lights.push(WorldLight {
+                light: Light::directional(Vector3::repeat(1.0), 1.0),
+                position: camera.eye,
+                direction: camera.forward(),
+            });
+        }
+        let default_material = Material {
+            metallic: 0.0,
+            roughness: 0.5,
+            ..Default::default()
+        };
+        // 材质的纹理在第一次用到时创建，之后的绘制调用共用
+        let mut maps: Vec<Option<(Option<Texture>, Option<NormalMap>)>> =
+            vec![None; self.materials.len()];
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        for call in self.draw_calls() {
+            let mut mesh = self.posed_mesh(&call);
+            if mesh.normals.is_empty() {
+                mesh.to_mut().ensure_normals(None);
+            }
+            let needs_tangents = mesh.groups.iter().any(|g| {
+                g.material
+                    .and_then(|m| self.materials.get(m))
+                    .is_some_and(|m| m.normal_texture.is_some())
+            });
+            if needs_tangents && mesh.tangents.is_empty() {
+                mesh.to_mut().compute_tangents();
+            }
+            let mesh = mesh.as_ref();
+            let pipeline = Pipeline::new(call.world, camera, width, height);
+            // 顶点着色只和网格有关，所有材质共用一次的结果
+            let outputs: Vec<_> = (0..mesh.vertex_count())
+                .map(|v| surface_vertex(mesh, &pipeline, &call.joints, v))
+                .collect();
+            let material_of = |face: usize| {
+                mesh.face_group(face)
+                    .and_then(|g| g.material)
+                    .filter(|&m| m < self.materials.len())
+            };
+            // 连续使用同一个材质的面一起绘制
+            let mut start = 0;
+            while start < mesh.faces.len() {
+                let index = material_of(start);
+                let end = (start..mesh.faces.len())
+                    .find(|&f| material_of(f) != index)
+                    .unwrap_or(mesh.faces.len());
+                let material = index.map_or(&default_material, |m| &self.materials[m]);
+                let (texture, normal_map) = match index {
+                    Some(m) => {
+                        let maps = maps[m].get_or_insert_with(|| self.material_maps(material));
+                        (maps.0.as_ref(), maps.1.as_ref())
+                    }
+                    None => (None, None),
+                };
+                let shader = BlinnPhongShader {
+                    mesh,
+                    pipeline: pipeline.clone(),
+                    joints: &call.joints,
+                    lights: &lights,
+                    eye: camera.eye,
+                    material: BlinnPhong::from_material(material),
+                    ambient: Vector3::zeros(),
+                    double_sided: material.double_sided,
+                    texture,
+                    normal_map,
+                };
+                let cull = if material.double_sided {
+                    Cull::None
+                } else {
+                    Cull::Back
+                };
+                let triangles = mesh.faces[start..end].iter().flat_map(|f| {
+                    (1..f.len().saturating_sub(1)).map(move |i| [f[0], f[i], f[i + 1]])
+                });
+                framebuffer.draw_transformed(&shader, &outputs, triangles, cull);
+                start = end;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use nalgebra::{Point3, Vector4};
+
+    #[test]
+    fn test_local_matrix() {
+        let mut node = Node::new("node");
+        node.translation = Vector3::new(1.0, 2.0, 3.0);
+        node.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f32::consts::FRAC_PI_2);
+        node.scale = Vector3::new(2.0, 2.0, 2.0);
+        let p = node
+            .local_matrix()
+            .transform_point(&Point3::new(1.0, 0.0, 0.0));
+        assert!((p - Point3::new(1.0, 4.0, 3.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_find_parent() {
+        let mut scene = Scene::default();
+        let mut root = Node::new("root");
+        root.children.push(1);
+        scene.nodes.push(root);
+        scene.nodes.push(Node::new("child"));
+        scene.roots.push(0);
+        assert_eq!(scene.find_node("child"), Some(1));
+        assert_eq!(scene.parent(1), Some(0));
+        assert_eq!(scene.parent(0), None);
+    }
+
+    #[test]
+    fn test_world_matrices() {
+        let mut scene = Scene::default();
+        let mut arm = Node::new("arm");
+        arm.translation = Vector3::new(1.0, 0.0, 0.0);
+        arm.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::FRAC_PI_2);
+        let arm = scene.add_node(arm, None);
+        let mut hand = Node::new("hand");
+        hand.translation = Vector3::new(0.0, 0.0, 2.0);
+        let hand = scene.add_node(hand, Some(arm));
+        let finger = scene.add_node(Node::new("finger"), Some(hand));
+        let orphan = scene.nodes.len();
+        scene.nodes.push(Node::new("orphan"));
+
+        let matrices = scene.world_matrices();
+        // 父节点绕 y 旋转 90 度，子节点的 +z 偏移变成 +x
+        let p = matrices[finger].transform_point(&Point3::origin());
+        assert!((p - Point3::new(3.0, 0.0, 0.0)).norm() < 1e-5);
+        assert_eq!(matrices[orphan], Matrix4::identity());
+        assert_eq!(scene.parent(finger), Some(hand));
+
+        // 深度优先的遍历顺序
+        let mut order = Vec::new();
+        scene.traverse(|index, _, _| order.push(index));
+        assert_eq!(order, vec![arm, hand, finger]);
+
+        // 层级中的环不会导致死循环
+        scene.nodes[finger].children.push(arm);
+        scene.nodes[finger].children.push(100);
+        let mut count = 0;
+        scene.traverse(|_, _, _| count += 1);
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_draw_calls_lights_and_cameras() {
+        let mut scene = Scene::default();
+        scene.meshes.push(crate::mesh::primitive::cube(1.0));
+        let mut root = Node::new("root");
+        root.scale = Vector3::new(2.0, 1.0, 1.0);
+        let root = scene.add_node(root, None);
+        for x in [-1.0, 1.0] {
+            let mut node = Node::new("cube");
+            node.translation = Vector3::new(x, 0.0, 0.0);
+            node.mesh = Some(0);
+            scene.add_node(node, Some(root));
+        }
+
+        let calls = scene.draw_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].node, 2);
+        let center = calls[1].world.transform_point(&Point3::origin());
+        assert!((center - Point3::new(2.0, 0.0, 0.0)).norm() < 1e-5);
+        // 法向量矩阵抵消非均匀缩放
+        let n = calls[1].normal * Vector3::new(1.0, 1.0, 0.0);
+        assert!((n.normalize() - Vector3::new(0.5, 1.0, 0.0).normalize()).norm() < 1e-5);
+
+        scene
+            .lights
+            .push(Light::directional(Vector3::repeat(1.0), 1.0));
+        let mut sun = Node::new("sun");
+        sun.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -std::f32::consts::FRAC_PI_2);
+        sun.light = Some(0);
+        scene.add_node(sun, None);
+        let lights = scene.world_lights();
+        assert_eq!(lights.len(), 1);
+        assert!((lights[0].direction + Vector3::y()).norm() < 1e-5);
+
+        assert!(scene.active_camera().is_none());
+        scene.cameras.push(Camera {
+            eye: Point3::origin(),
+            target: Point3::new(0.0, 0.0, -1.0),
+            ..Default::default()
+        });
+        let mut eye = Node::new("eye");
+        eye.translation = Vector3::new(0.0, 0.0, 10.0);
+        eye.camera = Some(0);
+        let eye = scene.add_node(eye, Some(root));
+        let camera = scene.world_camera(eye).unwrap();
+        assert_eq!(camera.eye, Point3::new(0.0, 0.0, 10.0));
+        assert!((camera.forward() + Vector3::z()).norm() < 1e-5);
+        assert_eq!(scene.active_camera(), Some(camera));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut scene = Scene::default();
+        scene.meshes.push(crate::mesh::primitive::cube(1.0));
+        for (name, color) in [
+            ("red", Vector4::new(1.0, 0.0, 0.0, 1.0)),
+            ("green", Vector4::new(0.0, 1.0, 0.0, 1.0)),
+        ] {
+            scene.materials.push(Material {
+                name: name.to_string(),
+                base_color: color,
+                ..Default::default()
+            });
+        }
+        // 同一个网格通过分组材质画成两种颜色：复制一份网格并指定不同的材质
+        let mut green = scene.meshes[0].clone();
+        green.groups = vec![crate::mesh::Group {
+            name: "green".to_string(),
+            material: Some(1),
+            start: 0,
+            count: green.face_count(),
+        }];
+        scene.meshes[0].groups = vec![crate::mesh::Group {
+            name: "red".to_string(),
+            material: Some(0),
+            start: 0,
+            count: green.face_count(),
+        }];
+        scene.meshes.push(green);
+        // 红色立方体在前，绿色立方体在后并且偏右
+        let mut front = Node::new("front");
+        front.mesh = Some(0);
+        scene.add_node(front, None);
+        let mut back = Node::new("back");
+        back.mesh = Some(1);
+        back.translation = Vector3::new(0.8, 0.0, -3.0);
+        scene.add_node(back, None);
+
+        let camera = Camera {
+            eye: Point3::new(0.0, 0.0, 4.0),
+            ..Default::default()
+        };
+        let mut framebuffer = Framebuffer::new(64, 64);
+        framebuffer.clear(Rgba([0, 0, 0, 255]));
+        scene.render(&camera, &mut framebuffer);
+        // 画面中心是正对相机的红色面，头灯照明下亮度接近 1
+        let center = framebuffer.color.get_pixel(32, 32);
+        assert!(center[0] > 240 && center[1] == 0);
+        // 右侧露出的绿色立方体
+        let right = framebuffer.color.get_pixel(44, 32);
+        assert!(right[1] > 0 && right[0] == 0);
+        assert!(framebuffer.depth_at(32, 32) < framebuffer.depth_at(44, 32));
+
+        // 加入从左边照射的平行光之后，正对相机的面不再被照亮
+        scene
+            .lights
+            .push(Light::directional(Vector3::repeat(1.0), 1.0));
+        let mut sun = Node::new("sun");
+        sun.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -std::f32::consts::FRAC_PI_2);
+        sun.light = Some(0);
+        scene.add_node(sun, None);
+        framebuffer.clear(Rgba([0, 0, 0, 255]));
+        scene.render(&camera, &mut framebuffer);
+        assert_eq!(framebuffer.color.get_pixel(32, 32)[0], 0);
+    }
+
+    #[test]
+    fn test_render_triangle_behind_camera() {
+        // 站在一块大地面上，组成地面的两个三角形都有顶点在相机后面
+        let mut scene = Scene::default();
+        scene
+            .meshes
+            .push(crate::mesh::primitive::plane(200.0, 200.0, 1));
+        let mut floor = Node::new("floor");
+        floor.mesh = Some(0);
+        scene.add_node(floor, None);
+        let camera = Camera {
+            eye: Point3::new(0.0, 1.0, 0.0),
+            target: Point3::new(0.0, 0.5, -1.0),
+            ..Default::default()
+        };
+        let mut framebuffer = Framebuffer::new(64, 64);
+        framebuffer.clear(Rgba([0, 0, 0, 255]));
+        scene.render(&camera, &mut framebuffer);
+        // 近处的地面按近平面裁剪后仍然画出来，不会留下空洞
+        assert!(framebuffer.color.get_pixel(32, 60)[0] > 0);
+        assert!(framebuffer.color.get_pixel(2, 60)[0] > 0);
+    }
+
+    #[test]
+    fn test_render_blinn_phong() {
+        let mut scene = Scene::default();
+        scene
+            .meshes
+            .push(crate::mesh::primitive::plane(2.0, 2.0, 1));
+        scene.materials.push(Material {
+            base_color: Vector4::new(1.0, 0.0, 0.0, 1.0),
+            metallic: 0.0,
+            roughness: 0.2,
+            double_sided: true,
+            ..Default::default()
+        });
+        scene.meshes[0].groups = vec![crate::mesh::Group {
+            name: "red".to_string(),
+            material: Some(0),
+            start: 0,
+            count: 1,
+        }];
+        // 平面绕 x 轴旋转之后朝向相机
+        let mut plane = Node::new("plane");
+        plane.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+        plane.mesh = Some(0);
+        let plane = scene.add_node(plane, None);
+        scene
+            .lights
+            .push(Light::point(Vector3::repeat(1.0), 40.0, None));
+        let mut bulb = Node::new("bulb");
+        bulb.translation = Vector3::new(0.0, 0.0, 2.0);
+        bulb.light = Some(0);
+        scene.add_node(bulb, None);
+
+        let camera = Camera {
+            eye: Point3::new(0.0, 0.0, 4.0),
+            ..Default::default()
+        };
+        let render = |scene: &Scene| {
+            let mut framebuffer = Framebuffer::new(64, 64);
+            framebuffer.clear(Rgba([0, 0, 0, 255]));
+            scene.render(&camera, &mut framebuffer);
+            framebuffer.color
+        };
+        // 非金属的白色高光只出现在中心附近
+        let image = render(&scene);
+        let (center, side) = (image.get_pixel(32, 32), image.get_pixel(47, 32));
+        assert!(center[0] == 255 && center[1] > 80, "{:?}", center);
+        assert!(side[0] == 255 && side[1] < 30, "{:?}", side);
+
+        // 双面材质的背面和正面一样被照亮
+        scene.nodes[plane].rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -std::f32::consts::FRAC_PI_2);
+        assert_eq!(render(&scene).get_pixel(32, 32), center);
+        scene.materials[0].double_sided = false;
+        assert_eq!(render(&scene).get_pixel(32, 32), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_render_textures() {
+        let mut scene = Scene::default();
+        scene
+            .meshes
+            .push(crate::mesh::primitive::plane(2.0, 2.0, 1));
+        scene.meshes[0].groups = vec![crate::mesh::Group {
+            name: "textured".to_string(),
+            material: Some(0),
+            start: 0,
+            count: 1,
+        }];
+        let mut plane = Node::new("plane");
+        plane.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+        plane.mesh = Some(0);
+        scene.add_node(plane, None);
+        let image = |color| DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(4, 4, color));
+        scene.images.push(image(Rgba([0, 255, 0, 255])));
+        // 法向量沿切线方向，和头灯垂直
+        scene.images.push(image(Rgba([255, 128, 128, 255])));
+        scene.materials.push(Material {
+            metallic: 0.0,
+            roughness: 1.0,
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+
+        let camera = Camera {
+            eye: Point3::new(0.0, 0.0, 4.0),
+            ..Default::default()
+        };
+        let render = |scene: &Scene| {
+            let mut framebuffer = Framebuffer::new(64, 64);
+            framebuffer.clear(Rgba([0, 0, 0, 255]));
+            scene.render(&camera, &mut framebuffer);
+            *framebuffer.color.get_pixel(32, 32)
+        };
+        // 基础颜色乘以纹理颜色
+        let textured = render(&scene);
+        assert!(textured[0] < 30 && textured[1] > 200, "{:?}", textured);
+
+        // 网格没有切线，渲染时生成
+        assert!(scene.meshes[0].tangents.is_empty());
+        scene.materials[0].normal_texture = Some(1);
+        let bumped = render(&scene);
+        assert!(bumped[1] < 30, "{:?}", bumped);
+        // normal_scale 为 0 时贴图不起作用
+        scene.materials[0].normal_scale = 0.0;
+        assert_eq!(render(&scene), textured);
+        // 无效的图片索引被忽略
+        scene.materials[0].base_color_texture = Some(5);
+        assert_eq!(render(&scene)[0], 255);
+    }
+
+    #[test]
+    fn test_render_morph_normals() {
+        // 形变目标只改变法向量，顶点位置不动
+        let mut mesh = crate::mesh::primitive::plane(2.0, 2.0, 1);
+        mesh.targets.push(crate::mesh::MorphTarget {
+            name: "tilt".to_string(),
+            positions: vec![Vector3::zeros(); mesh.vertex_count()],
+            normals: vec![Vector3::x(); mesh.vertex_count()],
+        });
+        let mut scene = Scene::default();
+        scene.meshes.push(mesh);
+        let mut plane = Node::new("plane");
+        plane.rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+        plane.mesh = Some(0);
+        plane.morph_weights = vec![0.0];
+        let plane = scene.add_node(plane, None);
+        let camera = Camera {
+            eye: Point3::new(0.0, 0.0, 4.0),
+            ..Default::default()
+        };
+        let render = |scene: &Scene| {
+            let mut framebuffer = Framebuffer::new(64, 64);
+            scene.render(&camera, &mut framebuffer);
+            framebuffer.color.get_pixel(32, 32)[0]
+        };
+        assert_eq!(render(&scene), 255);
+        // 法向量偏转 45 度之后，头灯照射下变暗
+        scene.nodes[plane].morph_weights[0] = 1.0;
+        let tilted = render(&scene);
+        assert!(tilted > 150 && tilted < 200, "{}", tilted);
+    }
+
+    #[test]
+    fn test_skinning() {
+        let mut scene = Scene::default();
+        let mut cube = crate::mesh::primitive::cube(1.0);
+        cube.joints = vec![[0; 4]; cube.vertex_count()];
+        cube.weights = vec![Vector4::x(); cube.vertex_count()];
+        scene.meshes.push(cube);
+        // 骨骼在绑定姿势中位于 (0, 1, 0)，当前移动到了 (0, 2, 0)
+        let mut bone = Node::new("bone");
+        bone.translation = Vector3::new(0.0, 2.0, 0.0);
+        let bone = scene.add_node(bone, None);
+        scene.skins.push(Skin {
+            name: "skin".to_string(),
+            joints: vec![bone],
+            inverse_bind_matrices: vec![Matrix4::new_translation(&Vector3::new(0.0, -1.0, 0.0))],
+        });
+        // 蒙皮网格所在节点的变换不影响结果
+        let mut body = Node::new("body");
+        body.translation = Vector3::new(5.0, 0.0, 0.0);
+        body.mesh = Some(0);
+        body.skin = Some(0);
+        scene.add_node(body, None);
+
+        let calls = scene.draw_calls();
+        assert_eq!(calls[0].joints.len(), 1);
+        let p = (calls[0].world * calls[0].joints[0]).transform_point(&Point3::origin());
+        assert!((p - Point3::new(0.0, 1.0, 0.0)).norm() < 1e-5);
+
+        let camera = Camera {
+            eye: Point3::new(0.0, 0.0, 4.0),
+            ..Default::default()
+        };
+        let mut framebuffer = Framebuffer::new(64, 64);
+        framebuffer.clear(Rgba([0, 0, 0, 255]));
+        scene.render(&camera, &mut framebuffer);
+        // 立方体向上移动了 1，画面中心空出来
+        assert_eq!(framebuffer.color.get_pixel(32, 32), &Rgba([0, 0, 0, 255]));
+        assert!(framebuffer.color.get_pixel(32, 12)[0] > 0);
+    }
+}
@@ -0,0 +1,186 @@
+//! 渲染图片序列
+//!
+//! 逐帧渲染并写入带编号的 PNG 或 TGA 文件，之后可以用 ffmpeg 等工具合成视频，例如
+//! `ffmpeg -framerate 30 -i frame_%04d.png turntable.mp4`
+
+use crate::camera::Camera;
+use crate::raster::Framebuffer;
+use crate::scene::Scene;
+use image::{ImageFormat, ImageResult, Rgba};
+use std::path::PathBuf;
+
+/// 帧图片的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    Png,
+    /// 未压缩的 TGA，写入比 PNG 快
+    Tga,
+}
+
+impl FrameFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Png => "png",
+            FrameFormat::Tga => "tga",
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            FrameFormat::Png => ImageFormat::Png,
+            FrameFormat::Tga => ImageFormat::Tga,
+        }
+    }
+}
+
+/// 图片序列的输出设置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    /// 输出目录，不存在时自动创建
+    pub directory: PathBuf,
+    /// 文件名为 <prefix><编号>.<扩展名>，编号从 0 开始，至少 4 位
+    pub prefix: String,
+    pub format: FrameFormat,
+    pub frames: usize,
+    /// 每秒的帧数，决定每一帧对应的动画时间
+    pub fps: f32,
+    pub width: u32,
+    pub height: u32,
+    /// 每一帧开始时清屏的颜色
+    pub background: Rgba<u8>,
+}
+
+impl Sequence {
+    pub fn new<P: Into<PathBuf>>(directory: P, frames: usize, width: u32, height: u32) -> Self {
+        Sequence {
+            directory: directory.into(),
+            prefix: "frame_".to_string(),
+            format: FrameFormat::Png,
+            frames,
+            fps: 30.0,
+            width,
+            height,
+            background: Rgba([40, 40, 48, 255]),
+        }
+    }
+
+    /// 序列的总时长，最后一帧之后再过一帧回到开头，适合循环播放
+    pub fn duration(&self) -> f32 {
+        self.frames as f32 / self.fps
+    }
+
+    /// 第 frame 帧的时间
+    pub fn time(&self, frame: usize) -> f32 {
+        frame as f32 / self.fps
+    }
+
+    /// 第 frame 帧的文件路径
+    pub fn frame_path(&self, frame: usize) -> PathBuf {
+        let digits = self.frames.saturating_sub(1).to_string().len().max(4);
+        self.directory.join(format!(
+            "{}{:0digits$}.{}",
+            self.prefix,
+            frame,
+            self.format.extension(),
+        ))
+    }
+
+    /// 逐帧清空帧缓冲，调用 draw(帧编号, 时间, 帧缓冲) 绘制后写入文件，返回写入的文件路径
+    pub fn render<F>(&self, mut draw: F) -> ImageResult<Vec<PathBuf>>
+    where
+        F: FnMut(usize, f32, &mut Framebuffer),
+    {
+        std::fs::create_dir_all(&self.directory)?;
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        let mut paths = Vec::with_capacity(self.frames);
+        for frame in 0..self.frames {
+            framebuffer.clear(self.background);
+            draw(frame, self.time(frame), &mut framebuffer);
+            let path = self.frame_path(frame);
+            framebuffer
+                .color
+                .save_with_format(&path, self.format.image_format())?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// 播放场景中的动画并渲染每一帧，场景有相机时使用 Scene::active_camera，否则使用 camera
+    pub fn render_scene(&self, scene: &mut Scene, camera: &Camera) -> ImageResult<Vec<PathBuf>> {
+        self.render(|_, time, framebuffer| {
+            scene.animate(time);
+            let camera = scene.active_camera().unwrap_or(*camera);
+            scene.render(&camera, framebuffer);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Animation;
+    use crate::mesh::primitive;
+    use crate::scene::Node;
+    use nalgebra::{Point3, UnitQuaternion, Vector3};
+
+    #[test]
+    fn test_frame_path() {
+        let mut sequence = Sequence::new("out", 100, 4, 4);
+        assert_eq!(sequence.frame_path(7), PathBuf::from("out/frame_0007.png"));
+        sequence.frames = 12345;
+        sequence.format = FrameFormat::Tga;
+        assert_eq!(sequence.frame_path(7), PathBuf::from("out/frame_00007.tga"));
+        assert_eq!(sequence.time(15), 0.5);
+    }
+
+    #[test]
+    fn test_render_turntable() {
+        let dir = std::env::temp_dir().join("render_sequence_test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // 一个偏离中心的立方体，转台旋转时在画面中移动
+        let mut scene = Scene::default();
+        let mut cube = primitive::cube(0.5);
+        cube.translate(&Vector3::new(1.0, 0.0, 0.0));
+        scene.meshes.push(cube);
+        let node = scene.add_node(
+            Node {
+                mesh: Some(0),
+                ..Node::new("model")
+            },
+            None,
+        );
+        let mut sequence = Sequence::new(&dir, 4, 32, 32);
+        sequence.fps = 4.0;
+        sequence.format = FrameFormat::Tga;
+        scene.animations.push(Animation::turntable(
+            node,
+            &Vector3::y_axis(),
+            sequence.duration(),
+            UnitQuaternion::identity(),
+        ));
+        let camera = Camera {
+            eye: Point3::new(0.0, 0.0, 5.0),
+            ..Default::default()
+        };
+
+        let paths = sequence.render_scene(&mut scene, &camera).unwrap();
+        assert_eq!(paths.len(), 4);
+        let frames: Vec<_> = paths
+            .iter()
+            .map(|p| image::open(p).unwrap().to_rgba8())
+            .collect();
+        assert_eq!(frames[0].dimensions(), (32, 32));
+        assert!(frames[0].pixels().any(|p| *p != sequence.background));
+        assert_ne!(frames[0], frames[1]);
+        // 转半圈之后立方体在另一侧，画面左右对称
+        let mirrored = image::imageops::flip_horizontal(&frames[2]);
+        let differing = mirrored
+            .pixels()
+            .zip(frames[0].pixels())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(differing < 32);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
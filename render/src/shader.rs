@@ -0,0 +1,671 @@
+//! 可编程着色器
+//!
+//! 顶点着色器把输入的顶点变换到裁剪空间并输出插值量，光栅化时插值量按透视校正的重心坐标插值，
+//! 片元着色器根据插值后的值计算颜色。新的光照模型只需要实现 Shader，
+//! 再交给 Framebuffer::draw 或 Framebuffer::draw_mesh 绘制
+
+use crate::camera::Pipeline;
+use crate::light::{BlinnPhong, WorldLight};
+use crate::math;
+use crate::mesh::Mesh;
+use crate::normal_map::NormalMap;
+use crate::texture::Texture;
+use image::Rgba;
+use nalgebra::{Matrix4, Point3, Vector2, Vector3, Vector4};
+
+/// 线性空间的 RGBA 颜色，分量范围 [0, 1]
+pub type Color = Vector4<f32>;
+
+/// 颜色转换为 8 位像素，超出 [0, 1] 的分量被截断
+pub fn to_rgba(color: &Color) -> Rgba<u8> {
+    Rgba(
+        color
+            .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .into(),
+    )
+}
+
+/// 可以在三角形内插值的量
+pub trait Varyings: Clone {
+    /// 三个值按权重线性组合；插值时权重之和为 1，求相邻像素的差时为 0
+    fn interpolate(values: &[Self; 3], weights: [f32; 3]) -> Self;
+}
+
+/// 插值量在屏幕空间的偏导数，用 2x2 像素块中相邻像素的差近似
+#[derive(Debug, Clone, PartialEq)]
+pub struct Derivatives<V> {
+    /// 沿 x 方向（向右）相邻像素的差
+    pub dx: V,
+    /// 沿 y 方向（向下）相邻像素的差
+    pub dy: V,
+}
+
+impl<V: Varyings> Derivatives<V> {
+    /// 从 2x2 像素块（左上、右上、左下、右下）的插值量计算，块内的像素共用一个偏导数
+    pub fn from_quad(quad: &[V; 4]) -> Self {
+        let difference =
+            |a: &V, b: &V| V::interpolate(&[a.clone(), b.clone(), b.clone()], [1.0, -1.0, 0.0]);
+        Derivatives {
+            dx: difference(&quad[1], &quad[0]),
+            dy: difference(&quad[2], &quad[0]),
+        }
+    }
+}
+
+impl Varyings for () {
+    fn interpolate(_: &[Self; 3], _: [f32; 3]) -> Self {}
+}
+
+impl Varyings for f32 {
+    fn interpolate(values: &[Self; 3], weights: [f32; 3]) -> Self {
+        values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+    }
+}
+
+macro_rules! impl_varyings_vector {
+    ($($ty:ty),*) => {
+        $(
+            impl Varyings for $ty {
+                fn interpolate(values: &[Self; 3], weights: [f32; 3]) -> Self {
+                    values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+                }
+            }
+        )*
+    };
+}
+
+impl_varyings_vector!(Vector2<f32>, Vector3<f32>, Vector4<f32>);
+
+impl Varyings for Point3<f32> {
+    fn interpolate(values: &[Self; 3], weights: [f32; 3]) -> Self {
+        Point3::from(Vector3::interpolate(&values.map(|p| p.coords), weights))
+    }
+}
+
+macro_rules! impl_varyings_tuple {
+    ($($name:ident $index:tt),*) => {
+        impl<$($name: Varyings),*> Varyings for ($($name,)*) {
+            fn interpolate(values: &[Self; 3], weights: [f32; 3]) -> Self {
+                ($(
+                    $name::interpolate(
+                        &[
+                            values[0].$index.clone(),
+                            values[1].$index.clone(),
+                            values[2].$index.clone(),
+                        ],
+                        weights,
+                    ),
+                )*)
+            }
+        }
+    };
+}
+
+impl_varyings_tuple!(A 0, B 1);
+impl_varyings_tuple!(A 0, B 1, C 2);
+impl_varyings_tuple!(A 0, B 1, C 2, D 3);
+impl_varyings_tuple!(A 0, B 1, C 2, D 3, E 4);
+
+/// 着色器
+pub trait Shader {
+    /// 顶点着色器的输入，比如网格顶点的索引
+    type Vertex;
+    /// 从顶点着色器传给片元着色器的插值量
+    type Varyings: Varyings;
+
+    /// 返回裁剪空间的齐次坐标和这个顶点的插值量
+    fn vertex(&self, vertex: &Self::Vertex) -> (Vector4<f32>, Self::Varyings);
+
+    /// 返回片元的颜色，返回 None 时丢弃这个片元，不写入颜色和深度
+    /// derivatives 是插值量在屏幕空间的偏导数，用于选择纹理的细节层级
+    fn fragment(
+        &self,
+        varyings: &Self::Varyings,
+        derivatives: &Derivatives<Self::Varyings>,
+    ) -> Option<Color>;
+}
+
+/// 网格表面的插值量：世界空间的位置、法向量、纹理坐标、切线和顶点颜色
+pub type SurfaceVaryings = (Point3<f32>, Vector3<f32>, Vector2<f32>, Vector4<f32>, Color);
+
+/// 以顶点索引为输入的顶点着色器，把网格顶点的属性变换到世界空间，缺少的属性为 0，缺少顶点颜色时为白色
+///
+/// joints 为蒙皮的骨骼矩阵（见 Skin::joint_matrices），不为空并且网格有蒙皮数据时，
+/// 顶点先用按权重混合的骨骼矩阵变换到当前姿势（线性混合蒙皮），再做模型变换
+pub fn surface_vertex(
+    mesh: &Mesh,
+    pipeline: &Pipeline,
+    joints: &[Matrix4<f32>],
+    v: usize,
+) -> (Vector4<f32>, SurfaceVaryings) {
+    let skin = mesh.skin_matrix(v, joints);
+    let p = match &skin {
+        Some(m) => m.transform_point(&mesh.positions[v]),
+        None => mesh.positions[v],
+    };
+    let normal = mesh.normals.get(v).map_or_else(Vector3::zeros, |n| {
+        let n = match &skin {
+            Some(m) => math::normal_matrix(m) * n,
+            None => *n,
+        };
+        pipeline.world_normal(&n)
+    });
+    let uv = mesh.uvs.get(v).copied().unwrap_or_else(Vector2::zeros);
+    // 切线在表面内，和位置一样用骨骼矩阵和模型矩阵变换
+    let tangent = mesh.tangents.get(v).map_or_else(Vector4::zeros, |t| {
+        let direction = match &skin {
+            Some(m) => m.transform_vector(&t.xyz()),
+            None => t.xyz(),
+        };
+        let world = pipeline.model.transform_vector(&direction);
+        world
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::zeros)
+            .push(t.w)
+    });
+    let color = mesh.colors.get(v).copied().unwrap_or(Color::repeat(1.0));
+    (
+        pipeline.clip(&p),
+        (
+            pipeline.model.transform_point(&p),
+            normal,
+            uv,
+            tangent,
+            color,
+        ),
+    )
+}
+
+/// 片元的单位法向量，有法线贴图时用贴图扰动；法向量为 0 时返回 None
+fn surface_normal(
+    normal: &Vector3<f32>,
+    uv: &Vector2<f32>,
+    tangent: &Vector4<f32>,
+    derivatives: &Derivatives<SurfaceVaryings>,
+    normal_map: Option<&NormalMap>,
+    pipeline: &Pipeline,
+) -> Option<Vector3<f32>> {
+    let normal = normal.try_normalize(f32::EPSILON)?;
+    Some(match normal_map {
+        Some(map) => {
+            let (dx, dy) = (&derivatives.dx.2, &derivatives.dy.2);
+            map.perturb(uv, dx, dy, &normal, tangent, &pipeline.normal)
+        }
+        None => normal,
+    })
+}
+
+/// 逐像素的漫反射（Lambert）着色，输入是网格顶点的索引，网格需要有顶点法向量
+/// 没有光源时以从相机看过去的方向作为光照方向；基础颜色乘以顶点颜色，有纹理时再乘以纹理在顶点 uv 处的颜色
+/// 切线空间的法线贴图需要网格有切线（见 Mesh::compute_tangents）
+#[derive(Debug, Clone)]
+pub struct DiffuseShader<'a> {
+    pub mesh: &'a Mesh,
+    pub pipeline: Pipeline,
+    /// 蒙皮的骨骼矩阵，为空时不做蒙皮
+    pub joints: &'a [Matrix4<f32>],
+    pub lights: &'a [WorldLight],
+    pub eye: Point3<f32>,
+    pub base_color: Color,
+    pub texture: Option<&'a Texture>,
+    pub normal_map: Option<&'a NormalMap>,
+}
+
+impl Shader for DiffuseShader<'_> {
+    type Vertex = usize;
+    type Varyings = SurfaceVaryings;
+
+    fn vertex(&self, &v: &usize) -> (Vector4<f32>, Self::Varyings) {
+        surface_vertex(self.mesh, &self.pipeline, self.joints, v)
+    }
+
+    fn fragment(
+        &self,
+        (position, normal, uv, tangent, color): &Self::Varyings,
+        derivatives: &Derivatives<Self::Varyings>,
+    ) -> Option<Color> {
+        let (dx, dy) = (&derivatives.dx.2, &derivatives.dy.2);
+        let normal = surface_normal(
+            normal,
+            uv,
+            tangent,
+            derivatives,
+            self.normal_map,
+            &self.pipeline,
+        )?;
+        let irradiance = if self.lights.is_empty() {
+            let to_eye = (self.eye - position).try_normalize(f32::EPSILON)?;
+            Vector3::repeat(normal.dot(&to_eye).max(0.0))
+        } else {
+            self.lights.iter().fold(Vector3::zeros(), |sum, light| {
+                let (l, e) = light.incident(position);
+                sum + e * normal.dot(&l).max(0.0)
+            })
+        };
+        let base_color = self.base_color.component_mul(color);
+        let base_color = match self.texture {
+            Some(texture) => base_color.component_mul(&texture.sample_grad(uv, dx, dy)),
+            None => base_color,
+        };
+        let rgb = base_color.xyz().component_mul(&irradiance);
+        Some(rgb.push(base_color.w))
+    }
+}
+
+/// 逐像素的 Blinn-Phong 着色，输入是网格顶点的索引，网格需要有顶点法向量
+/// 可以有任意数量的光源；顶点颜色和纹理的颜色乘到环境光和漫反射上，片元的透明度取它们的 alpha
+#[derive(Debug, Clone)]
+pub struct BlinnPhongShader<'a> {
+    pub mesh: &'a Mesh,
+    pub pipeline: Pipeline,
+    /// 蒙皮的骨骼矩阵，为空时不做蒙皮
+    pub joints: &'a [Matrix4<f32>],
+    pub lights: &'a [WorldLight],
+    pub eye: Point3<f32>,
+    pub material: BlinnPhong,
+    /// 环境光的颜色
+    pub ambient: Vector3<f32>,
+    /// 双面材质，法向量背对相机时翻转，背面和正面一样被照亮
+    pub double_sided: bool,
+    pub texture: Option<&'a Texture>,
+    pub normal_map: Option<&'a NormalMap>,
+}
+
+impl Shader for BlinnPhongShader<'_> {
+    type Vertex = usize;
+    type Varyings = SurfaceVaryings;
+
+    fn vertex(&self, &v: &usize) -> (Vector4<f32>, Self::Varyings) {
+        surface_vertex(self.mesh, &self.pipeline, self.joints, v)
+    }
+
+    fn fragment(
+        &self,
+        (position, normal, uv, tangent, color): &Self::Varyings,
+        derivatives: &Derivatives<Self::Varyings>,
+    ) -> Option<Color> {
+        let normal = surface_normal(
+            normal,
+            uv,
+            tangent,
+            derivatives,
+            self.normal_map,
+            &self.pipeline,
+        )?;
+        let normal = if self.double_sided && normal.dot(&(self.eye - position)) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        let mut color = *color;
+        if let Some(texture) = self.texture {
+            color.component_mul_assign(&texture.sample_grad(
+                uv,
+                &derivatives.dx.2,
+                &derivatives.dy.2,
+            ));
+        }
+        let mut material = self.material;
+        material.ambient = material.ambient.component_mul(&color.xyz());
+        material.diffuse = material.diffuse.component_mul(&color.xyz());
+        let alpha = color.w;
+        let rgb = material.shade(position, &normal, &self.eye, self.lights, &self.ambient);
+        Some(rgb.push(alpha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::light::Light;
+    use crate::mesh::primitive;
+    use crate::raster::{Cull, Framebuffer};
+    use crate::texture::Filter;
+
+    /// 正对相机的正方形，uv 原点在左下角（OBJ 的约定），纹理重复 uv_scale 次
+    fn quad(uv_scale: f32) -> Mesh {
+        let mut mesh = Mesh::new("quad");
+        mesh.positions = vec![
+            Point3::new(-1.0, -1.0, 0.0),
+            Point3::new(1.0, -1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(-1.0, 1.0, 0.0),
+        ];
+        mesh.normals = vec![Vector3::z(); 4];
+        mesh.uvs = mesh
+            .positions
+            .iter()
+            .map(|p| (p.xy().coords + Vector2::repeat(1.0)) * (uv_scale / 2.0))
+            .collect();
+        mesh.faces = vec![vec![0, 1, 2, 3]];
+        mesh
+    }
+
+    /// 正交相机，画面正好是 quad 的范围
+    fn front_camera() -> Camera {
+        Camera::orthographic(
+            Point3::new(0.0, 0.0, 10.0),
+            Point3::origin(),
+            Vector3::y(),
+            2.0,
+            0.1,
+            20.0,
+        )
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let weights = [0.5, 0.25, 0.25];
+        assert_eq!(f32::interpolate(&[0.0, 4.0, 8.0], weights), 3.0);
+        let values = [
+            (1.0, Vector2::new(0.0, 0.0)),
+            (2.0, Vector2::new(4.0, 0.0)),
+            (3.0, Vector2::new(0.0, 4.0)),
+        ];
+        assert_eq!(
+            <(f32, Vector2<f32>)>::interpolate(&values, weights),
+            (1.75, Vector2::new(1.0, 1.0))
+        );
+        let points = [
+            Point3::origin(),
+            Point3::new(4.0, 0.0, 0.0),
+            Point3::origin(),
+        ];
+        assert_eq!(
+            Point3::interpolate(&points, weights),
+            Point3::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_skinned_vertex() {
+        // 上半部分绑定到旋转的骨骼，下半部分绑定到不动的骨骼
+        let mut mesh = primitive::cube(1.0);
+        assert!(mesh.compute_tangents());
+        mesh.joints = mesh
+            .positions
+            .iter()
+            .map(|p| if p.y > 0.0 { [1, 0, 0, 0] } else { [0; 4] })
+            .collect();
+        mesh.weights = vec![Vector4::x(); mesh.vertex_count()];
+        let rotation = Matrix4::from_axis_angle(&Vector3::y_axis(), 0.5)
+            * Matrix4::new_translation(&Vector3::new(0.0, 0.5, 0.0));
+        let joints = [Matrix4::identity(), rotation];
+        let model = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0));
+        let pipeline = Pipeline::new(model, &front_camera(), 32, 32);
+        // 在顶点阶段蒙皮和先用 Mesh::skin 得到摆好姿势的网格结果相同
+        let posed = mesh.skin(&joints);
+        for v in 0..mesh.vertex_count() {
+            let (clip, (p, n, _, t, _)) = surface_vertex(&mesh, &pipeline, &joints, v);
+            let (expected_clip, (ep, en, _, et, _)) = surface_vertex(&posed, &pipeline, &[], v);
+            assert!((clip - expected_clip).norm() < 1e-5);
+            assert!((p - ep).norm() < 1e-5);
+            assert!((n - en).norm() < 1e-5);
+            assert!((t - et).norm() < 1e-5);
+        }
+        // 没有骨骼矩阵时不做蒙皮
+        let (_, (p, ..)) = surface_vertex(&mesh, &pipeline, &[], 0);
+        assert_eq!(p, model.transform_point(&mesh.positions[0]));
+    }
+
+    #[test]
+    fn test_diffuse_shader() {
+        let mesh = primitive::uv_sphere(1.0, 32, 16);
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        let camera = Camera::perspective(
+            Point3::new(0.0, 0.0, 4.0),
+            Point3::origin(),
+            Vector3::y(),
+            45f32.to_radians(),
+            0.1,
+            100.0,
+        );
+        let shader = DiffuseShader {
+            mesh: &mesh,
+            pipeline: Pipeline::new(Matrix4::identity(), &camera, 64, 64),
+            joints: &[],
+            lights: &[],
+            eye: camera.eye,
+            base_color: Vector4::new(1.0, 0.5, 0.0, 1.0),
+            texture: None,
+            normal_map: None,
+        };
+        let mut framebuffer = Framebuffer::new(64, 64);
+        assert!(framebuffer.draw_mesh(&shader, &mesh, Cull::Back) > 0);
+        // 正对相机的中心最亮，边缘变暗
+        let center = framebuffer.color.get_pixel(32, 32);
+        assert!(center[0] >= 250 && (center[1] as i32 - 128).abs() <= 3);
+        let edge = framebuffer.color.get_pixel(32, 21);
+        assert!(edge[3] == 255 && edge[0] < center[0]);
+        assert_eq!(framebuffer.color.get_pixel(2, 2), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_vertex_colors() {
+        // 左边两个顶点红色，右边两个顶点蓝色，中间是两者的混合
+        let mut mesh = quad(1.0);
+        let (red, blue) = (
+            Color::new(1.0, 0.0, 0.0, 1.0),
+            Color::new(0.0, 0.0, 1.0, 1.0),
+        );
+        mesh.colors = vec![red, blue, blue, red];
+        let camera = front_camera();
+        let shader = BlinnPhongShader {
+            mesh: &mesh,
+            pipeline: Pipeline::new(Matrix4::identity(), &camera, 32, 32),
+            joints: &[],
+            lights: &[],
+            eye: camera.eye,
+            material: BlinnPhong {
+                ambient: Vector3::repeat(1.0),
+                diffuse: Vector3::zeros(),
+                specular: Vector3::zeros(),
+                shininess: 1.0,
+            },
+            ambient: Vector3::repeat(1.0),
+            double_sided: false,
+            texture: None,
+            normal_map: None,
+        };
+        let mut framebuffer = Framebuffer::new(32, 32);
+        framebuffer.draw_mesh(&shader, &mesh, Cull::Back);
+        let left = framebuffer.color.get_pixel(0, 16);
+        assert!(left[0] > 240 && left[2] < 15, "{:?}", left);
+        let right = framebuffer.color.get_pixel(31, 16);
+        assert!(right[0] < 15 && right[2] > 240, "{:?}", right);
+        let middle = framebuffer.color.get_pixel(16, 16);
+        assert!(
+            (middle[0] as i32 - middle[2] as i32).abs() < 20,
+            "{:?}",
+            middle
+        );
+    }
+
+    #[test]
+    fn test_textured() {
+        let mesh = quad(1.0);
+        // 左上角是白色的 2x2 棋盘格
+        let image = image::RgbaImage::from_fn(2, 2, |x, y| {
+            let v = if (x + y) % 2 == 0 { 255 } else { 0 };
+            Rgba([v, v, v, 255])
+        });
+        let mut texture = Texture::new(&image::DynamicImage::ImageRgba8(image));
+        texture.filter = Filter::Nearest;
+        texture.flip_v = true;
+
+        let camera = front_camera();
+        let shader = DiffuseShader {
+            mesh: &mesh,
+            pipeline: Pipeline::new(Matrix4::identity(), &camera, 32, 32),
+            joints: &[],
+            lights: &[],
+            eye: camera.eye,
+            base_color: Vector4::repeat(1.0),
+            texture: Some(&texture),
+            normal_map: None,
+        };
+        let mut framebuffer = Framebuffer::new(32, 32);
+        assert!(framebuffer.draw_mesh(&shader, &mesh, Cull::Back) > 0);
+        assert!(framebuffer.color.get_pixel(8, 8)[0] > 200);
+        assert!(framebuffer.color.get_pixel(24, 8)[0] < 10);
+        assert!(framebuffer.color.get_pixel(8, 24)[0] < 10);
+        assert!(framebuffer.color.get_pixel(24, 24)[0] > 200);
+    }
+
+    #[test]
+    fn test_trilinear_minification() {
+        // 32 个像素覆盖 15 * 8 个纹素，每个像素大约 4 个纹素
+        let mesh = quad(15.0);
+        let image = image::RgbaImage::from_fn(8, 8, |x, y| {
+            let v = if (x + y) % 2 == 0 { 255 } else { 0 };
+            Rgba([v, v, v, 255])
+        });
+        let mut texture = Texture::new(&image::DynamicImage::ImageRgba8(image));
+        texture.generate_mipmaps(crate::texture::MipFilter::Box, false);
+        let camera = front_camera();
+        // 画面中红色分量的标准差
+        let mut deviation = |filter| {
+            texture.filter = filter;
+            let shader = DiffuseShader {
+                mesh: &mesh,
+                pipeline: Pipeline::new(Matrix4::identity(), &camera, 32, 32),
+                joints: &[],
+                lights: &[],
+                eye: camera.eye,
+                base_color: Vector4::repeat(1.0),
+                texture: Some(&texture),
+                normal_map: None,
+            };
+            let mut framebuffer = Framebuffer::new(32, 32);
+            framebuffer.draw_mesh(&shader, &mesh, Cull::Back);
+            let values: Vec<f32> = framebuffer.color.pixels().map(|p| p[0] as f32).collect();
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            let variance =
+                values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+            variance.sqrt()
+        };
+        // 双线性过滤只采样第 0 层，结果是混叠的条纹；三线性过滤得到均匀的灰色
+        let bilinear = deviation(Filter::Bilinear);
+        let trilinear = deviation(Filter::Trilinear);
+        assert!(bilinear > 30.0, "{}", bilinear);
+        assert!(trilinear < 5.0, "{}", trilinear);
+    }
+
+    #[test]
+    fn test_normal_mapped() {
+        let mut mesh = quad(1.0);
+        assert!(mesh.compute_tangents());
+        // 贴图中的法向量向切线方向（+x）倾斜 45 度
+        let image = image::RgbaImage::from_pixel(4, 4, Rgba([218, 128, 218, 255]));
+        let normal_map = NormalMap::new(
+            Texture::new(&image::DynamicImage::ImageRgba8(image)),
+            crate::normal_map::NormalSpace::TangentOpenGl,
+        );
+        // 光从 +x、+z 之间照过来
+        let lights = [WorldLight {
+            light: crate::light::Light::directional(Vector3::repeat(1.0), 1.0),
+            position: Point3::origin(),
+            direction: Vector3::new(-1.0, 0.0, -1.0).normalize(),
+        }];
+        let camera = front_camera();
+        let render = |normal_map| {
+            let shader = DiffuseShader {
+                mesh: &mesh,
+                pipeline: Pipeline::new(Matrix4::identity(), &camera, 32, 32),
+                joints: &[],
+                lights: &lights,
+                eye: camera.eye,
+                base_color: Vector4::repeat(1.0),
+                texture: None,
+                normal_map,
+            };
+            let mut framebuffer = Framebuffer::new(32, 32);
+            framebuffer.draw_mesh(&shader, &mesh, Cull::Back);
+            framebuffer.color.get_pixel(16, 16)[0]
+        };
+        // 平坦的表面和光的夹角是 45 度，贴图后正对着光
+        let flat = render(None);
+        assert!((flat as i32 - 180).abs() <= 2, "{}", flat);
+        assert!(render(Some(&normal_map)) >= 253);
+    }
+
+    #[test]
+    fn test_blinn_phong_shader() {
+        let mesh = primitive::uv_sphere(1.0, 48, 24);
+        let camera = Camera::perspective(
+            Point3::new(0.0, 0.0, 4.0),
+            Point3::origin(),
+            Vector3::y(),
+            45f32.to_radians(),
+            0.1,
+            100.0,
+        );
+        let material = BlinnPhong {
+            ambient: Vector3::new(0.5, 0.0, 0.0),
+            diffuse: Vector3::new(0.5, 0.0, 0.0),
+            specular: Vector3::repeat(1.0),
+            shininess: 64.0,
+        };
+        // 从相机方向照过来的平行光，加上从正上方向下照的窄聚光灯
+        let lights = [
+            WorldLight {
+                light: Light::directional(Vector3::repeat(1.0), 1.0),
+                position: Point3::origin(),
+                direction: -Vector3::z(),
+            },
+            WorldLight {
+                light: Light::spot(
+                    Vector3::repeat(1.0),
+                    9.0,
+                    20f32.to_radians(),
+                    25f32.to_radians(),
+                    None,
+                ),
+                position: Point3::new(0.0, 4.0, 0.0),
+                direction: -Vector3::y(),
+            },
+        ];
+        let render = |lights| {
+            let shader = BlinnPhongShader {
+                mesh: &mesh,
+                pipeline: Pipeline::new(Matrix4::identity(), &camera, 64, 64),
+                joints: &[],
+                lights,
+                eye: camera.eye,
+                material,
+                ambient: Vector3::repeat(0.2),
+                double_sided: false,
+                texture: None,
+                normal_map: None,
+            };
+            let mut framebuffer = Framebuffer::new(64, 64);
+            framebuffer.draw_mesh(&shader, &mesh, Cull::Back);
+            framebuffer.color
+        };
+
+        let image = render(&lights[..1]);
+        // 正对相机的中心有白色的高光，旁边只有红色的漫反射
+        let center = image.get_pixel(32, 32);
+        assert!(center[1] > 240 && center[2] > 240, "{:?}", center);
+        let side = image.get_pixel(40, 32);
+        assert!(side[0] > 100 && side[1] < 10, "{:?}", side);
+        // 只有环境光
+        let ambient = render(&[]);
+        assert_eq!(ambient.get_pixel(32, 32), &Rgba([26, 0, 0, 255]));
+
+        // 聚光灯只照亮球的上半部分
+        let both = render(&lights);
+        let top = both.get_pixel(32, 18);
+        assert!(top[0] > image.get_pixel(32, 18)[0] + 30, "{:?}", top);
+        assert_eq!(both.get_pixel(32, 42), image.get_pixel(32, 42));
+    }
+
+    #[test]
+    fn test_to_rgba() {
+        assert_eq!(
+            to_rgba(&Vector4::new(1.5, 0.5, -1.0, 1.0)),
+            Rgba([255, 128, 0, 255])
+        );
+    }
+}
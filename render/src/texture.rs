@@ -0,0 +1,615 @@
+//! 纹理采样
+
+use crate::shader::Color;
+use image::{DynamicImage, ImageResult};
+use nalgebra::Vector2;
+use std::path::Path;
+
+/// 纹理过滤方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// 取最近的纹素
+    Nearest,
+    /// 相邻 4 个纹素双线性插值
+    #[default]
+    Bilinear,
+    /// 在相邻的两个 mip 层级上双线性采样后按 LOD 插值，没有 mipmap 时和 Bilinear 相同
+    Trilinear,
+}
+
+/// 生成 mipmap 时使用的降采样滤波器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipFilter {
+    /// 盒式滤波，对应的纹素取平均
+    Box,
+    /// Kaiser 窗的 sinc 滤波，更锐利，混叠更少
+    Kaiser,
+}
+
+impl MipFilter {
+    /// 距离以目标纹素为单位时的滤波器半径
+    fn radius(self) -> f32 {
+        match self {
+            MipFilter::Box => 0.5,
+            MipFilter::Kaiser => 2.0,
+        }
+    }
+
+    fn weight(self, t: f32) -> f32 {
+        let t = t.abs();
+        match self {
+            MipFilter::Box if t < 0.5 => 1.0,
+            MipFilter::Box if t == 0.5 => 0.5,
+            MipFilter::Box => 0.0,
+            MipFilter::Kaiser => {
+                const ALPHA: f32 = 4.0;
+                let x = t / self.radius();
+                if x >= 1.0 {
+                    return 0.0;
+                }
+                let sinc = if t < 1e-6 {
+                    1.0
+                } else {
+                    (std::f32::consts::PI * t).sin() / (std::f32::consts::PI * t)
+                };
+                sinc * bessel_i0(ALPHA * (1.0 - x * x).sqrt()) / bessel_i0(ALPHA)
+            }
+        }
+    }
+}
+
+/// 第一类零阶修正贝塞尔函数，用于 Kaiser 窗
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x * 0.5;
+    for k in 1..20 {
+        term *= (half / k as f32) * (half / k as f32);
+        sum += term;
+    }
+    sum
+}
+
+/// sRGB 编码的分量转换到线性空间
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 线性空间的分量编码为 sRGB
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 纹理坐标超出 [0, 1] 时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Wrap {
+    /// 重复平铺
+    #[default]
+    Repeat,
+    /// 使用边缘的纹素
+    ClampToEdge,
+    /// 镜像平铺
+    MirroredRepeat,
+}
+
+impl Wrap {
+    /// 把纹素索引映射到 [0, size) 内
+    fn apply(self, i: i64, size: i64) -> usize {
+        let i = match self {
+            Wrap::Repeat => i.rem_euclid(size),
+            Wrap::ClampToEdge => i.clamp(0, size - 1),
+            Wrap::MirroredRepeat => {
+                let m = i.rem_euclid(2 * size);
+                if m < size {
+                    m
+                } else {
+                    2 * size - 1 - m
+                }
+            }
+        };
+        i as usize
+    }
+}
+
+/// 一个 mip 层级的纹素
+#[derive(Debug, Clone, PartialEq)]
+struct Level {
+    width: u32,
+    height: u32,
+    texels: Vec<Color>,
+}
+
+/// 纹理，纹素保存为 [0, 1] 的 RGBA，不做颜色空间转换
+/// 纹理坐标原点在图片左上角（和 gltf 一致），OBJ 的原点在左下角，需要设置 flip_v
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    /// 第 0 层是原图，之后每层的宽高减半，直到 1x1
+    levels: Vec<Level>,
+    pub filter: Filter,
+    pub wrap_u: Wrap,
+    pub wrap_v: Wrap,
+    /// 采样前把 v 翻转为 1 - v
+    pub flip_v: bool,
+    /// 各向异性过滤沿像素覆盖区域的长轴最多采样的次数，只用于 Trilinear，不大于 1 时关闭
+    pub max_anisotropy: f32,
+}
+
+impl Texture {
+    pub fn new(image: &DynamicImage) -> Self {
+        let image = image.to_rgba32f();
+        let level = Level {
+            width: image.width(),
+            height: image.height(),
+            texels: image.pixels().map(|p| Color::from(p.0)).collect(),
+        };
+        Texture {
+            levels: vec![level],
+            filter: Filter::default(),
+            wrap_u: Wrap::default(),
+            wrap_v: Wrap::default(),
+            flip_v: false,
+            max_anisotropy: 1.0,
+        }
+    }
+
+    /// 读取 PNG、TGA 等 image 支持的图片
+    pub fn load<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        Ok(Texture::new(&image::open(path)?))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.levels[0].height
+    }
+
+    /// mip 层级的数量，没有生成 mipmap 时为 1
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// 第 level 层的宽高
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        let level = &self.levels[level];
+        (level.width, level.height)
+    }
+
+    /// 生成完整的 mip 链，替换已有的 mipmap
+    ///
+    /// 每一层从上一层降采样得到，超出边缘的纹素按 wrap_u、wrap_v 取；
+    /// srgb 为 true 时 RGB 分量在线性空间中滤波（比如颜色贴图），alpha 始终按线性处理
+    pub fn generate_mipmaps(&mut self, filter: MipFilter, srgb: bool) {
+        self.levels.truncate(1);
+        if self.levels[0].texels.is_empty() {
+            return;
+        }
+        let decode = |c: &Color| {
+            if srgb {
+                Color::new(
+                    srgb_to_linear(c.x),
+                    srgb_to_linear(c.y),
+                    srgb_to_linear(c.z),
+                    c.w,
+                )
+            } else {
+                *c
+            }
+        };
+        let encode = |c: &Color| {
+            let c = c.map(|v| v.clamp(0.0, 1.0));
+            if srgb {
+                Color::new(
+                    linear_to_srgb(c.x),
+                    linear_to_srgb(c.y),
+                    linear_to_srgb(c.z),
+                    c.w,
+                )
+            } else {
+                c
+            }
+        };
+        let base = &self.levels[0];
+        // 在线性空间中逐层降采样，避免每层反复编码带来的误差
+        let mut current = Level {
+            width: base.width,
+            height: base.height,
+            texels: base.texels.iter().map(decode).collect(),
+        };
+        while current.width > 1 || current.height > 1 {
+            let (width, height) = ((current.width / 2).max(1), (current.height / 2).max(1));
+            let horizontal = downsample(&current, width, true, self.wrap_u, filter);
+            current = downsample(&horizontal, height, false, self.wrap_v, filter);
+            self.levels.push(Level {
+                width,
+                height,
+                texels: current.texels.iter().map(encode).collect(),
+            });
+        }
+    }
+
+    /// 按环绕方式取第 level 层的纹素，空纹理返回透明的黑色
+    pub fn texel(&self, level: usize, x: i64, y: i64) -> Color {
+        self.fetch(&self.levels[level], x, y)
+    }
+
+    fn fetch(&self, level: &Level, x: i64, y: i64) -> Color {
+        if level.texels.is_empty() {
+            return Color::zeros();
+        }
+        let x = self.wrap_u.apply(x, level.width as i64);
+        let y = self.wrap_v.apply(y, level.height as i64);
+        level.texels[y * level.width as usize + x]
+    }
+
+    /// 在纹理坐标 uv 处采样第 0 层
+    pub fn sample(&self, uv: &Vector2<f32>) -> Color {
+        self.sample_lod(uv, 0.0)
+    }
+
+    /// 在纹理坐标 uv 处按细节层级 lod 采样，只有 Trilinear 使用 lod，其他过滤方式总是采样第 0 层
+    pub fn sample_lod(&self, uv: &Vector2<f32>, lod: f32) -> Color {
+        let v = if self.flip_v { 1.0 - uv.y } else { uv.y };
+        let finite = |t: f32| if t.is_finite() { t } else { 0.0 };
+        let uv = Vector2::new(finite(uv.x), finite(v));
+        match self.filter {
+            Filter::Nearest => self.nearest(&self.levels[0], &uv),
+            Filter::Bilinear => self.bilinear(&self.levels[0], &uv),
+            Filter::Trilinear => {
+                let max = (self.levels.len() - 1) as f32;
+                let lod = if lod.is_nan() {
+                    0.0
+                } else {
+                    lod.clamp(0.0, max)
+                };
+                let level = lod.floor();
+                let t = lod - level;
+                let level = level as usize;
+                let color = self.bilinear(&self.levels[level], &uv);
+                if t > 0.0 {
+                    color.lerp(&self.bilinear(&self.levels[level + 1], &uv), t)
+                } else {
+                    color
+                }
+            }
+        }
+    }
+
+    /// 根据纹理坐标在屏幕上相邻像素之间的变化（偏导数）计算细节层级
+    /// 一个像素覆盖的纹素越多层级越大，覆盖不到一个纹素时为负
+    pub fn lod(&self, dx: &Vector2<f32>, dy: &Vector2<f32>) -> f32 {
+        let size = Vector2::new(self.width() as f32, self.height() as f32);
+        let rho = dx
+            .component_mul(&size)
+            .norm()
+            .max(dy.component_mul(&size).norm());
+        rho.log2()
+    }
+
+    /// 用纹理坐标的屏幕空间偏导数选择细节层级并采样
+    ///
+    /// 开启各向异性过滤时，像素在纹理上的覆盖区域近似为平行四边形，沿长轴均匀采样多次后取平均，
+    /// 细节层级按长轴除以采样次数选择，斜着看的表面不会因为短轴方向而整体变模糊
+    pub fn sample_grad(&self, uv: &Vector2<f32>, dx: &Vector2<f32>, dy: &Vector2<f32>) -> Color {
+        if self.filter != Filter::Trilinear {
+            return self.sample(uv);
+        }
+        let size = Vector2::new(self.width() as f32, self.height() as f32);
+        let (x, y) = (
+            dx.component_mul(&size).norm(),
+            dy.component_mul(&size).norm(),
+        );
+        let (major, minor, axis) = if x >= y { (x, y, dx) } else { (y, x, dy) };
+        let taps = self.anisotropy(major, minor);
+        if taps <= 1 {
+            return self.sample_lod(uv, major.log2());
+        }
+        let lod = (major / taps as f32).log2();
+        let sum = (0..taps).fold(Color::zeros(), |sum, i| {
+            let offset = (i as f32 + 0.5) / taps as f32 - 0.5;
+            sum + self.sample_lod(&(uv + axis * offset), lod)
+        });
+        sum / taps as f32
+    }
+
+    /// 长轴和短轴长度（以纹素为单位）的比值决定的采样次数，不超过 max_anisotropy
+    fn anisotropy(&self, major: f32, minor: f32) -> usize {
+        if self.max_anisotropy <= 1.0 || !major.is_finite() {
+            return 1;
+        }
+        let ratio = major / minor.max(1e-6);
+        ratio.min(self.max_anisotropy).ceil().max(1.0) as usize
+    }
+
+    fn nearest(&self, level: &Level, uv: &Vector2<f32>) -> Color {
+        let x = uv.x * level.width as f32;
+        let y = uv.y * level.height as f32;
+        self.fetch(level, x.floor() as i64, y.floor() as i64)
+    }
+
+    fn bilinear(&self, level: &Level, uv: &Vector2<f32>) -> Color {
+        // 纹素空间的坐标，纹素中心位于 (i + 0.5, j + 0.5)
+        let x = uv.x * level.width as f32 - 0.5;
+        let y = uv.y * level.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self
+            .fetch(level, x0, y0)
+            .lerp(&self.fetch(level, x0 + 1, y0), tx);
+        let bottom = self
+            .fetch(level, x0, y0 + 1)
+            .lerp(&self.fetch(level, x0 + 1, y0 + 1), tx);
+        top.lerp(&bottom, ty)
+    }
+}
+
+/// 沿一个方向把 level 降采样到 size 个纹素，horizontal 为 true 时沿 x 方向
+fn downsample(level: &Level, size: u32, horizontal: bool, wrap: Wrap, filter: MipFilter) -> Level {
+    let (source_size, other) = if horizontal {
+        (level.width, level.height)
+    } else {
+        (level.height, level.width)
+    };
+    let scale = source_size as f32 / size as f32;
+    // 每个目标纹素对应的源纹素和权重，所有行（列）共用
+    let taps: Vec<Vec<(usize, f32)>> = (0..size)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let reach = filter.radius() * scale;
+            let first = (center - reach).floor() as i64;
+            let last = (center + reach).ceil() as i64;
+            let mut taps: Vec<(usize, f32)> = (first..=last)
+                .map(|j| {
+                    let t = (j as f32 + 0.5 - center) / scale;
+                    (wrap.apply(j, source_size as i64), filter.weight(t))
+                })
+                .filter(|&(_, w)| w != 0.0)
+                .collect();
+            let sum: f32 = taps.iter().map(|(_, w)| w).sum();
+            for tap in taps.iter_mut() {
+                tap.1 /= sum;
+            }
+            taps
+        })
+        .collect();
+    let (width, height) = if horizontal {
+        (size, other)
+    } else {
+        (other, size)
+    };
+    let mut texels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let (i, row) = if horizontal { (x, y) } else { (y, x) };
+            let color = taps[i].iter().fold(Color::zeros(), |sum, &(j, w)| {
+                let index = if horizontal {
+                    row * level.width as usize + j
+                } else {
+                    j * level.width as usize + row
+                };
+                sum + level.texels[index] * w
+            });
+            texels.push(color);
+        }
+    }
+    Level {
+        width,
+        height,
+        texels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+
+    /// 2x2 的棋盘格，左上角是白色
+    fn checker() -> Texture {
+        let image = RgbaImage::from_fn(2, 2, |x, y| {
+            if (x + y) % 2 == 0 {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        });
+        Texture::new(&DynamicImage::ImageRgba8(image))
+    }
+
+    fn close(a: Color, b: Color) -> bool {
+        (a - b).norm() < 1e-5
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut texture = checker();
+        texture.filter = Filter::Nearest;
+        assert_eq!(texture.sample(&Vector2::new(0.25, 0.25)), WHITE);
+        assert_eq!(texture.sample(&Vector2::new(0.75, 0.25)), BLACK);
+        // 翻转后 v = 0 在图片的最下面一行
+        texture.flip_v = true;
+        assert_eq!(texture.sample(&Vector2::new(0.25, 0.1)), BLACK);
+        assert_eq!(texture.sample(&Vector2::new(0.25, f32::NAN)), WHITE);
+    }
+
+    #[test]
+    fn test_bilinear() {
+        let mut texture = checker();
+        // 纹素中心取到纹素本身，4 个纹素中间取平均
+        assert!(close(texture.sample(&Vector2::new(0.25, 0.25)), WHITE));
+        let gray = Color::new(0.5, 0.5, 0.5, 1.0);
+        assert!(close(texture.sample(&Vector2::new(0.5, 0.5)), gray));
+        // 重复平铺时边缘和另一侧混合
+        assert!(close(texture.sample(&Vector2::new(0.0, 0.25)), gray));
+        texture.wrap_u = Wrap::ClampToEdge;
+        assert!(close(texture.sample(&Vector2::new(0.0, 0.25)), WHITE));
+        let quarter = texture.sample(&Vector2::new(0.375, 0.25));
+        assert!(close(quarter, Color::new(0.75, 0.75, 0.75, 1.0)));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let mut texture = checker();
+        texture.filter = Filter::Nearest;
+        let white = [0.25, 1.25, -0.75];
+        for u in white {
+            assert_eq!(texture.sample(&Vector2::new(u, 0.25)), WHITE, "{}", u);
+        }
+        texture.wrap_u = Wrap::ClampToEdge;
+        assert_eq!(texture.sample(&Vector2::new(5.0, 0.25)), BLACK);
+        assert_eq!(texture.sample(&Vector2::new(-5.0, 0.25)), WHITE);
+        // 镜像：[1, 2) 是 [0, 1) 反过来
+        texture.wrap_u = Wrap::MirroredRepeat;
+        assert_eq!(texture.sample(&Vector2::new(1.25, 0.25)), BLACK);
+        assert_eq!(texture.sample(&Vector2::new(1.75, 0.25)), WHITE);
+        assert_eq!(texture.sample(&Vector2::new(-0.25, 0.25)), WHITE);
+        assert_eq!(texture.sample(&Vector2::new(2.25, 0.25)), WHITE);
+        assert_eq!(Wrap::MirroredRepeat.apply(-1, 4), 0);
+        assert_eq!(Wrap::MirroredRepeat.apply(4, 4), 3);
+        assert_eq!(Wrap::Repeat.apply(-1, 4), 3);
+    }
+
+    #[test]
+    fn test_mip_chain() {
+        let image = RgbaImage::from_pixel(5, 3, Rgba([255, 0, 0, 255]));
+        let mut texture = Texture::new(&DynamicImage::ImageRgba8(image));
+        for filter in [MipFilter::Box, MipFilter::Kaiser] {
+            texture.generate_mipmaps(filter, true);
+            let sizes: Vec<(u32, u32)> = (0..texture.level_count())
+                .map(|l| texture.level_size(l))
+                .collect();
+            assert_eq!(sizes, vec![(5, 3), (2, 1), (1, 1)]);
+            // 纯色的图片每层都保持原来的颜色
+            for level in 1..texture.level_count() {
+                let color = texture.texel(level, 0, 0);
+                assert!(close(color, Color::new(1.0, 0.0, 0.0, 1.0)), "{:?}", color);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mip_filters() {
+        let mut texture = checker();
+        // 线性空间中平均，黑白各半是线性的 0.5，编码后更亮
+        texture.generate_mipmaps(MipFilter::Box, false);
+        assert_eq!(texture.level_count(), 2);
+        assert!(close(
+            texture.texel(1, 0, 0),
+            Color::new(0.5, 0.5, 0.5, 1.0)
+        ));
+        texture.generate_mipmaps(MipFilter::Box, true);
+        let srgb = linear_to_srgb(0.5);
+        assert!((srgb - 0.7354).abs() < 1e-3);
+        assert!(close(
+            texture.texel(1, 0, 0),
+            Color::new(srgb, srgb, srgb, 1.0)
+        ));
+        assert!((srgb_to_linear(srgb) - 0.5).abs() < 1e-6);
+
+        // 8x8 的细棋盘格缩小后是均匀的灰色
+        let image = RgbaImage::from_fn(8, 8, |x, y| {
+            let v = if (x + y) % 2 == 0 { 255 } else { 0 };
+            Rgba([v, v, v, 255])
+        });
+        let mut texture = Texture::new(&DynamicImage::ImageRgba8(image));
+        texture.generate_mipmaps(MipFilter::Kaiser, false);
+        assert_eq!(texture.level_count(), 4);
+        for level in 1..4 {
+            let (w, h) = texture.level_size(level);
+            for y in 0..h as i64 {
+                for x in 0..w as i64 {
+                    let c = texture.texel(level, x, y);
+                    assert!((c.x - 0.5).abs() < 0.02, "{} {:?}", level, c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_trilinear() {
+        let mut texture = checker();
+        texture.filter = Filter::Trilinear;
+        let uv = Vector2::new(0.25, 0.25);
+        // 没有 mipmap 时和双线性一样
+        assert!(close(texture.sample_lod(&uv, 1.0), WHITE));
+        texture.generate_mipmaps(MipFilter::Box, false);
+        let gray = Color::new(0.5, 0.5, 0.5, 1.0);
+        assert!(close(texture.sample_lod(&uv, 1.0), gray));
+        assert!(close(texture.sample_lod(&uv, 5.0), gray));
+        assert!(close(texture.sample_lod(&uv, -1.0), WHITE));
+        let half = texture.sample_lod(&uv, 0.5);
+        assert!(close(half, Color::new(0.75, 0.75, 0.75, 1.0)));
+
+        // 每个像素跨过一个纹素时是第 0 层，跨过两个纹素时是第 1 层
+        let texel = Vector2::new(0.5, 0.0);
+        assert!(texture.lod(&texel, &Vector2::zeros()).abs() < 1e-6);
+        assert!((texture.lod(&(texel * 2.0), &(texel * 0.5)) - 1.0).abs() < 1e-6);
+        assert!(close(
+            texture.sample_grad(&uv, &(texel * 2.0), &Vector2::zeros()),
+            gray
+        ));
+        // 其他过滤方式忽略 mipmap
+        texture.filter = Filter::Bilinear;
+        assert!(close(
+            texture.sample_grad(&uv, &(texel * 2.0), &Vector2::zeros()),
+            WHITE
+        ));
+    }
+
+    #[test]
+    fn test_anisotropic() {
+        // 竖条纹，x 方向每个纹素黑白交替，y 方向不变
+        let image = RgbaImage::from_fn(8, 8, |x, _| {
+            let v = if x % 2 == 0 { 255 } else { 0 };
+            Rgba([v, v, v, 255])
+        });
+        let mut texture = Texture::new(&DynamicImage::ImageRgba8(image));
+        texture.generate_mipmaps(MipFilter::Box, false);
+        texture.filter = Filter::Trilinear;
+        // 每个像素在 x 方向跨 1 个纹素，y 方向跨 8 个纹素
+        let uv = Vector2::new(0.5 / 8.0, 0.5);
+        let (dx, dy) = (Vector2::new(1.0 / 8.0, 0.0), Vector2::new(0.0, 1.0));
+        // 按长轴选择层级时条纹被平均成灰色
+        let blurred = texture.sample_grad(&uv, &dx, &dy);
+        assert!((blurred.x - 0.5).abs() < 0.01, "{:?}", blurred);
+        texture.max_anisotropy = 8.0;
+        assert!(close(texture.sample_grad(&uv, &dx, &dy), WHITE));
+        // 采样次数受 max_anisotropy 限制，各向同性时和三线性一样
+        texture.max_anisotropy = 2.0;
+        assert_eq!(texture.anisotropy(8.0, 1.0), 2);
+        assert_eq!(texture.anisotropy(3.0, 2.0), 2);
+        assert_eq!(texture.anisotropy(1.0, 1.0), 1);
+        assert_eq!(texture.anisotropy(1.0, 0.0), 2);
+        let isotropic = Vector2::new(0.0, 1.0 / 8.0);
+        assert_eq!(
+            texture.sample_grad(&uv, &dx, &isotropic),
+            texture.sample_lod(&uv, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let resource = format!("{}/../resource", env!("CARGO_MANIFEST_DIR"));
+        let png = Texture::load(format!("{}/gltf/checker.png", resource)).unwrap();
+        assert!(png.width() > 0 && png.height() > 0);
+        let tga = Texture::load(format!("{}/study/img2.tga", resource)).unwrap();
+        assert!(tga.width() > 0 && tga.height() > 0);
+        assert!(Texture::load(format!("{}/missing.png", resource)).is_err());
+
+        let empty = Texture::new(&DynamicImage::new_rgba8(0, 0));
+        assert_eq!(empty.sample(&Vector2::new(0.5, 0.5)), Color::zeros());
+    }
+}